@@ -0,0 +1 @@
+DROP TABLE playground_code;
@@ -0,0 +1,6 @@
+CREATE TABLE playground_code (
+    id         BIGSERIAL PRIMARY KEY,
+    gist       TEXT NOT NULL UNIQUE,
+    code       JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
@@ -0,0 +1,8 @@
+DROP TABLE playground_revisions;
+DROP INDEX playground_user_id;
+ALTER TABLE playground
+DROP COLUMN title,
+DROP COLUMN revision,
+DROP COLUMN parent_id,
+DROP COLUMN created_at,
+DROP COLUMN updated_at;
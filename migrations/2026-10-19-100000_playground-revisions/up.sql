@@ -0,0 +1,17 @@
+ALTER TABLE playground
+ADD COLUMN title      TEXT DEFAULT NULL,
+ADD COLUMN revision   BIGINT NOT NULL DEFAULT 1,
+ADD COLUMN parent_id  BIGINT DEFAULT NULL REFERENCES playground (id) ON DELETE SET NULL,
+ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT now(),
+ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT now();
+
+CREATE INDEX playground_user_id ON playground (user_id);
+
+CREATE TABLE playground_revisions (
+    id            BIGSERIAL PRIMARY KEY,
+    playground_id BIGINT NOT NULL REFERENCES playground (id) ON DELETE CASCADE,
+    revision      BIGINT NOT NULL,
+    code          JSONB NOT NULL,
+    created_at    TIMESTAMP NOT NULL DEFAULT now(),
+    UNIQUE (playground_id, revision)
+);
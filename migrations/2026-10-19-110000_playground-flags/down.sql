@@ -0,0 +1,2 @@
+DROP INDEX playground_flagged;
+DROP TABLE playground_flags;
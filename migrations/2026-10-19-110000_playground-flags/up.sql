@@ -0,0 +1,9 @@
+CREATE TABLE playground_flags (
+    id            BIGSERIAL PRIMARY KEY,
+    playground_id BIGINT NOT NULL REFERENCES playground (id) ON DELETE CASCADE,
+    reason        TEXT DEFAULT NULL,
+    created_at    TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX playground_flags_playground_id ON playground_flags (playground_id);
+CREATE INDEX playground_flagged ON playground (flagged) WHERE flagged;
@@ -0,0 +1,2 @@
+ALTER TABLE playground
+DROP COLUMN key_version;
@@ -0,0 +1,2 @@
+ALTER TABLE playground
+ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+DROP TABLE search_query_stats;
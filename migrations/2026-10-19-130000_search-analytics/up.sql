@@ -0,0 +1,17 @@
+CREATE TABLE search_query_stats (
+    id                  BIGSERIAL PRIMARY KEY,
+    day                 DATE NOT NULL DEFAULT CURRENT_DATE,
+    query               TEXT NOT NULL,
+    locales             TEXT NOT NULL,
+    sort                TEXT NOT NULL,
+    searches            BIGINT NOT NULL DEFAULT 0,
+    paginated           BIGINT NOT NULL DEFAULT 0,
+    zero_results        BIGINT NOT NULL DEFAULT 0,
+    suggestions_shown   BIGINT NOT NULL DEFAULT 0,
+    suggestions_clicked BIGINT NOT NULL DEFAULT 0,
+    total_hits          BIGINT NOT NULL DEFAULT 0,
+    took_ms             BIGINT NOT NULL DEFAULT 0,
+    UNIQUE (day, query, locales, sort)
+);
+
+CREATE INDEX search_query_stats_day ON search_query_stats (day);
@@ -0,0 +1,2 @@
+DROP TABLE bcd_sync_runs;
+DROP TYPE bcd_sync_status;
@@ -0,0 +1,15 @@
+CREATE TYPE bcd_sync_status AS ENUM ('running', 'succeeded', 'failed');
+
+CREATE TABLE bcd_sync_runs (
+    id          BIGSERIAL PRIMARY KEY,
+    started_at  TIMESTAMP NOT NULL DEFAULT now(),
+    finished_at TIMESTAMP,
+    status      bcd_sync_status NOT NULL DEFAULT 'running',
+    inserted    BIGINT NOT NULL DEFAULT 0,
+    updated     BIGINT NOT NULL DEFAULT 0,
+    removed     BIGINT NOT NULL DEFAULT 0,
+    report      JSONB NOT NULL DEFAULT '{}',
+    errors      TEXT[] NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX bcd_sync_runs_started_at ON bcd_sync_runs (started_at);
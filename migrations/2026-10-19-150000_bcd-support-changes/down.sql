@@ -0,0 +1,64 @@
+DROP MATERIALIZED VIEW bcd_updates_view;
+
+DELETE FROM bcd_updates WHERE event_type::TEXT NOT IN (
+    'added_stable', 'added_preview', 'added_subfeatures', 'added_nonnull', 'removed_stable', 'unknown'
+);
+
+ALTER TABLE bcd_updates
+    DROP CONSTRAINT bcd_updates_browser_release_feature_event_type_key,
+    DROP COLUMN statement_before,
+    DROP COLUMN statement_after;
+
+-- Keep one event per feature and release again.
+DELETE FROM bcd_updates a USING bcd_updates b
+WHERE a.browser_release = b.browser_release AND a.feature = b.feature AND a.id > b.id;
+
+ALTER TABLE bcd_updates
+    ADD CONSTRAINT bcd_updates_browser_release_feature_key UNIQUE (browser_release, feature);
+
+ALTER TYPE bcd_event_type RENAME TO bcd_event_type_old;
+CREATE TYPE bcd_event_type AS ENUM 
+(
+    'added_stable', 
+    'added_preview', 
+    'added_subfeatures',
+    'added_nonnull',
+    'removed_stable',
+    'unknown'
+);
+ALTER TABLE bcd_updates
+    ALTER COLUMN event_type TYPE bcd_event_type USING event_type::TEXT::bcd_event_type;
+DROP TYPE bcd_event_type_old;
+
+CREATE MATERIALIZED VIEW bcd_updates_view AS
+SELECT 
+    b.display_name as browser_name,
+    b.name as browser,
+    SPLIT_PART(f.path, '.', 1) as category,
+    f.deprecated,
+    up.description,
+    br.engine,
+    br.engine_version,
+    up.event_type,
+    f.experimental,
+    f.mdn_url,
+    f.short_title,
+    f.path,
+    br.release_date,
+    br.release_id,
+    br.release_notes,
+    f.source_file,
+    f.spec_url,
+    f.standard_track,
+    br.status,
+    up.engines
+FROM bcd_updates up
+    left join browser_releases br on up.browser_release = br.id
+    left join bcd_features f on f.id = up.feature
+    left join browsers b on br.browser = b.name;
+
+CREATE UNIQUE INDEX buv_unique_idx ON bcd_updates_view ((browser::TEXT), (event_type::bcd_event_type), (release_id::TEXT), (path::TEXT));
+CREATE INDEX buv_release_date_idx ON bcd_updates_view ((release_date::DATE));
+CREATE INDEX buv_browser_name_idx ON bcd_updates_view ((browser::TEXT));
+CREATE INDEX buv_category_idx ON bcd_updates_view ((category::TEXT));
+CREATE INDEX buv_bcd_updates_lower_case_url_idx ON bcd_updates_view ((lower(mdn_url)));
@@ -0,0 +1,47 @@
+CREATE TYPE baseline_status AS ENUM ('limited', 'newly', 'widely');
+
+ALTER TABLE bcd_features
+    ADD COLUMN baseline_status     baseline_status NOT NULL DEFAULT 'limited',
+    ADD COLUMN baseline_date       DATE,
+    ADD COLUMN baseline_changed_at TIMESTAMP;
+
+DROP MATERIALIZED VIEW bcd_updates_view;
+
+CREATE MATERIALIZED VIEW bcd_updates_view AS
+SELECT 
+    b.display_name as browser_name,
+    b.name as browser,
+    SPLIT_PART(f.path, '.', 1) as category,
+    f.deprecated,
+    up.description,
+    br.engine,
+    br.engine_version,
+    up.event_type,
+    f.experimental,
+    f.mdn_url,
+    f.short_title,
+    f.path,
+    br.release_date,
+    br.release_id,
+    br.release_notes,
+    f.source_file,
+    f.spec_url,
+    f.standard_track,
+    br.status,
+    up.engines,
+    up.statement_before,
+    up.statement_after,
+    f.baseline_status,
+    f.baseline_date
+FROM bcd_updates up
+    left join browser_releases br on up.browser_release = br.id
+    left join bcd_features f on f.id = up.feature
+    left join browsers b on br.browser = b.name;
+
+CREATE UNIQUE INDEX buv_unique_idx ON bcd_updates_view ((browser::TEXT), (event_type::bcd_event_type), (release_id::TEXT), (path::TEXT));
+CREATE INDEX buv_release_date_idx ON bcd_updates_view ((release_date::DATE));
+CREATE INDEX buv_browser_name_idx ON bcd_updates_view ((browser::TEXT));
+CREATE INDEX buv_category_idx ON bcd_updates_view ((category::TEXT));
+CREATE INDEX buv_bcd_updates_lower_case_url_idx ON bcd_updates_view ((lower(mdn_url)));
+CREATE INDEX buv_event_type_idx ON bcd_updates_view ((event_type::bcd_event_type));
+CREATE INDEX buv_baseline_status_idx ON bcd_updates_view ((baseline_status::baseline_status));
@@ -0,0 +1,2 @@
+ALTER TABLE settings DROP COLUMN bcd_digest;
+DROP TABLE bcd_notifications;
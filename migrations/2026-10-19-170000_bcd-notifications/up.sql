@@ -0,0 +1,15 @@
+CREATE TABLE bcd_notifications (
+    id            BIGSERIAL PRIMARY KEY,
+    user_id       BIGINT NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    bcd_update_id BIGINT NOT NULL REFERENCES bcd_updates (id) ON DELETE CASCADE,
+    document_id   BIGINT NOT NULL REFERENCES documents (id) ON DELETE CASCADE,
+    created_at    TIMESTAMP NOT NULL DEFAULT now(),
+    read_at       TIMESTAMP,
+    mailed_at     TIMESTAMP,
+    UNIQUE (user_id, bcd_update_id, document_id)
+);
+
+CREATE INDEX bcd_notifications_user_created_at ON bcd_notifications (user_id, created_at);
+CREATE INDEX bcd_notifications_unread ON bcd_notifications (user_id) WHERE read_at IS NULL;
+
+ALTER TABLE settings ADD COLUMN bcd_digest BOOLEAN NOT NULL DEFAULT FALSE;
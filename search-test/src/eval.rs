@@ -0,0 +1,174 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Error};
+use elasticsearch::{http::transport::Transport, Elasticsearch};
+use rumba::{
+    api::{search::search_urls, search_ranking},
+    settings::{RankingProfile, SETTINGS},
+};
+use serde::Serialize;
+use tokio::fs;
+
+use crate::{
+    metrics::{ndcg, recall, reciprocal_rank},
+    queries,
+};
+
+#[derive(Serialize, Default, Clone, Copy)]
+pub struct Scores {
+    pub ndcg: f64,
+    pub mrr: f64,
+    pub recall: f64,
+}
+
+#[derive(Serialize)]
+pub struct QueryReport {
+    pub query: String,
+    pub locale: String,
+    pub baseline: Scores,
+    pub candidate: Scores,
+    pub baseline_urls: Vec<String>,
+    pub candidate_urls: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Report {
+    pub baseline: String,
+    pub candidate: String,
+    pub mean_baseline: Scores,
+    pub mean_candidate: Scores,
+    pub queries: Vec<QueryReport>,
+}
+
+/// A profile name from the settings, or the path to a YAML file with a profile.
+fn load_profile(profile: &str) -> Result<RankingProfile, Error> {
+    if Path::new(profile).is_file() {
+        Ok(serde_yaml::from_reader(std::fs::File::open(profile)?)?)
+    } else {
+        search_ranking::profile(profile)
+            .ok_or_else(|| anyhow!("unknown ranking profile: {profile}"))
+    }
+}
+
+fn scores(urls: &[String], expected: &[String]) -> Scores {
+    Scores {
+        ndcg: ndcg(urls, expected),
+        mrr: reciprocal_rank(urls, expected),
+        recall: recall(urls, expected),
+    }
+}
+
+fn mean(scores: impl Iterator<Item = Scores>) -> Scores {
+    let (sum, n) = scores.fold((Scores::default(), 0), |(sum, n), x| {
+        (
+            Scores {
+                ndcg: sum.ndcg + x.ndcg,
+                mrr: sum.mrr + x.mrr,
+                recall: sum.recall + x.recall,
+            },
+            n + 1,
+        )
+    });
+    if n == 0 {
+        return sum;
+    }
+    Scores {
+        ndcg: sum.ndcg / n as f64,
+        mrr: sum.mrr / n as f64,
+        recall: sum.recall / n as f64,
+    }
+}
+
+pub async fn eval_all(
+    path: Option<impl AsRef<Path>>,
+    out: Option<impl AsRef<Path>>,
+    baseline: String,
+    candidate: String,
+) -> Result<(), Error> {
+    let client = Elasticsearch::new(Transport::single_node(&SETTINGS.search.url)?);
+    let baseline_profile = load_profile(&baseline)?;
+    let candidate_profile = load_profile(&candidate)?;
+
+    let mut queries_report = vec![];
+    for query in queries::read(path)? {
+        let query_string = form_urlencoded::Serializer::new(String::new())
+            .append_pair("q", &query.query)
+            .append_pair("locale", &query.locale)
+            .finish();
+        println!("processing: {} ({})", query.query, query.locale);
+        let baseline_urls = search_urls(&client, &query_string, &baseline_profile).await?;
+        let candidate_urls = search_urls(&client, &query_string, &candidate_profile).await?;
+        queries_report.push(QueryReport {
+            baseline: scores(&baseline_urls, &query.expected),
+            candidate: scores(&candidate_urls, &query.expected),
+            query: query.query,
+            locale: query.locale,
+            baseline_urls,
+            candidate_urls,
+        });
+    }
+
+    let report = Report {
+        mean_baseline: mean(queries_report.iter().map(|x| x.baseline)),
+        mean_candidate: mean(queries_report.iter().map(|x| x.candidate)),
+        baseline,
+        candidate,
+        queries: queries_report,
+    };
+    println!("{}", to_md(&report));
+    if let Some(out) = out {
+        println!("writing: {}", out.as_ref().display());
+        fs::write(out, serde_json::to_vec_pretty(&report)?).await?;
+    }
+    Ok(())
+}
+
+fn diff(baseline: f64, candidate: f64) -> String {
+    let delta = candidate - baseline;
+    if delta.abs() < 0.0005 {
+        "=".to_string()
+    } else {
+        format!("{delta:+.3}")
+    }
+}
+
+fn row(name: &str, baseline: &Scores, candidate: &Scores) -> String {
+    format!(
+        "| {name} | {:.3} | {:.3} | {} | {:.3} | {:.3} | {} | {:.3} | {:.3} | {} |",
+        baseline.ndcg,
+        candidate.ndcg,
+        diff(baseline.ndcg, candidate.ndcg),
+        baseline.mrr,
+        candidate.mrr,
+        diff(baseline.mrr, candidate.mrr),
+        baseline.recall,
+        candidate.recall,
+        diff(baseline.recall, candidate.recall),
+    )
+}
+
+/// A markdown table with the metrics of both profiles and their difference per query.
+fn to_md(report: &Report) -> String {
+    let mut lines = vec![
+        format!(
+            "baseline: {}, candidate: {}",
+            report.baseline, report.candidate
+        ),
+        String::new(),
+        "| query | nDCG@10 | | Δ | MRR | | Δ | recall@10 | | Δ |".to_string(),
+        "|---|---|---|---|---|---|---|---|---|---|".to_string(),
+    ];
+    lines.extend(report.queries.iter().map(|query| {
+        row(
+            &format!("{} ({})", query.query, query.locale),
+            &query.baseline,
+            &query.candidate,
+        )
+    }));
+    lines.push(row(
+        "**mean**",
+        &report.mean_baseline,
+        &report.mean_candidate,
+    ));
+    lines.join("\n")
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use anyhow::Error;
+use clap::{Parser, Subcommand};
+use rumba::logging::init_logging;
+
+use crate::eval::eval_all;
+
+mod eval;
+mod metrics;
+mod queries;
+
+#[derive(Parser)]
+#[command(name = "search-test")]
+#[command(author = "MDN Engineering Team <mdn-dev@mozilla.com>")]
+#[command(version = "1.0")]
+#[command(about = "Search relevance evaluation", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    Eval {
+        /// Path to YAML file with queries and their expected result urls.
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+        /// Path to write the full report as JSON.
+        #[arg(short, long)]
+        out: Option<PathBuf>,
+        /// Ranking profile name from the settings, or path to a YAML file with a profile.
+        #[arg(short, long, default_value = "default")]
+        baseline: String,
+        /// Ranking profile to compare against the baseline, like `--baseline`.
+        #[arg(short, long)]
+        candidate: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var("RUST_LOG", "info");
+    }
+
+    init_logging(false);
+
+    let cli = Cli::parse();
+    match cli.command {
+        Commands::Eval {
+            path,
+            out,
+            baseline,
+            candidate,
+        } => {
+            eval_all(path, out, baseline, candidate).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+/// Number of results the metrics look at, the first page of the search.
+pub const K: usize = 10;
+
+/// Normalized discounted cumulative gain at `K`, with binary relevance.
+pub fn ndcg(results: &[String], expected: &[String]) -> f64 {
+    let dcg: f64 = results
+        .iter()
+        .take(K)
+        .enumerate()
+        .filter(|(_, url)| expected.contains(url))
+        .map(|(i, _)| discount(i))
+        .sum();
+    let ideal: f64 = (0..expected.len().min(K)).map(discount).sum();
+    if ideal == 0.0 {
+        0.0
+    } else {
+        dcg / ideal
+    }
+}
+
+fn discount(i: usize) -> f64 {
+    1.0 / (i as f64 + 2.0).log2()
+}
+
+/// Reciprocal rank of the first expected result, 0 if there is none in the first `K`.
+pub fn reciprocal_rank(results: &[String], expected: &[String]) -> f64 {
+    results
+        .iter()
+        .take(K)
+        .position(|url| expected.contains(url))
+        .map(|i| 1.0 / (i as f64 + 1.0))
+        .unwrap_or(0.0)
+}
+
+/// Share of the expected results found in the first `K`.
+pub fn recall(results: &[String], expected: &[String]) -> f64 {
+    if expected.is_empty() {
+        return 0.0;
+    }
+    let found = expected
+        .iter()
+        .filter(|url| results.iter().take(K).any(|x| x == *url))
+        .count();
+    found as f64 / expected.len() as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn urls(urls: &[&str]) -> Vec<String> {
+        urls.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn test_metrics() {
+        let expected = urls(&["a", "b"]);
+        let perfect = urls(&["a", "b", "c"]);
+        assert_eq!(ndcg(&perfect, &expected), 1.0);
+        assert_eq!(reciprocal_rank(&perfect, &expected), 1.0);
+        assert_eq!(recall(&perfect, &expected), 1.0);
+
+        let partial = urls(&["c", "b"]);
+        assert!((ndcg(&partial, &expected) - 0.6309 / 1.6309).abs() < 0.001);
+        assert_eq!(reciprocal_rank(&partial, &expected), 0.5);
+        assert_eq!(recall(&partial, &expected), 0.5);
+
+        let none = urls(&["c", "d"]);
+        assert_eq!(ndcg(&none, &expected), 0.0);
+        assert_eq!(reciprocal_rank(&none, &expected), 0.0);
+        assert_eq!(recall(&none, &expected), 0.0);
+    }
+}
@@ -0,0 +1,27 @@
+use std::{fs, path::Path};
+
+use anyhow::Error;
+use serde::Deserialize;
+
+const QUERIES_YAML: &str = include_str!("../data/queries.yaml");
+
+#[derive(Deserialize)]
+pub struct Query {
+    pub query: String,
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Urls of the documents that should be on the first page, most relevant first.
+    pub expected: Vec<String>,
+}
+
+fn default_locale() -> String {
+    "en-US".to_string()
+}
+
+pub fn read(path: Option<impl AsRef<Path>>) -> Result<Vec<Query>, Error> {
+    if let Some(path) = path {
+        Ok(serde_yaml::from_reader(fs::File::open(path)?)?)
+    } else {
+        Ok(serde_yaml::from_str(QUERIES_YAML)?)
+    }
+}
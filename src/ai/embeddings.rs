@@ -58,6 +58,27 @@ WHERE LENGTH(doc.markdown) >= $4
 ORDER BY doc.embedding_next <=> $1
 LIMIT $3;";
 
+const SEARCH_EMB_DISTANCE: f64 = 0.78;
+
+const SEARCH_DOCS_QUERY: &str = "SELECT
+  doc.mdn_url AS url,
+  doc.title,
+  doc.embedding_next <=> $1 AS similarity
+FROM mdn_doc_macro doc
+WHERE (doc.embedding_next <=> $1) < $2
+  AND LOWER(doc.mdn_url) LIKE ANY($3)
+  AND doc.mdn_url NOT LIKE '/en-US/docs/MDN%'
+ORDER BY doc.embedding_next <=> $1
+LIMIT $4
+OFFSET $5;";
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct SearchDoc {
+    pub url: String,
+    pub title: String,
+    pub similarity: f64,
+}
+
 #[derive(sqlx::FromRow, Debug)]
 pub struct RelatedDoc {
     pub url: String,
@@ -174,3 +195,33 @@ pub async fn get_related_docs(
 
     Ok(docs)
 }
+
+/// Documents most similar to a search query, `url_patterns` are `LIKE` patterns
+/// matched against the lower-cased url to restrict the locales.
+pub async fn get_search_docs(
+    client: &Client<OpenAIConfig>,
+    pool: &SupaPool,
+    query: &str,
+    url_patterns: &[String],
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SearchDoc>, AIError> {
+    let embedding_req = CreateEmbeddingRequestArgs::default()
+        .model(EMBEDDING_MODEL)
+        .input(query)
+        .build()?;
+    let embedding_res = client.embeddings().create(embedding_req).await?;
+
+    let embedding =
+        pgvector::Vector::from(embedding_res.data.into_iter().next().unwrap().embedding);
+    let docs: Vec<SearchDoc> = sqlx::query_as(SEARCH_DOCS_QUERY)
+        .bind(embedding)
+        .bind(SEARCH_EMB_DISTANCE)
+        .bind(url_patterns)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(docs)
+}
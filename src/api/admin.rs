@@ -1,5 +1,17 @@
+use crate::api::play::{current_key_version, key_versions, GithubGistClient};
+use crate::api::play_moderation::{
+    delete_flagged_playground, hide_flagged_playground, list_flagged_playgrounds,
+    preview_playground, restore_flagged_playground,
+};
+use crate::api::play_storage::migrate_gists;
+use crate::api::search::compare_ranking_profiles;
+use crate::api::search_analytics::search_analytics_report;
+use crate::api::v2::notifications::send_digests;
 use crate::db::ai_history::do_delete_old_ai_history;
-use crate::db::v2::synchronize_bcd_updates_db::update_bcd;
+use crate::db::play::count_playgrounds_by_key_version;
+use crate::db::v2::synchronize_bcd_updates_db::{
+    list_bcd_sync_runs, update_bcd, validate_bcd_updates,
+};
 use crate::db::Pool;
 use crate::settings::SETTINGS;
 use actix_rt::ArbiterHandle;
@@ -9,9 +21,18 @@ use actix_web::HttpResponse;
 use actix_web::{web, Error};
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use actix_web_httpauth::middleware::HttpAuthentication;
+use serde::Serialize;
 
 use super::error::ApiError;
 
+#[derive(Serialize)]
+pub struct PlaygroundKeyUsage {
+    version: i32,
+    playgrounds: i64,
+    configured: bool,
+    current: bool,
+}
+
 pub async fn validator(
     req: ServiceRequest,
     credentials: BearerAuth,
@@ -26,8 +47,41 @@ pub async fn validator(
 pub fn admin_service() -> impl HttpServiceFactory {
     web::scope("/admin-api")
         .wrap(HttpAuthentication::bearer(validator))
-        .service(web::resource("/v2/updates/").route(web::post().to(update_bcd)))
+        .service(
+            web::resource("/v2/updates/")
+                .app_data(web::PayloadConfig::new(64 * 1_048_576))
+                .route(web::post().to(update_bcd)),
+        )
+        .service(web::resource("/v2/updates/runs/").route(web::get().to(list_bcd_sync_runs)))
+        .service(
+            web::resource("/v2/updates/validate/")
+                // the feed is several megabytes
+                .app_data(web::PayloadConfig::new(64 * 1_048_576))
+                .route(web::post().to(validate_bcd_updates)),
+        )
+        .service(web::resource("/v2/notifications/digest/").route(web::post().to(send_digests)))
         .service(web::resource("/ai-history/").route(web::post().to(delete_old_ai_history)))
+        .service(web::resource("/search/report/").route(web::get().to(search_analytics_report)))
+        .service(web::resource("/search/compare/").route(web::get().to(compare_ranking_profiles)))
+        .service(
+            web::resource("/playground/migrate/").route(web::post().to(migrate_playground_gists)),
+        )
+        .service(web::resource("/playground/keys/").route(web::get().to(playground_key_usage)))
+        .service(
+            web::resource("/playground/flagged/").route(web::get().to(list_flagged_playgrounds)),
+        )
+        .service(
+            web::resource("/playground/{id}/")
+                .route(web::get().to(preview_playground))
+                .route(web::delete().to(delete_flagged_playground)),
+        )
+        .service(
+            web::resource("/playground/{id}/hide/").route(web::post().to(hide_flagged_playground)),
+        )
+        .service(
+            web::resource("/playground/{id}/restore/")
+                .route(web::post().to(restore_flagged_playground)),
+        )
 }
 
 pub async fn delete_old_ai_history(
@@ -43,3 +97,48 @@ pub async fn delete_old_ai_history(
     }
     Ok(HttpResponse::Accepted().finish())
 }
+
+pub async fn migrate_playground_gists(
+    pool: Data<Pool>,
+    github_gist_client: Data<GithubGistClient>,
+    arbiter: Data<ArbiterHandle>,
+) -> Result<HttpResponse, ApiError> {
+    if github_gist_client.0.is_none() {
+        return Err(ApiError::NotImplemented);
+    }
+    if !arbiter.spawn(async move {
+        if let Some(client) = &github_gist_client.0 {
+            if let Err(e) = migrate_gists(&pool, client).await {
+                error!("{}", e);
+            }
+        }
+    }) {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Accepted().finish())
+}
+
+/// Number of playgrounds whose share id was handed out with each key version,
+/// including configured keys that are not in use (yet).
+pub async fn playground_key_usage(pool: Data<Pool>) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    let mut counts = count_playgrounds_by_key_version(&mut conn)?;
+    let configured = key_versions();
+    for version in &configured {
+        if !counts.iter().any(|(v, _)| v == version) {
+            counts.push((*version, 0));
+        }
+    }
+    counts.sort_unstable();
+    let current = current_key_version().ok();
+    let res: Vec<PlaygroundKeyUsage> = counts
+        .into_iter()
+        .map(|(version, playgrounds)| PlaygroundKeyUsage {
+            version,
+            playgrounds,
+            configured: configured.contains(&version),
+            current: current == Some(version),
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(res))
+}
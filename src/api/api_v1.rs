@@ -8,9 +8,9 @@ use crate::api::newsletter::{
     is_subscribed, subscribe_anonymous_handler, subscribe_handler, unsubscribe_handler,
 };
 use crate::api::ping::ping;
-use crate::api::play::{flag, load, save};
+use crate::api::play::{delete, flag, fork, list, load, revisions, save, update};
 use crate::api::root::root_service;
-use crate::api::search::search;
+use crate::api::search::{search, suggest};
 use crate::api::settings::update_settings;
 use crate::api::whoami::whoami;
 use actix_web::dev::HttpServiceFactory;
@@ -77,15 +77,27 @@ pub fn api_v1_service() -> impl HttpServiceFactory {
                 ),
         )
         .service(web::resource("/search").route(web::get().to(search)))
+        .service(web::resource("/search/suggest").route(web::get().to(suggest)))
         .service(web::resource("/whoami").route(web::get().to(whoami)))
         .service(web::resource("/ping").route(web::post().to(ping)))
         .service(web::resource("/newsletter").route(web::post().to(subscribe_anonymous_handler)))
         .service(
             web::scope("/play")
                 .app_data(json_cfg_1mb_limit)
-                .service(web::resource("/").route(web::post().to(save)))
+                .service(
+                    web::resource("/")
+                        .route(web::get().to(list))
+                        .route(web::post().to(save)),
+                )
                 .service(web::resource("/flag").route(web::post().to(flag)))
-                .service(web::resource("/{gist_id}").route(web::get().to(load))),
+                .service(
+                    web::resource("/{gist_id}")
+                        .route(web::get().to(load))
+                        .route(web::post().to(update))
+                        .route(web::delete().to(delete)),
+                )
+                .service(web::resource("/{gist_id}/fork").route(web::post().to(fork)))
+                .service(web::resource("/{gist_id}/revisions").route(web::get().to(revisions))),
         )
         .service(root_service())
 }
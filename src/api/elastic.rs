@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
@@ -13,11 +14,16 @@ pub struct Search<'a> {
     pub highlight: Highlight,
     pub suggest: Option<Suggest>,
     pub sort: Option<Vec<SortField>>,
+    pub search_after: Option<Vec<Value>>,
+    pub post_filter: Option<Query<'a>>,
+    pub aggs: Option<Aggregations<'a>>,
 }
 
 #[derive(Serialize)]
-pub struct Count<'a> {
+pub struct Autocomplete<'a> {
+    pub size: u64,
     pub query: Query<'a>,
+    pub _source: Source,
 }
 
 #[derive(Serialize)]
@@ -25,6 +31,7 @@ pub struct Count<'a> {
 pub enum Query<'a> {
     Bool(QueryBool<'a>),
     Terms(QueryTerms),
+    Prefix(QueryPrefix),
     Match(QueryMatch),
     MatchPhrase(QueryMatch),
     MultiMatch(QueryMultiMatch),
@@ -36,16 +43,39 @@ pub enum Query<'a> {
 pub struct QueryBool<'a> {
     pub filter: Option<Vec<Query<'a>>>,
     pub must: Option<Vec<Query<'a>>>,
+    pub must_not: Option<Vec<Query<'a>>>,
     pub should: Option<Vec<Query<'a>>>,
 }
 
 #[derive(Serialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum QueryTerms {
     Locale(Vec<Locale>),
+    PageType(Vec<String>),
+    Status(Vec<BcdStatus>),
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryPrefix {
+    Slug(String),
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BcdStatus {
+    Deprecated,
+    Experimental,
+}
+
+impl FromStr for BcdStatus {
+    type Err = serde_json::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(serde_json::Value::String(s.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Locale {
     #[serde(rename = "en-us")]
     English,
@@ -85,18 +115,31 @@ pub enum QueryMatch {
     Body(QueryMatchField),
 }
 
+#[serde_with::skip_serializing_none]
 #[derive(Serialize)]
 pub struct QueryMultiMatch {
     pub query: String,
     pub fields: Vec<Field>,
+    #[serde(rename = "type")]
+    pub kind: Option<MultiMatchType>,
 }
 
 #[derive(Serialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
+pub enum MultiMatchType {
+    BoolPrefix,
+    Phrase,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Field {
     Title,
     Body,
     Popularity,
+    PageType,
+    Status,
+    Summary,
 }
 
 #[derive(Serialize)]
@@ -123,21 +166,64 @@ pub enum BoostMode {
 #[serde(rename_all = "lowercase")]
 pub enum ScoreMode {
     Max,
+    Sum,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryFunctionScoreFunction {
     FieldValueFactor(QueryFunctionScoreFunctionFieldValueFactor),
+    #[serde(untagged)]
+    Decay(QueryFunctionScoreFunctionDecay),
 }
 
 #[derive(Serialize)]
 pub struct QueryFunctionScoreFunctionFieldValueFactor {
     pub field: Field,
-    pub factor: u64,
+    pub factor: f64,
     pub missing: u64,
 }
 
+#[derive(Serialize)]
+pub struct QueryFunctionScoreFunctionDecay {
+    /// Keyed by the date field.
+    pub exp: BTreeMap<String, DecayParams>,
+    pub weight: f64,
+}
+
+#[derive(Serialize)]
+pub struct DecayParams {
+    pub origin: String,
+    pub scale: String,
+    pub offset: String,
+    pub decay: f64,
+}
+
+#[derive(Serialize)]
+pub struct Aggregations<'a> {
+    pub area: Aggregation<'a>,
+    pub page_type: Aggregation<'a>,
+    pub status: Aggregation<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation<'a> {
+    Terms(AggregationTerms),
+    Filters(AggregationFilters<'a>),
+}
+
+#[derive(Serialize)]
+pub struct AggregationTerms {
+    pub field: Field,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+pub struct AggregationFilters<'a> {
+    pub filters: BTreeMap<String, Query<'a>>,
+}
+
 #[derive(Serialize)]
 pub struct Source {
     pub excludes: Vec<Field>,
@@ -168,27 +254,47 @@ pub struct HighlightFields {
 #[derive(Serialize)]
 pub struct Suggest {
     pub text: String,
-    pub title_suggestions: Suggester,
-    pub body_suggestions: Suggester,
+    pub phrase_suggestions: Suggester,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Suggester {
-    Term(TermSuggester),
+    Phrase(PhraseSuggester),
+}
+
+#[derive(Serialize)]
+pub struct PhraseSuggester {
+    pub field: Field,
+    pub size: u64,
+    pub direct_generator: Vec<DirectGenerator>,
+    pub collate: SuggestCollate,
 }
 
 #[derive(Serialize)]
-pub struct TermSuggester {
+pub struct DirectGenerator {
     pub field: Field,
 }
 
+/// Without `prune`, suggestions for which the query finds nothing are dropped.
+#[derive(Serialize)]
+pub struct SuggestCollate {
+    pub query: SuggestCollateQuery,
+}
+
+/// The query is a template, with the suggested text in `{{suggestion}}`.
+#[derive(Serialize)]
+pub struct SuggestCollateQuery {
+    pub source: Query<'static>,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SortField {
     #[serde(rename = "_score")]
     Score(Order),
     Popularity(Order),
+    Slug(Order),
 }
 
 #[derive(Serialize)]
@@ -202,9 +308,38 @@ pub enum Order {
 pub struct SearchResponse {
     pub hits: ResponseHits,
     pub suggest: Option<ResponseSuggest>,
+    pub aggregations: Option<ResponseAggregations>,
     pub took: u64,
 }
 
+#[derive(Deserialize)]
+pub struct ResponseAggregations {
+    pub area: ResponseFiltersAggregation,
+    pub page_type: ResponseTermsAggregation,
+    pub status: ResponseTermsAggregation,
+}
+
+#[derive(Deserialize)]
+pub struct ResponseFiltersAggregation {
+    pub buckets: BTreeMap<String, ResponseFiltersBucket>,
+}
+
+#[derive(Deserialize)]
+pub struct ResponseFiltersBucket {
+    pub doc_count: u64,
+}
+
+#[derive(Deserialize)]
+pub struct ResponseTermsAggregation {
+    pub buckets: Vec<ResponseTermsBucket>,
+}
+
+#[derive(Deserialize)]
+pub struct ResponseTermsBucket {
+    pub key: String,
+    pub doc_count: u64,
+}
+
 #[derive(Deserialize)]
 pub struct ResponseHits {
     pub hits: Vec<ResponseHit>,
@@ -217,6 +352,9 @@ pub struct ResponseHit {
     pub _score: f64,
     pub _source: ResponseSource,
     pub highlight: ResponseHighlight,
+    /// Only present if the search had an explicit sort.
+    #[serde(default)]
+    pub sort: Vec<Value>,
 }
 
 #[derive(Deserialize)]
@@ -232,7 +370,7 @@ pub struct ResponseSource {
     pub summary: String,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct ResponseHighlight {
     #[serde(default = "Vec::default")]
     pub body: Vec<String>,
@@ -242,8 +380,7 @@ pub struct ResponseHighlight {
 
 #[derive(Deserialize)]
 pub struct ResponseSuggest {
-    pub body_suggestions: Vec<ResponseSuggestion>,
-    pub title_suggestions: Vec<ResponseSuggestion>,
+    pub phrase_suggestions: Vec<ResponseSuggestion>,
 }
 
 #[derive(Deserialize)]
@@ -255,16 +392,15 @@ pub struct ResponseSuggestion {
 pub struct ResponseSuggestionOption {
     pub text: String,
     pub score: f64,
-    pub freq: u64,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct ResponseTotal {
     pub value: u64,
     pub relation: ResponseTotalRelation,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub enum ResponseTotalRelation {
     #[serde(rename = "eq")]
     Equal,
@@ -273,6 +409,17 @@ pub enum ResponseTotalRelation {
 }
 
 #[derive(Deserialize)]
-pub struct CountResponse {
-    pub count: u64,
+pub struct AutocompleteResponse {
+    pub hits: AutocompleteHits,
+}
+
+#[derive(Deserialize)]
+pub struct AutocompleteHits {
+    pub hits: Vec<AutocompleteHit>,
+}
+
+#[derive(Deserialize)]
+pub struct AutocompleteHit {
+    pub _id: String,
+    pub _source: ResponseSource,
 }
@@ -33,6 +33,8 @@ pub enum SearchError {
     ParseResponse,
     #[error("Query string parsing failed for {key}: {message}")]
     Query { key: String, message: String },
+    #[error("Search is temporarily unavailable")]
+    Unavailable,
 }
 
 #[derive(Error, Debug)]
@@ -59,12 +61,40 @@ pub enum PlaygroundError {
     DecodeError(#[from] base64::DecodeError),
     #[error("No nonce error")]
     NoNonceError,
+    #[error("Unknown share id key")]
+    UnknownKey,
     #[error("Crypt utf error: {0}")]
     UtfDecodeError(#[from] FromUtf8Error),
     #[error("Playground error: no settings")]
     SettingsError,
     #[error("Gist not owned by the playground bot user")]
     NotGistOwner,
+    #[error("Playground not found")]
+    NotFound,
+    #[error("Playground not owned by the user")]
+    NotPlaygroundOwner,
+    #[error("Playground has been hidden by a moderator")]
+    Hidden,
+    #[error("Json error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Invalid playground code: {0}")]
+    InvalidCode(serde_json::Error),
+    #[error("Gist has {0} files instead of one playground file")]
+    InvalidGist(usize),
+    #[error("DB error: {0}")]
+    DbError(#[from] DbError),
+}
+
+impl From<diesel::result::Error> for PlaygroundError {
+    fn from(e: diesel::result::Error) -> Self {
+        PlaygroundError::DbError(e.into())
+    }
+}
+
+impl From<r2d2::Error> for PlaygroundError {
+    fn from(e: r2d2::Error) -> Self {
+        PlaygroundError::DbError(e.into())
+    }
 }
 
 impl ResponseError for PlaygroundError {
@@ -73,11 +103,19 @@ impl ResponseError for PlaygroundError {
             PlaygroundError::CryptError(_)
             | PlaygroundError::DecodeError(_)
             | PlaygroundError::NoNonceError
-            | PlaygroundError::UtfDecodeError(_) => StatusCode::BAD_REQUEST,
-            PlaygroundError::NotGistOwner => StatusCode::FORBIDDEN,
-            PlaygroundError::OctocrabError(_) | PlaygroundError::SettingsError => {
-                StatusCode::INTERNAL_SERVER_ERROR
+            | PlaygroundError::UnknownKey
+            | PlaygroundError::UtfDecodeError(_)
+            | PlaygroundError::InvalidCode(_)
+            | PlaygroundError::InvalidGist(_) => StatusCode::BAD_REQUEST,
+            PlaygroundError::NotGistOwner | PlaygroundError::NotPlaygroundOwner => {
+                StatusCode::FORBIDDEN
             }
+            PlaygroundError::NotFound => StatusCode::NOT_FOUND,
+            PlaygroundError::Hidden => StatusCode::GONE,
+            PlaygroundError::OctocrabError(_)
+            | PlaygroundError::SettingsError
+            | PlaygroundError::JsonError(_)
+            | PlaygroundError::DbError(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
@@ -92,6 +130,14 @@ impl ResponseError for PlaygroundError {
     }
 }
 
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("Mail request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Mail error: no mail_url configured")]
+    NoUrl,
+}
+
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("Artificial error")]
@@ -138,6 +184,8 @@ pub enum ApiError {
     AIError(#[from] AIError),
     #[error("Playground error: {0}")]
     PlaygroundError(#[from] PlaygroundError),
+    #[error("Mail error: {0}")]
+    MailError(#[from] MailError),
     #[error("Unknown error: {0}")]
     Generic(String),
     #[error("Payment required")]
@@ -170,6 +218,7 @@ impl ApiError {
             Self::MultipleCollectionSubscriptionLimitReached => "Subscription limit reached",
             Self::BasketError(_) => "Error managing newsletter",
             Self::PlaygroundError(_) => "Error querying playground",
+            Self::MailError(_) => "Error sending mail",
             Self::Generic(err) => err,
             Self::LoginRequiredForFeature(_) => "Login Required",
             Self::OpenAIError(_) => "Open AI error",
@@ -190,6 +239,7 @@ impl ResponseError for ApiError {
             Self::MalformedUrl => StatusCode::BAD_REQUEST,
             Self::Query(_) => StatusCode::BAD_REQUEST,
             Self::Search(SearchError::Query { .. }) => StatusCode::BAD_REQUEST,
+            Self::Search(SearchError::Unavailable) => StatusCode::SERVICE_UNAVAILABLE,
             Self::Unauthorized => StatusCode::UNAUTHORIZED,
             Self::CollectionNotFound(_) => StatusCode::BAD_REQUEST,
             Self::ValidationError(_) => StatusCode::BAD_REQUEST,
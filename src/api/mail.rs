@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+use url::Url;
+
+use crate::api::error::MailError;
+use crate::settings::{MailBackend, Notifications};
+
+#[derive(Serialize, Debug)]
+pub struct Mail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+}
+
+/// Backend that delivers the mails of rumba, currently the BCD notification digests.
+#[async_trait]
+pub trait MailSender: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError>;
+}
+
+pub struct Mailer(pub Option<Box<dyn MailSender>>);
+
+impl Mailer {
+    pub fn new(settings: Option<&Notifications>, client: &Client) -> Self {
+        let sender: Option<Box<dyn MailSender>> =
+            settings.map(|settings| match settings.mail_backend {
+                MailBackend::Log => Box::new(LogMailer) as Box<dyn MailSender>,
+                MailBackend::Http => Box::new(HttpMailer {
+                    client: client.clone(),
+                    url: settings.mail_url.clone(),
+                    token: settings.mail_token.clone(),
+                }),
+            });
+        Mailer(sender)
+    }
+}
+
+pub struct LogMailer;
+
+#[async_trait]
+impl MailSender for LogMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        info!("mail to {}: {}\n{}", mail.to, mail.subject, mail.text);
+        Ok(())
+    }
+}
+
+/// Posts every mail as JSON, authenticated with a bearer token if one is configured.
+pub struct HttpMailer {
+    pub client: Client,
+    pub url: Option<Url>,
+    pub token: Option<String>,
+}
+
+#[async_trait]
+impl MailSender for HttpMailer {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        let url = self.url.as_ref().ok_or(MailError::NoUrl)?;
+        let mut req = self.client.post(url.clone()).json(mail);
+        if let Some(token) = &self.token {
+            req = req.bearer_auth(token);
+        }
+        req.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
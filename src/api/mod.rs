@@ -9,11 +9,19 @@ pub mod error;
 pub mod fxa_webhook;
 pub mod healthz;
 pub mod info;
+pub mod mail;
 pub mod newsletter;
 pub mod ping;
 pub mod play;
+pub mod play_moderation;
+pub mod play_storage;
 pub mod root;
 pub mod search;
+pub mod search_analytics;
+pub mod search_cursor;
+pub mod search_query;
+pub mod search_ranking;
+pub mod search_resilience;
 pub mod settings;
 pub mod v2;
 pub mod whoami;
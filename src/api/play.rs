@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use actix_identity::Identity;
 use actix_web::{web, HttpResponse};
 use aes_gcm::{
@@ -5,20 +7,32 @@ use aes_gcm::{
     Aes256Gcm, KeyInit, Nonce,
 };
 use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::NaiveDateTime;
+use diesel::{Connection, OptionalExtension, PgConnection};
 use octocrab::Octocrab;
 use once_cell::sync::Lazy;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde::{Deserialize, Serialize};
 use url::Url;
+use validator::{Validate, ValidationError};
 
 use crate::{
-    api::error::{ApiError, PlaygroundError},
+    api::{
+        error::{ApiError, PlaygroundError},
+        play_storage::PlaygroundStorage,
+    },
     db::{
         self,
-        model::PlaygroundInsert,
-        play::{create_playground, flag_playground},
+        model::{PlaygroundInsert, PlaygroundQuery, PlaygroundRevisionInsert},
+        play::{
+            add_playground_revision, create_playground, create_playground_revision,
+            delete_playground, flag_playground, get_playground, get_playground_gists,
+            get_playground_revision, get_playground_revisions, get_playgrounds_for_user,
+            revert_playground_revision,
+        },
         Pool,
     },
+    helpers::to_utc,
     settings::SETTINGS,
 };
 
@@ -28,18 +42,216 @@ const DESCRIPTION: &str = "Code shared from the MDN Playground";
 pub struct GithubGistClient(pub Option<Octocrab>);
 pub struct GithubFlagsClient(pub Option<Octocrab>);
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+/// Current version of the playground JSON format.
+pub const PLAY_CODE_VERSION: u32 = 2;
+const MAX_FILE_LENGTH: u64 = 256 * 1024;
+const MAX_FILES: u64 = 32;
+const MAX_LIBRARIES: u64 = 16;
+const LEGACY_JS_FILENAME: &str = "script.js";
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayMode {
+    #[default]
+    Javascript,
+    Typescript,
+    Jsx,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Validate)]
+pub struct PlayFile {
+    #[validate(length(min = 1, max = 255, message = "'name' must be between 1 and 255 chars"))]
+    name: String,
+    #[validate(custom(function = "validate_file_length"))]
+    content: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, Validate)]
 pub struct PlayCode {
+    #[validate(range(
+        min = 2,
+        max = PLAY_CODE_VERSION,
+        message = "unsupported playground version"
+    ))]
+    version: u32,
+    #[serde(default)]
+    mode: PlayMode,
+    #[validate(custom(function = "validate_file_length"))]
+    head: Option<String>,
+    #[validate(custom(function = "validate_file_length"))]
+    html: Option<String>,
+    #[validate(custom(function = "validate_file_length"))]
+    css: Option<String>,
+    /// JavaScript (or TypeScript/JSX depending on `mode`) modules, the first one is the
+    /// entry point.
+    #[serde(default)]
+    #[validate(custom(function = "validate_files"), nested)]
+    files: Vec<PlayFile>,
+    /// External library URLs loaded before the files.
+    #[serde(default)]
+    #[validate(custom(function = "validate_libraries"))]
+    libraries: Vec<String>,
+    src: Option<String>,
+}
+
+/// The playground format before it was versioned.
+#[derive(Deserialize)]
+struct LegacyPlayCode {
     html: Option<String>,
     css: Option<String>,
     js: Option<String>,
     src: Option<String>,
 }
+
+impl Default for PlayCode {
+    fn default() -> Self {
+        PlayCode {
+            version: PLAY_CODE_VERSION,
+            mode: Default::default(),
+            head: None,
+            html: None,
+            css: None,
+            files: vec![],
+            libraries: vec![],
+            src: None,
+        }
+    }
+}
+
+impl From<LegacyPlayCode> for PlayCode {
+    fn from(legacy: LegacyPlayCode) -> Self {
+        PlayCode {
+            html: legacy.html,
+            css: legacy.css,
+            files: legacy
+                .js
+                .map(|content| PlayFile {
+                    name: LEGACY_JS_FILENAME.to_string(),
+                    content,
+                })
+                .into_iter()
+                .collect(),
+            src: legacy.src,
+            ..Default::default()
+        }
+    }
+}
+
+impl PlayCode {
+    /// Parses playground code in any known format, upgrading unversioned code to the
+    /// current format.
+    pub fn from_value(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        if value.get("version").is_some() {
+            serde_json::from_value(value)
+        } else {
+            serde_json::from_value::<LegacyPlayCode>(value).map(Into::into)
+        }
+    }
+}
+
+// The limits are checked by hand so the messages can't drift from the constants.
+fn limit_error(code: &'static str, message: String) -> ValidationError {
+    let mut err = ValidationError::new(code);
+    err.message = Some(message.into());
+    err
+}
+
+fn validate_file_length(content: &str) -> Result<(), ValidationError> {
+    if content.chars().count() as u64 > MAX_FILE_LENGTH {
+        return Err(limit_error(
+            "length",
+            format!("must not be longer than {MAX_FILE_LENGTH} chars"),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_files(files: &[PlayFile]) -> Result<(), ValidationError> {
+    if files.len() as u64 > MAX_FILES {
+        return Err(limit_error(
+            "length",
+            format!("must not contain more than {MAX_FILES} files"),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_libraries(libraries: &[String]) -> Result<(), ValidationError> {
+    if libraries.len() as u64 > MAX_LIBRARIES {
+        return Err(limit_error(
+            "length",
+            format!("must not contain more than {MAX_LIBRARIES} urls"),
+        ));
+    }
+    for library in libraries {
+        match Url::parse(library) {
+            Ok(url) if url.scheme() == "https" => {}
+            _ => {
+                let mut err = ValidationError::new("libraries");
+                err.message = Some("'libraries' must only contain https urls".into());
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize, Validate)]
+pub struct PlaySaveRequest {
+    #[serde(flatten)]
+    code: serde_json::Value,
+    #[validate(length(max = 1024, message = "'title' must not be longer than 1024 chars"))]
+    title: Option<String>,
+}
+
+#[derive(Deserialize, Validate, Default)]
+pub struct PlayForkRequest {
+    #[validate(length(max = 1024, message = "'title' must not be longer than 1024 chars"))]
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PlayLoadQuery {
+    revision: Option<i64>,
+}
+
 #[derive(Serialize)]
 pub struct PlaySaveResponse {
     id: String,
 }
 
+#[derive(Serialize)]
+pub struct PlayUpdateResponse {
+    id: String,
+    revision: i64,
+}
+
+#[derive(Serialize)]
+pub struct PlayInfo {
+    id: String,
+    title: Option<String>,
+    revision: i64,
+    forked_from: Option<String>,
+    #[serde(serialize_with = "to_utc")]
+    created_at: NaiveDateTime,
+    #[serde(serialize_with = "to_utc")]
+    updated_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct PlayRevision {
+    revision: i64,
+    #[serde(serialize_with = "to_utc")]
+    created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct PlayRevisionsResponse {
+    #[serde(flatten)]
+    info: PlayInfo,
+    revisions: Vec<PlayRevision>,
+}
+
 #[derive(Debug)]
 pub struct Gist {
     pub id: String,
@@ -66,59 +278,117 @@ pub struct PlayFlagRequest {
 }
 
 pub(crate) const NONCE_LEN: usize = 12;
-static CIPHER: Lazy<Option<Aes256Gcm>> = Lazy::new(|| {
-    SETTINGS
-        .playground
-        .as_ref()
-        .map(|playground| Aes256Gcm::new(GenericArray::from_slice(&playground.crypt_key)))
+const KEY_VERSION_SEPARATOR: char = '.';
+
+struct KeyRing {
+    current: i32,
+    ciphers: HashMap<i32, Aes256Gcm>,
+}
+
+static KEY_RING: Lazy<Option<KeyRing>> = Lazy::new(|| {
+    let playground = SETTINGS.playground.as_ref()?;
+    let mut ciphers: HashMap<i32, Aes256Gcm> = playground
+        .crypt_keys
+        .iter()
+        .map(|key| {
+            (
+                key.version,
+                Aes256Gcm::new(GenericArray::from_slice(&key.key)),
+            )
+        })
+        .collect();
+    if let Some(key) = &playground.crypt_key {
+        ciphers.insert(0, Aes256Gcm::new(GenericArray::from_slice(key)));
+    }
+    let current = *ciphers.keys().max()?;
+    Some(KeyRing { current, ciphers })
 });
 
+/// The key version new share ids are encrypted with.
+pub fn current_key_version() -> Result<i32, PlaygroundError> {
+    KEY_RING
+        .as_ref()
+        .map(|ring| ring.current)
+        .ok_or(PlaygroundError::SettingsError)
+}
+
+/// All key versions share ids can currently be decrypted with.
+pub fn key_versions() -> Vec<i32> {
+    let mut versions: Vec<i32> = KEY_RING
+        .as_ref()
+        .map(|ring| ring.ciphers.keys().copied().collect())
+        .unwrap_or_default();
+    versions.sort_unstable();
+    versions
+}
+
 pub fn encrypt(gist_id: &str) -> Result<String, PlaygroundError> {
-    if let Some(cipher) = &*CIPHER {
-        let mut nonce = vec![0; NONCE_LEN];
-        OsRng.fill_bytes(&mut nonce);
-        let nonce = Nonce::from_slice(&nonce);
-        let mut data = cipher.encrypt(nonce, gist_id.as_bytes())?;
-        data.extend_from_slice(nonce.as_slice());
-
-        Ok(STANDARD.encode(data))
+    encrypt_with_key(gist_id, current_key_version()?)
+}
+
+/// Share ids for key version 0 have no prefix, so links created before we had
+/// multiple keys keep working.
+pub fn encrypt_with_key(gist_id: &str, version: i32) -> Result<String, PlaygroundError> {
+    let cipher = KEY_RING
+        .as_ref()
+        .ok_or(PlaygroundError::SettingsError)?
+        .ciphers
+        .get(&version)
+        .ok_or(PlaygroundError::UnknownKey)?;
+    let mut nonce = vec![0; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let nonce = Nonce::from_slice(&nonce);
+    let mut data = cipher.encrypt(nonce, gist_id.as_bytes())?;
+    data.extend_from_slice(nonce.as_slice());
+
+    let encoded = STANDARD.encode(data);
+    if version == 0 {
+        Ok(encoded)
     } else {
-        Err(PlaygroundError::SettingsError)
+        Ok(format!("{version}{KEY_VERSION_SEPARATOR}{encoded}"))
     }
 }
 
 fn decrypt(encoded: &str) -> Result<String, PlaygroundError> {
-    if let Some(cipher) = &*CIPHER {
-        let data = STANDARD.decode(encoded)?;
-        if NONCE_LEN > data.len() {
-            return Err(PlaygroundError::NoNonceError);
-        }
-        let (enc, nonce) = data.split_at(data.len() - NONCE_LEN);
-        let nonce = Nonce::from_slice(nonce);
-        let data = cipher.decrypt(nonce, enc)?;
+    let ring = KEY_RING.as_ref().ok_or(PlaygroundError::SettingsError)?;
+    let (version, encoded) = match encoded.split_once(KEY_VERSION_SEPARATOR) {
+        Some((version, encoded)) => (
+            version
+                .parse::<i32>()
+                .map_err(|_| PlaygroundError::UnknownKey)?,
+            encoded,
+        ),
+        None => (0, encoded),
+    };
+    let cipher = ring
+        .ciphers
+        .get(&version)
+        .ok_or(PlaygroundError::UnknownKey)?;
+    let data = STANDARD.decode(encoded)?;
+    if NONCE_LEN > data.len() {
+        return Err(PlaygroundError::NoNonceError);
+    }
+    let (enc, nonce) = data.split_at(data.len() - NONCE_LEN);
+    let nonce = Nonce::from_slice(nonce);
+    let data = cipher.decrypt(nonce, enc)?;
 
-        Ok(String::from_utf8(data)?)
-    } else {
-        Err(PlaygroundError::SettingsError)
+    Ok(String::from_utf8(data)?)
+}
+
+/// The code of a gist, which must consist of a single playground file.
+pub fn gist_code(gist: &octocrab::models::gists::Gist) -> Result<PlayCode, PlaygroundError> {
+    let mut files = gist.files.values();
+    match (files.next(), files.next()) {
+        (Some(file), None) => serde_json::from_str(file.content.as_deref().unwrap_or_default())
+            .and_then(PlayCode::from_value)
+            .map_err(PlaygroundError::InvalidCode),
+        _ => Err(PlaygroundError::InvalidGist(gist.files.len())),
     }
 }
 
 impl From<octocrab::models::gists::Gist> for Gist {
     fn from(other: octocrab::models::gists::Gist) -> Self {
-        let mut files: Vec<_> = other
-            .files
-            .into_iter()
-            .map(|(name, file)| (name, file.content))
-            .collect();
-
-        files.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
-        let code = if files.len() != 1 {
-            PlayCode::default()
-        } else {
-            let (_, content) = files.pop().unwrap();
-            serde_json::from_str(&content.unwrap_or_default()).unwrap_or_default()
-        };
-
+        let code = gist_code(&other).unwrap_or_default();
         Gist {
             id: other.id,
             url: other.html_url,
@@ -143,6 +413,22 @@ pub async fn create_gist(
         .map_err(Into::into)
 }
 
+pub async fn update_gist(
+    client: &Octocrab,
+    id: &str,
+    code: impl Into<String>,
+) -> Result<(), PlaygroundError> {
+    client
+        .gists()
+        .update(id)
+        .file(FILENAME)
+        .with_content(code)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(Into::into)
+}
+
 pub async fn create_flag_issue(
     client: &Octocrab,
     gist_id: String,
@@ -169,6 +455,14 @@ pub async fn create_flag_issue(
 }
 
 pub async fn load_gist(client: &Octocrab, id: &str) -> Result<Gist, PlaygroundError> {
+    load_raw_gist(client, id).await.map(Into::into)
+}
+
+/// Loads a gist of the playground bot user without parsing its code.
+pub async fn load_raw_gist(
+    client: &Octocrab,
+    id: &str,
+) -> Result<octocrab::models::gists::Gist, PlaygroundError> {
     let expected_owner = SETTINGS
         .playground
         .as_ref()
@@ -178,32 +472,38 @@ pub async fn load_gist(client: &Octocrab, id: &str) -> Result<Gist, PlaygroundEr
     if owner.as_ref().map(|owner| owner.login.as_str()) != Some(expected_owner) {
         return Err(PlaygroundError::NotGistOwner);
     }
-    Ok(gist.into())
+    Ok(gist)
 }
 
 pub async fn save(
-    save: web::Json<PlayCode>,
+    save: web::Json<PlaySaveRequest>,
     id: Option<Identity>,
     pool: web::Data<Pool>,
-    github_gist_client: web::Data<GithubGistClient>,
+    playground_storage: web::Data<PlaygroundStorage>,
 ) -> Result<HttpResponse, ApiError> {
-    if let Some(client) = &github_gist_client.0 {
+    if let Some(storage) = &playground_storage.0 {
         if let Some(user_id) = id {
-            let gist =
-                create_gist(client, serde_json::to_string_pretty(&save.into_inner())?).await?;
+            save.validate()?;
+            let PlaySaveRequest { code, title } = save.into_inner();
+            let code = PlayCode::from_value(code).map_err(PlaygroundError::InvalidCode)?;
+            code.validate()?;
+            let gist_id = storage.save(&code).await?;
             let mut conn = pool.get()?;
             let user = db::users::get_user(&mut conn, user_id.id().unwrap())?;
-            create_playground(
+            create_playground_with_code(
                 &mut conn,
                 PlaygroundInsert {
                     user_id: Some(user.id),
-                    gist: gist.id.clone(),
+                    gist: gist_id.clone(),
                     active: true,
+                    title,
+                    key_version: current_key_version()?,
                     ..Default::default()
                 },
+                &code,
             )?;
 
-            let id = encrypt(&gist.id)?;
+            let id = encrypt(&gist_id)?;
             Ok(HttpResponse::Created().json(PlaySaveResponse { id }))
         } else {
             Ok(HttpResponse::Unauthorized().finish())
@@ -215,30 +515,292 @@ pub async fn save(
 
 pub async fn load(
     gist_id: web::Path<String>,
-    github_gist_client: web::Data<GithubGistClient>,
+    query: web::Query<PlayLoadQuery>,
+    pool: web::Data<Pool>,
+    playground_storage: web::Data<PlaygroundStorage>,
 ) -> Result<HttpResponse, ApiError> {
-    if let Some(client) = &github_gist_client.0 {
+    if let Some(storage) = &playground_storage.0 {
         let id = decrypt(&gist_id.into_inner())?;
-        let gist = load_gist(client, &id).await?;
-        Ok(HttpResponse::Ok().json(gist.code))
+        let mut conn = pool.get()?;
+        let playground = get_visible_playground(&mut conn, &id)?;
+        let code = if let Some(revision) = query.revision {
+            let playground = playground.ok_or(PlaygroundError::NotFound)?;
+            let code = get_playground_revision(&mut conn, playground.id, revision)?
+                .ok_or(PlaygroundError::NotFound)?;
+            PlayCode::from_value(code)?
+        } else {
+            drop(conn);
+            storage.load(&id).await?
+        };
+        Ok(HttpResponse::Ok().json(code))
+    } else {
+        Ok(HttpResponse::NotImplemented().finish())
+    }
+}
+
+pub async fn update(
+    gist_id: web::Path<String>,
+    save: web::Json<PlaySaveRequest>,
+    id: Option<Identity>,
+    pool: web::Data<Pool>,
+    playground_storage: web::Data<PlaygroundStorage>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(storage) = &playground_storage.0 {
+        if let Some(user_id) = id {
+            save.validate()?;
+            let PlaySaveRequest { code, title } = save.into_inner();
+            let code = PlayCode::from_value(code).map_err(PlaygroundError::InvalidCode)?;
+            code.validate()?;
+            let share_id = gist_id.into_inner();
+            let gist_id = decrypt(&share_id)?;
+            let (playground, has_current_revision) = {
+                let mut conn = pool.get()?;
+                let user = db::users::get_user(&mut conn, user_id.id().unwrap())?;
+                let playground = get_owned_playground(&mut conn, &gist_id, user.id)?;
+                let has_current_revision =
+                    get_playground_revision(&mut conn, playground.id, playground.revision)?
+                        .is_some();
+                (playground, has_current_revision)
+            };
+            // Playgrounds saved before we kept revisions only exist in the storage backend.
+            // Keep their current code as the revision we are about to replace.
+            if !has_current_revision {
+                let current = storage.load(&gist_id).await?;
+                let mut conn = pool.get()?;
+                create_playground_revision(
+                    &mut conn,
+                    PlaygroundRevisionInsert {
+                        playground_id: playground.id,
+                        revision: playground.revision,
+                        code: serde_json::to_value(&current)?,
+                    },
+                )?;
+            }
+            // Bump the revision first and undo it if the storage backend fails, so the
+            // stored code never gets ahead of the revisions.
+            let revision = {
+                let mut conn = pool.get()?;
+                add_playground_revision(
+                    &mut conn,
+                    playground.id,
+                    title.as_deref(),
+                    serde_json::to_value(&code)?,
+                )?
+            };
+            if let Err(e) = storage.update(&gist_id, &code).await {
+                let mut conn = pool.get()?;
+                revert_playground_revision(&mut conn, &playground)?;
+                return Err(e.into());
+            }
+            Ok(HttpResponse::Ok().json(PlayUpdateResponse {
+                id: share_id,
+                revision,
+            }))
+        } else {
+            Ok(HttpResponse::Unauthorized().finish())
+        }
+    } else {
+        Ok(HttpResponse::NotImplemented().finish())
+    }
+}
+
+pub async fn fork(
+    gist_id: web::Path<String>,
+    fork: Option<web::Json<PlayForkRequest>>,
+    id: Option<Identity>,
+    pool: web::Data<Pool>,
+    playground_storage: web::Data<PlaygroundStorage>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(storage) = &playground_storage.0 {
+        if let Some(user_id) = id {
+            let fork = fork.map(|fork| fork.into_inner()).unwrap_or_default();
+            fork.validate()?;
+            let gist_id = decrypt(&gist_id.into_inner())?;
+            let parent = {
+                let mut conn = pool.get()?;
+                get_visible_playground(&mut conn, &gist_id)?.ok_or(PlaygroundError::NotFound)?
+            };
+            let code = storage.load(&gist_id).await?;
+            let fork_gist_id = storage.save(&code).await?;
+            let mut conn = pool.get()?;
+            let user = db::users::get_user(&mut conn, user_id.id().unwrap())?;
+            create_playground_with_code(
+                &mut conn,
+                PlaygroundInsert {
+                    user_id: Some(user.id),
+                    gist: fork_gist_id.clone(),
+                    active: true,
+                    title: fork.title.or(parent.title),
+                    parent_id: Some(parent.id),
+                    key_version: current_key_version()?,
+                    ..Default::default()
+                },
+                &code,
+            )?;
+
+            let id = encrypt(&fork_gist_id)?;
+            Ok(HttpResponse::Created().json(PlaySaveResponse { id }))
+        } else {
+            Ok(HttpResponse::Unauthorized().finish())
+        }
     } else {
         Ok(HttpResponse::NotImplemented().finish())
     }
 }
 
+pub async fn delete(
+    gist_id: web::Path<String>,
+    id: Option<Identity>,
+    pool: web::Data<Pool>,
+    playground_storage: web::Data<PlaygroundStorage>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(storage) = &playground_storage.0 {
+        if let Some(user_id) = id {
+            let gist_id = decrypt(&gist_id.into_inner())?;
+            let playground = {
+                let mut conn = pool.get()?;
+                let user = db::users::get_user(&mut conn, user_id.id().unwrap())?;
+                get_owned_playground(&mut conn, &gist_id, user.id)?
+            };
+            storage.delete(&gist_id).await?;
+            let mut conn = pool.get()?;
+            delete_playground(&mut conn, playground.id)?;
+            Ok(HttpResponse::Ok().finish())
+        } else {
+            Ok(HttpResponse::Unauthorized().finish())
+        }
+    } else {
+        Ok(HttpResponse::NotImplemented().finish())
+    }
+}
+
+pub async fn list(id: Option<Identity>, pool: web::Data<Pool>) -> Result<HttpResponse, ApiError> {
+    if let Some(user_id) = id {
+        let mut conn = pool.get()?;
+        let user = db::users::get_user(&mut conn, user_id.id().unwrap())?;
+        let playgrounds = get_playgrounds_for_user(&mut conn, user.id)?;
+        Ok(HttpResponse::Ok().json(play_infos(&mut conn, playgrounds)?))
+    } else {
+        Ok(HttpResponse::Unauthorized().finish())
+    }
+}
+
+pub async fn revisions(
+    gist_id: web::Path<String>,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ApiError> {
+    let gist_id = decrypt(&gist_id.into_inner())?;
+    let mut conn = pool.get()?;
+    let playground =
+        get_visible_playground(&mut conn, &gist_id)?.ok_or(PlaygroundError::NotFound)?;
+    let revisions = get_playground_revisions(&mut conn, playground.id)?
+        .into_iter()
+        .map(|(revision, created_at)| PlayRevision {
+            revision,
+            created_at,
+        })
+        .collect();
+    let info = play_infos(&mut conn, vec![playground])?
+        .pop()
+        .ok_or(PlaygroundError::NotFound)?;
+    Ok(HttpResponse::Ok().json(PlayRevisionsResponse { info, revisions }))
+}
+
+fn create_playground_with_code(
+    conn: &mut PgConnection,
+    playground: PlaygroundInsert,
+    code: &PlayCode,
+) -> Result<i64, PlaygroundError> {
+    let code = serde_json::to_value(code)?;
+    conn.transaction(|conn| {
+        let id = create_playground(conn, playground)?;
+        create_playground_revision(
+            conn,
+            PlaygroundRevisionInsert {
+                playground_id: id,
+                revision: 1,
+                code,
+            },
+        )?;
+        Ok::<_, diesel::result::Error>(id)
+    })
+    .map_err(Into::into)
+}
+
+/// Playgrounds saved before we tracked them in the database have no row, those are
+/// always visible.
+fn get_visible_playground(
+    conn: &mut PgConnection,
+    gist_id: &str,
+) -> Result<Option<PlaygroundQuery>, PlaygroundError> {
+    match get_playground(conn, gist_id).optional()? {
+        Some(playground) if !playground.active => Err(PlaygroundError::Hidden),
+        playground => Ok(playground),
+    }
+}
+
+fn get_owned_playground(
+    conn: &mut PgConnection,
+    gist_id: &str,
+    user_id: i64,
+) -> Result<PlaygroundQuery, PlaygroundError> {
+    let playground = get_playground(conn, gist_id)
+        .optional()?
+        .ok_or(PlaygroundError::NotFound)?;
+    if playground.user_id != Some(user_id) {
+        return Err(PlaygroundError::NotPlaygroundOwner);
+    }
+    Ok(playground)
+}
+
+fn play_infos(
+    conn: &mut PgConnection,
+    playgrounds: Vec<PlaygroundQuery>,
+) -> Result<Vec<PlayInfo>, PlaygroundError> {
+    let parent_ids: Vec<i64> = playgrounds.iter().filter_map(|p| p.parent_id).collect();
+    let parents: HashMap<i64, String> = get_playground_gists(conn, &parent_ids)?
+        .into_iter()
+        .collect();
+    playgrounds
+        .into_iter()
+        .map(|playground| {
+            Ok(PlayInfo {
+                id: encrypt(&playground.gist)?,
+                title: playground.title,
+                revision: playground.revision,
+                forked_from: playground
+                    .parent_id
+                    .and_then(|parent_id| parents.get(&parent_id))
+                    .map(|gist| encrypt(gist))
+                    .transpose()?,
+                created_at: playground.created_at,
+                updated_at: playground.updated_at,
+            })
+        })
+        .collect()
+}
+
+/// Flags of playgrounds with a row go to the moderation queue. Playgrounds saved before
+/// we tracked them only have a gist, those are flagged by filing an issue.
 pub async fn flag(
     flag: web::Json<PlayFlagRequest>,
     pool: web::Data<Pool>,
     github_flags_client: web::Data<GithubFlagsClient>,
 ) -> Result<HttpResponse, ApiError> {
-    if let Some(client) = &github_flags_client.0 {
-        let PlayFlagRequest { id, reason } = flag.into_inner();
-        let gist_id = decrypt(&id)?;
+    let PlayFlagRequest { id, reason } = flag.into_inner();
+    let gist_id = decrypt(&id)?;
+    let queued = {
         let mut conn = pool.get()?;
-        flag_playground(&mut conn, &gist_id)?;
-        create_flag_issue(client, gist_id, id, reason).await?;
-        Ok(HttpResponse::Created().finish())
-    } else {
-        Ok(HttpResponse::NotImplemented().finish())
+        flag_playground(&mut conn, &gist_id, reason.clone())
+            .optional()?
+            .is_some()
+    };
+    match &github_flags_client.0 {
+        Some(client) => {
+            create_flag_issue(client, gist_id, id, reason).await?;
+            Ok(HttpResponse::Created().finish())
+        }
+        None if queued => Ok(HttpResponse::Created().finish()),
+        None => Ok(HttpResponse::NotImplemented().finish()),
     }
 }
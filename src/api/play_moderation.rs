@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use actix_web::{web, HttpResponse};
+use chrono::NaiveDateTime;
+use diesel::OptionalExtension;
+use serde::Serialize;
+
+use crate::{
+    api::{
+        error::{ApiError, PlaygroundError},
+        play::{encrypt, PlayCode},
+        play_storage::PlaygroundStorage,
+    },
+    db::{
+        model::{PlaygroundFlagQuery, PlaygroundQuery},
+        play::{
+            delete_playground, get_flagged_playgrounds, get_playground_by_id, get_playground_flags,
+            hide_playground, restore_playground,
+        },
+        Pool,
+    },
+    helpers::to_utc,
+};
+
+#[derive(Serialize)]
+pub struct PlaygroundFlag {
+    reason: Option<String>,
+    #[serde(serialize_with = "to_utc")]
+    created_at: NaiveDateTime,
+}
+
+#[derive(Serialize)]
+pub struct FlaggedPlayground {
+    id: i64,
+    share_id: String,
+    title: Option<String>,
+    hidden: bool,
+    flagged: bool,
+    flags: Vec<PlaygroundFlag>,
+}
+
+#[derive(Serialize)]
+pub struct PlaygroundPreview {
+    #[serde(flatten)]
+    playground: FlaggedPlayground,
+    code: PlayCode,
+}
+
+fn flagged_playground(
+    playground: PlaygroundQuery,
+    flags: Vec<PlaygroundFlagQuery>,
+) -> Result<FlaggedPlayground, PlaygroundError> {
+    Ok(FlaggedPlayground {
+        id: playground.id,
+        share_id: encrypt(&playground.gist)?,
+        title: playground.title,
+        hidden: !playground.active,
+        flagged: playground.flagged,
+        flags: flags
+            .into_iter()
+            .map(|flag| PlaygroundFlag {
+                reason: flag.reason,
+                created_at: flag.created_at,
+            })
+            .collect(),
+    })
+}
+
+pub async fn list_flagged_playgrounds(pool: web::Data<Pool>) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    let playgrounds = get_flagged_playgrounds(&mut conn)?;
+    let ids: Vec<i64> = playgrounds.iter().map(|p| p.id).collect();
+    let mut flags: HashMap<i64, Vec<PlaygroundFlagQuery>> = HashMap::new();
+    for flag in get_playground_flags(&mut conn, &ids)? {
+        flags.entry(flag.playground_id).or_default().push(flag);
+    }
+    let res = playgrounds
+        .into_iter()
+        .map(|playground| {
+            let flags = flags.remove(&playground.id).unwrap_or_default();
+            flagged_playground(playground, flags)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(HttpResponse::Ok().json(res))
+}
+
+pub async fn preview_playground(
+    id: web::Path<i64>,
+    pool: web::Data<Pool>,
+    playground_storage: web::Data<PlaygroundStorage>,
+) -> Result<HttpResponse, ApiError> {
+    let storage = playground_storage
+        .0
+        .as_ref()
+        .ok_or(ApiError::NotImplemented)?;
+    let (playground, flags) = {
+        let mut conn = pool.get()?;
+        let playground = get_playground_by_id(&mut conn, id.into_inner())
+            .optional()?
+            .ok_or(PlaygroundError::NotFound)?;
+        let flags = get_playground_flags(&mut conn, &[playground.id])?;
+        (playground, flags)
+    };
+    let code = storage.load(&playground.gist).await?;
+    Ok(HttpResponse::Ok().json(PlaygroundPreview {
+        playground: flagged_playground(playground, flags)?,
+        code,
+    }))
+}
+
+pub async fn hide_flagged_playground(
+    id: web::Path<i64>,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    match hide_playground(&mut conn, id.into_inner())? {
+        0 => Err(PlaygroundError::NotFound.into()),
+        _ => Ok(HttpResponse::Ok().finish()),
+    }
+}
+
+pub async fn restore_flagged_playground(
+    id: web::Path<i64>,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    match restore_playground(&mut conn, id.into_inner())? {
+        0 => Err(PlaygroundError::NotFound.into()),
+        _ => Ok(HttpResponse::Ok().finish()),
+    }
+}
+
+pub async fn delete_flagged_playground(
+    id: web::Path<i64>,
+    pool: web::Data<Pool>,
+    playground_storage: web::Data<PlaygroundStorage>,
+) -> Result<HttpResponse, ApiError> {
+    let storage = playground_storage
+        .0
+        .as_ref()
+        .ok_or(ApiError::NotImplemented)?;
+    let playground = {
+        let mut conn = pool.get()?;
+        get_playground_by_id(&mut conn, id.into_inner())
+            .optional()?
+            .ok_or(PlaygroundError::NotFound)?
+    };
+    storage.delete(&playground.gist).await?;
+    let mut conn = pool.get()?;
+    delete_playground(&mut conn, playground.id)?;
+    Ok(HttpResponse::Ok().finish())
+}
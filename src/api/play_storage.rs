@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+use octocrab::Octocrab;
+use uuid::Uuid;
+
+use crate::{
+    api::{
+        error::PlaygroundError,
+        play::{create_gist, gist_code, load_gist, load_raw_gist, update_gist, PlayCode},
+    },
+    db::{
+        model::PlaygroundCodeInsert,
+        play::{
+            create_playground_code, delete_playground_code, get_gists_without_code,
+            get_playground_code, upsert_playground_code,
+        },
+        Pool,
+    },
+    settings::PlaygroundStorageBackend,
+};
+
+/// Backend that persists the code of shared playgrounds.
+///
+/// The id returned by `save` is what ends up (encrypted) in share links, so
+/// backends must keep accepting every id they ever handed out.
+#[async_trait]
+pub trait PlayStorage: Send + Sync {
+    async fn save(&self, code: &PlayCode) -> Result<String, PlaygroundError>;
+    async fn load(&self, id: &str) -> Result<PlayCode, PlaygroundError>;
+    async fn update(&self, id: &str, code: &PlayCode) -> Result<(), PlaygroundError>;
+    async fn delete(&self, id: &str) -> Result<(), PlaygroundError>;
+}
+
+pub struct PlaygroundStorage(pub Option<Box<dyn PlayStorage>>);
+
+impl PlaygroundStorage {
+    pub fn new(
+        backend: Option<PlaygroundStorageBackend>,
+        pool: &Pool,
+        gist_client: Option<&Octocrab>,
+    ) -> Self {
+        let storage: Option<Box<dyn PlayStorage>> = match backend {
+            Some(PlaygroundStorageBackend::Gist) => gist_client
+                .cloned()
+                .map(|client| Box::new(GistStorage(client)) as Box<dyn PlayStorage>),
+            Some(PlaygroundStorageBackend::Postgres) => Some(Box::new(PostgresStorage {
+                pool: pool.clone(),
+                gist_fallback: gist_client.cloned(),
+            })),
+            None => None,
+        };
+        PlaygroundStorage(storage)
+    }
+}
+
+pub struct GistStorage(pub Octocrab);
+
+#[async_trait]
+impl PlayStorage for GistStorage {
+    async fn save(&self, code: &PlayCode) -> Result<String, PlaygroundError> {
+        let gist = create_gist(&self.0, serde_json::to_string_pretty(code)?).await?;
+        Ok(gist.id)
+    }
+
+    async fn load(&self, id: &str) -> Result<PlayCode, PlaygroundError> {
+        Ok(load_gist(&self.0, id).await?.code)
+    }
+
+    async fn update(&self, id: &str, code: &PlayCode) -> Result<(), PlaygroundError> {
+        update_gist(&self.0, id, serde_json::to_string_pretty(code)?).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), PlaygroundError> {
+        Ok(self.0.gists().delete(id).await?)
+    }
+}
+
+/// Stores playgrounds in the `playground_code` table.
+///
+/// Ids have the same shape as gist ids. Gists which have not been migrated yet
+/// are read from GitHub if a gist client is configured.
+pub struct PostgresStorage {
+    pub pool: Pool,
+    pub gist_fallback: Option<Octocrab>,
+}
+
+#[async_trait]
+impl PlayStorage for PostgresStorage {
+    async fn save(&self, code: &PlayCode) -> Result<String, PlaygroundError> {
+        let id = Uuid::new_v4().simple().to_string();
+        let mut conn = self.pool.get()?;
+        create_playground_code(
+            &mut conn,
+            PlaygroundCodeInsert {
+                gist: id.clone(),
+                code: serde_json::to_value(code)?,
+            },
+        )?;
+        Ok(id)
+    }
+
+    async fn load(&self, id: &str) -> Result<PlayCode, PlaygroundError> {
+        let code = {
+            let mut conn = self.pool.get()?;
+            get_playground_code(&mut conn, id)?
+        };
+        match (code, &self.gist_fallback) {
+            (Some(code), _) => Ok(PlayCode::from_value(code)?),
+            (None, Some(client)) => Ok(load_gist(client, id).await?.code),
+            (None, None) => Err(PlaygroundError::NotFound),
+        }
+    }
+
+    async fn update(&self, id: &str, code: &PlayCode) -> Result<(), PlaygroundError> {
+        let mut conn = self.pool.get()?;
+        upsert_playground_code(
+            &mut conn,
+            PlaygroundCodeInsert {
+                gist: id.to_string(),
+                code: serde_json::to_value(code)?,
+            },
+        )?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), PlaygroundError> {
+        {
+            let mut conn = self.pool.get()?;
+            delete_playground_code(&mut conn, id)?;
+        }
+        // Migrated gists still exist on GitHub.
+        if let Some(client) = &self.gist_fallback {
+            if let Err(e) = client.gists().delete(id).await {
+                warn!("Unable to delete gist {}: {}", id, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a gist migration run.
+#[derive(Debug, Default)]
+pub struct GistMigration {
+    pub migrated: usize,
+    /// Gists which could not be loaded or hold no valid playground code, with the
+    /// reason. They stay on GitHub and are retried by the next run.
+    pub failed: Vec<(String, PlaygroundError)>,
+}
+
+/// Copies all gists which are not yet in `playground_code` into the database.
+pub async fn migrate_gists(
+    pool: &Pool,
+    client: &Octocrab,
+) -> Result<GistMigration, PlaygroundError> {
+    let gists = {
+        let mut conn = pool.get()?;
+        get_gists_without_code(&mut conn)?
+    };
+    info!("Migrating {} gists", gists.len());
+    let mut result = GistMigration::default();
+    for gist_id in gists {
+        let code = match load_raw_gist(client, &gist_id)
+            .await
+            .and_then(|gist| gist_code(&gist))
+        {
+            Ok(code) => code,
+            Err(e) => {
+                warn!("Unable to migrate gist {}: {}", gist_id, e);
+                result.failed.push((gist_id, e));
+                continue;
+            }
+        };
+        let mut conn = pool.get()?;
+        result.migrated += create_playground_code(
+            &mut conn,
+            PlaygroundCodeInsert {
+                gist: gist_id,
+                code: serde_json::to_value(&code)?,
+            },
+        )?;
+    }
+    info!(
+        "Migrated {} gists, {} failed",
+        result.migrated,
+        result.failed.len()
+    );
+    Ok(result)
+}
@@ -1,39 +1,126 @@
+use crate::ai::embeddings::{get_search_docs, SearchDoc};
 use crate::api::elastic;
 use crate::api::error::{ApiError, SearchError};
-use crate::settings::SETTINGS;
+use crate::api::search_analytics::{self, SearchEvent};
+use crate::api::search_cursor::Cursor;
+use crate::api::search_query::{SearchQuery, Term};
+use crate::api::search_ranking;
+use crate::api::search_resilience::{ResponseCache, ELASTIC_BREAKER};
+use crate::db::{Pool, SupaPool};
+use crate::settings::{RankingProfile, SETTINGS};
 use actix_web::http::header::{CacheControl, CacheDirective};
 use actix_web::{web, HttpRequest, HttpResponse};
+use async_openai::{config::OpenAIConfig, Client};
 use elasticsearch::http::response::Response as ElasticResponse;
-use elasticsearch::{CountParts, Elasticsearch, SearchParts};
+use elasticsearch::{Elasticsearch, SearchParts};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::time::Instant;
 
-// TODO: add retry logic from kuma
+/// Documentation areas we return facet counts for, derived from the slug prefix.
+const AREAS: &[&str] = &[
+    "Web/API",
+    "Web/CSS",
+    "Web/HTML",
+    "Web/JavaScript",
+    "Web/SVG",
+    "Web/HTTP",
+    "Web/Accessibility",
+    "Web/MathML",
+    "Web/Media",
+    "WebAssembly",
+    "Mozilla/Add-ons",
+    "Glossary",
+    "Learn",
+];
 
-#[derive(Serialize)]
+static FALLBACK_CACHE: Lazy<ResponseCache<SearchResponse>> =
+    Lazy::new(|| ResponseCache::new(SETTINGS.search.fallback_cache_size));
+
+#[derive(Serialize, Clone)]
 struct SearchResponse {
     documents: Vec<Document>,
     metadata: Metadata,
     suggestions: Vec<Suggestion>,
+    facets: Facets,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone, Default)]
+struct Facets {
+    area: Vec<Facet>,
+    page_type: Vec<Facet>,
+    status: Vec<Facet>,
+}
+
+#[derive(Serialize, Clone)]
+struct Facet {
+    value: String,
+    count: u64,
+}
+
+impl From<elastic::ResponseAggregations> for Facets {
+    fn from(aggregations: elastic::ResponseAggregations) -> Self {
+        let mut areas = aggregations.area.buckets;
+        let terms = |aggregation: elastic::ResponseTermsAggregation| {
+            aggregation
+                .buckets
+                .into_iter()
+                .map(|bucket| Facet {
+                    value: bucket.key,
+                    count: bucket.doc_count,
+                })
+                .collect()
+        };
+        Facets {
+            area: AREAS
+                .iter()
+                .filter_map(|area| {
+                    areas
+                        .remove(*area)
+                        .filter(|bucket| bucket.doc_count > 0)
+                        .map(|bucket| Facet {
+                            value: area.to_string(),
+                            count: bucket.doc_count,
+                        })
+                })
+                .collect(),
+            page_type: terms(aggregations.page_type),
+            status: terms(aggregations.status),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
 struct Suggestion {
     text: String,
     total: elastic::ResponseTotal,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 struct Metadata {
     took_ms: u64,
     size: u64,
     page: u64,
     total: elastic::ResponseTotal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    degraded: Option<Degradation>,
+    /// Cursor for the next page, not limited to the first 10 pages like `page`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Degradation {
+    /// Elasticsearch failed, this is the last good response for the same query.
+    Stale,
+}
+
+#[derive(Serialize, Clone)]
 struct Document {
     mdn_url: String,
     score: f64,
@@ -45,7 +132,7 @@ struct Document {
     highlight: elastic::ResponseHighlight,
 }
 
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 #[serde(rename_all = "lowercase")]
 enum Sort {
     #[default]
@@ -54,21 +141,89 @@ enum Sort {
     Popularity,
 }
 
+#[derive(Deserialize, Serialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+    #[default]
+    Lexical,
+    Semantic,
+    Hybrid,
+}
+
 #[derive(Deserialize)]
 struct Params {
     q: String,
     #[serde(default)]
     sort: Sort,
+    #[serde(default)]
+    mode: Mode,
+    /// Set by the client when the search was started by clicking a suggestion.
+    #[serde(default)]
+    from_suggestion: bool,
     #[serde(default = "default_page")]
     page: u64,
+    cursor: Option<String>,
+    #[serde(skip)]
+    query: SearchQuery,
+    #[serde(skip)]
+    search_after: Option<Vec<Value>>,
     #[serde(skip)]
     locale: Vec<elastic::Locale>,
+    #[serde(skip)]
+    area: Vec<String>,
+    #[serde(skip)]
+    page_type: Vec<String>,
+    #[serde(skip)]
+    status: Vec<elastic::BcdStatus>,
 }
 
 fn default_page() -> u64 {
     1
 }
 
+fn locale_code(locale: &elastic::Locale) -> Option<String> {
+    match serde_json::to_value(locale) {
+        Ok(Value::String(locale)) => Some(locale),
+        _ => None,
+    }
+}
+
+impl Sort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Sort::Best => "best",
+            Sort::Relevance => "relevance",
+            Sort::Popularity => "popularity",
+        }
+    }
+}
+
+impl Params {
+    /// Identifies the query regardless of the page, ignoring case and repeated whitespace.
+    fn scope(&self) -> String {
+        let q = self
+            .q
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        serde_json::to_string(&(
+            q,
+            &self.sort,
+            &self.locale,
+            &self.area,
+            &self.page_type,
+            &self.status,
+        ))
+        .unwrap_or_default()
+    }
+
+    /// Identifies the query and page for the fallback cache.
+    fn cache_key(&self) -> String {
+        serde_json::to_string(&(self.scope(), self.page, &self.search_after)).unwrap_or_default()
+    }
+}
+
 impl FromStr for Params {
     type Err = SearchError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -95,6 +250,8 @@ impl FromStr for Params {
             });
         }
 
+        params.query = SearchQuery::parse(&params.q)?;
+
         match params.page {
             x if x < 1 => {
                 return Err(SearchError::Query {
@@ -111,32 +268,140 @@ impl FromStr for Params {
             _ => (),
         }
 
-        for value in web::Query::<Vec<(String, String)>>::from_query(s)
+        params.locale = parse_locales(s)?;
+        let locales = params.query.locales();
+        if !locales.is_empty() {
+            params.locale = locales;
+        }
+        for (key, value) in web::Query::<Vec<(String, String)>>::from_query(s)
             .unwrap_or_else(|_| web::Query(vec![]))
             .iter()
-            .filter_map(
-                |(key, value)| {
-                    if key == "locale" {
-                        Some(value)
-                    } else {
-                        None
-                    }
-                },
-            )
         {
-            params.locale.push(
-                value
-                    .to_lowercase()
-                    .parse::<elastic::Locale>()
-                    .map_err(|e| SearchError::Query {
-                        key: "locale".to_string(),
-                        message: e.to_string(),
-                    })?,
-            );
+            match key.as_str() {
+                "area" => {
+                    let area = value.trim_matches('/');
+                    if area.is_empty() {
+                        return Err(SearchError::Query {
+                            key: "area".to_string(),
+                            message: "Ensure this value is not empty.".to_string(),
+                        });
+                    }
+                    params.area.push(area.to_string());
+                }
+                "page_type" => params.page_type.push(value.to_lowercase()),
+                "status" => {
+                    params
+                        .status
+                        .push(
+                            value
+                                .to_lowercase()
+                                .parse::<elastic::BcdStatus>()
+                                .map_err(|e| SearchError::Query {
+                                    key: "status".to_string(),
+                                    message: e.to_string(),
+                                })?,
+                        )
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(token) = &params.cursor {
+            if params.mode != Mode::Lexical {
+                return Err(SearchError::Query {
+                    key: "cursor".to_string(),
+                    message: "Cursors are only supported in lexical mode.".to_string(),
+                });
+            }
+            let cursor = Cursor::decode(token, &params.scope())?;
+            params.page = cursor.page;
+            params.search_after = Some(cursor.search_after);
+        }
+
+        Ok(params)
+    }
+}
+
+fn parse_locales(s: &str) -> Result<Vec<elastic::Locale>, SearchError> {
+    let mut locales = web::Query::<Vec<(String, String)>>::from_query(s)
+        .unwrap_or_else(|_| web::Query(vec![]))
+        .iter()
+        .filter(|(key, _)| key == "locale")
+        .map(|(_, value)| {
+            value
+                .to_lowercase()
+                .parse::<elastic::Locale>()
+                .map_err(|e| SearchError::Query {
+                    key: "locale".to_string(),
+                    message: e.to_string(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if locales.is_empty() {
+        locales.push(elastic::Locale::English);
+    }
+    Ok(locales)
+}
+
+#[derive(Serialize)]
+struct AutocompleteResponse {
+    suggestions: Vec<AutocompleteSuggestion>,
+}
+
+#[derive(Serialize)]
+struct AutocompleteSuggestion {
+    mdn_url: String,
+    title: String,
+    locale: elastic::Locale,
+    slug: String,
+}
+
+#[derive(Deserialize)]
+struct AutocompleteParams {
+    q: String,
+    #[serde(default = "default_autocomplete_size")]
+    size: u64,
+    #[serde(skip)]
+    locale: Vec<elastic::Locale>,
+}
+
+fn default_autocomplete_size() -> u64 {
+    5
+}
+
+impl FromStr for AutocompleteParams {
+    type Err = SearchError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params: AutocompleteParams = serde_path_to_error::deserialize(
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(s.as_bytes())),
+        )
+        .map_err(|e| SearchError::Query {
+            key: e.path().to_string(),
+            message: e.inner().to_string(),
+        })?;
+
+        if params.q.trim().is_empty() {
+            return Err(SearchError::Query {
+                key: "q".to_string(),
+                message: "Ensure this value is not empty.".to_string(),
+            });
+        }
+        if params.q.len() > SETTINGS.search.query_max_length {
+            return Err(SearchError::Query {
+                key: "q".to_string(),
+                message: format!(
+                    "Ensure this value is less than or equal to {}.",
+                    SETTINGS.search.query_max_length
+                ),
+            });
         }
-        if params.locale.is_empty() {
-            params.locale.push(elastic::Locale::English);
+        if !(1..=10).contains(&params.size) {
+            return Err(SearchError::Query {
+                key: "size".to_string(),
+                message: "Ensure this value is between 1 and 10.".to_string(),
+            });
         }
+        params.locale = parse_locales(s)?;
 
         Ok(params)
     }
@@ -145,11 +410,87 @@ impl FromStr for Params {
 pub async fn search(
     request: HttpRequest,
     client: web::Data<Elasticsearch>,
+    openai_client: web::Data<Option<Client<OpenAIConfig>>>,
+    supabase_pool: web::Data<Option<SupaPool>>,
+    pool: web::Data<Pool>,
 ) -> Result<HttpResponse, ApiError> {
     let params: Params = request.query_string().parse()?;
 
+    let response = match params.mode {
+        Mode::Lexical => lexical_search_with_fallback(&client, &params).await?,
+        Mode::Semantic | Mode::Hybrid => {
+            let (Some(openai_client), Some(supabase_pool)) = (&**openai_client, &**supabase_pool)
+            else {
+                return Err(ApiError::NotImplemented);
+            };
+            let semantic = semantic_search(openai_client, supabase_pool, &params).await?;
+            if params.mode == Mode::Hybrid {
+                fuse(
+                    lexical_search_with_fallback(&client, &params).await?,
+                    semantic,
+                )
+            } else {
+                semantic
+            }
+        }
+    };
+    if search_analytics::sampled() {
+        search_analytics::record(
+            &pool,
+            SearchEvent {
+                query: &params.q,
+                locales: params.locale.iter().filter_map(locale_code).collect(),
+                sort: params.sort.as_str(),
+                page: params.page,
+                total_hits: response.metadata.total.value,
+                took_ms: response.metadata.took_ms,
+                suggestion_shown: !response.suggestions.is_empty(),
+                from_suggestion: params.from_suggestion,
+            },
+        );
+    }
+    let cache_control = if response.metadata.degraded.is_some() {
+        CacheControl(vec![CacheDirective::NoStore])
+    } else {
+        CacheControl(vec![CacheDirective::MaxAge(SETTINGS.search.cache_max_age)])
+    };
+    Ok(HttpResponse::Ok()
+        .insert_header(cache_control)
+        .json(response))
+}
+
+/// Remembers successful responses and serves them, marked as stale, when
+/// Elasticsearch fails or the circuit breaker is open.
+async fn lexical_search_with_fallback(
+    client: &Elasticsearch,
+    params: &Params,
+) -> Result<SearchResponse, ApiError> {
+    let key = params.cache_key();
+    let locales: Vec<String> = params.locale.iter().filter_map(locale_code).collect();
+    let profile = search_ranking::profile_for_locales(locales.iter().map(String::as_str));
+    match lexical_search(client, params, &profile).await {
+        Ok(response) => {
+            FALLBACK_CACHE.insert(key, response.clone());
+            Ok(response)
+        }
+        Err(e) => match FALLBACK_CACHE.get(&key) {
+            Some(mut response) => {
+                warn!("serving stale search response: {}", e);
+                response.metadata.degraded = Some(Degradation::Stale);
+                Ok(response)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+async fn lexical_search(
+    client: &Elasticsearch,
+    params: &Params,
+    profile: &RankingProfile,
+) -> Result<SearchResponse, ApiError> {
     let search_response: elastic::SearchResponse =
-        match parse_or_get_error_reason(do_search(&client, &params).await).await {
+        match parse_or_get_error_reason(do_search(client, params, profile).await).await {
             Ok(x) => x,
             Err(e) => {
                 error!("{}", e);
@@ -157,6 +498,18 @@ pub async fn search(
             }
         };
 
+    let next = search_response
+        .hits
+        .hits
+        .last()
+        .filter(|hit| search_response.hits.hits.len() == 10 && !hit.sort.is_empty())
+        .and_then(|hit| {
+            Cursor {
+                page: params.page + 1,
+                search_after: hit.sort.clone(),
+            }
+            .encode(&params.scope())
+        });
     let response = SearchResponse {
         documents: search_response
             .hits
@@ -178,13 +531,157 @@ pub async fn search(
             total: search_response.hits.total,
             size: 10,
             page: params.page,
+            degraded: None,
+            next,
         },
-        suggestions: match search_response.suggest {
-            Some(x) => get_suggestion(x, &client, &params.locale)
-                .await
-                .unwrap_or_default(),
-            None => vec![],
+        suggestions: search_response
+            .suggest
+            .map(get_suggestions)
+            .unwrap_or_default(),
+        facets: search_response
+            .aggregations
+            .map(Into::into)
+            .unwrap_or_default(),
+    };
+    Ok(response)
+}
+
+/// Nearest neighbours of the query embedding. There are no facets or suggestions in
+/// this mode, and the facet filters are not applied.
+async fn semantic_search(
+    openai_client: &Client<OpenAIConfig>,
+    supabase_pool: &SupaPool,
+    params: &Params,
+) -> Result<SearchResponse, ApiError> {
+    let start = Instant::now();
+    let url_patterns: Vec<String> = params
+        .locale
+        .iter()
+        .filter_map(locale_code)
+        .map(|locale| format!("/{locale}/docs/%"))
+        .collect();
+    let offset = 10 * (params.page - 1);
+    let docs = get_search_docs(
+        openai_client,
+        supabase_pool,
+        &params.query.plain_text(),
+        &url_patterns,
+        10,
+        offset as i64,
+    )
+    .await?;
+    let found = docs.len() as u64;
+    Ok(SearchResponse {
+        documents: docs.into_iter().map(Into::into).collect(),
+        metadata: Metadata {
+            took_ms: start.elapsed().as_millis() as u64,
+            size: 10,
+            page: params.page,
+            total: elastic::ResponseTotal {
+                value: offset + found,
+                relation: if found < 10 {
+                    elastic::ResponseTotalRelation::Equal
+                } else {
+                    elastic::ResponseTotalRelation::GreaterThanOrEqual
+                },
+            },
+            degraded: None,
+            // Cursors are rejected outside of lexical mode, see `Params::from_str`.
+            next: None,
         },
+        suggestions: vec![],
+        facets: Facets::default(),
+    })
+}
+
+impl From<SearchDoc> for Document {
+    fn from(doc: SearchDoc) -> Self {
+        let (locale, slug) = doc
+            .url
+            .trim_start_matches('/')
+            .split_once("/docs/")
+            .unwrap_or_default();
+        Document {
+            locale: locale
+                .to_lowercase()
+                .parse()
+                .unwrap_or(elastic::Locale::English),
+            slug: slug.to_lowercase(),
+            mdn_url: doc.url,
+            score: 1.0 - doc.similarity,
+            title: doc.title,
+            popularity: 0.0,
+            summary: String::default(),
+            highlight: elastic::ResponseHighlight {
+                body: vec![],
+                title: vec![],
+            },
+        }
+    }
+}
+
+const RRF_K: f64 = 60.0;
+
+/// Merges the lexical and semantic results of the same page with reciprocal rank
+/// fusion. Documents found by both keep the lexical version (with highlights and
+/// summary). The metadata, suggestions and facets are the lexical ones.
+fn fuse(lexical: SearchResponse, semantic: SearchResponse) -> SearchResponse {
+    let mut fused: HashMap<String, (f64, Document)> = HashMap::new();
+    for documents in [lexical.documents, semantic.documents] {
+        for (rank, document) in documents.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            fused
+                .entry(document.mdn_url.to_lowercase())
+                .and_modify(|(fused_score, _)| *fused_score += score)
+                .or_insert((score, document));
+        }
+    }
+    let mut documents: Vec<Document> = fused
+        .into_values()
+        .map(|(score, document)| Document { score, ..document })
+        .collect();
+    documents.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    documents.truncate(10);
+    SearchResponse {
+        documents,
+        metadata: Metadata {
+            took_ms: lexical.metadata.took_ms + semantic.metadata.took_ms,
+            // Cursors are rejected outside of lexical mode, see `Params::from_str`.
+            next: None,
+            ..lexical.metadata
+        },
+        suggestions: lexical.suggestions,
+        facets: lexical.facets,
+    }
+}
+
+pub async fn suggest(
+    request: HttpRequest,
+    client: web::Data<Elasticsearch>,
+) -> Result<HttpResponse, ApiError> {
+    let params: AutocompleteParams = request.query_string().parse()?;
+
+    let autocomplete_response: elastic::AutocompleteResponse =
+        match parse_or_get_error_reason(do_autocomplete(&client, &params).await).await {
+            Ok(x) => x,
+            Err(e) => {
+                error!("{}", e);
+                return Err(e.into());
+            }
+        };
+
+    let response = AutocompleteResponse {
+        suggestions: autocomplete_response
+            .hits
+            .hits
+            .into_iter()
+            .map(|hit| AutocompleteSuggestion {
+                mdn_url: hit._id,
+                title: hit._source.title,
+                locale: hit._source.locale,
+                slug: hit._source.slug,
+            })
+            .collect(),
     };
     Ok(HttpResponse::Ok()
         .insert_header(CacheControl(vec![CacheDirective::MaxAge(
@@ -193,11 +690,171 @@ pub async fn search(
         .json(response))
 }
 
+#[derive(Deserialize)]
+struct CompareProfiles {
+    #[serde(default = "default_compare_profile")]
+    a: String,
+    b: String,
+}
+
+fn default_compare_profile() -> String {
+    SETTINGS.search.ranking.profile.clone()
+}
+
+#[derive(Serialize)]
+struct RankingComparison {
+    a: ProfileRanking,
+    b: ProfileRanking,
+}
+
+#[derive(Serialize)]
+struct ProfileRanking {
+    profile: String,
+    total: elastic::ResponseTotal,
+    documents: Vec<RankedDocument>,
+}
+
+#[derive(Serialize)]
+struct RankedDocument {
+    rank: usize,
+    mdn_url: String,
+    title: String,
+    score: f64,
+    /// Rank with the other profile, if the document is on the same page there.
+    other_rank: Option<usize>,
+}
+
+fn ranking(profile: String, response: SearchResponse, other: &SearchResponse) -> ProfileRanking {
+    ProfileRanking {
+        profile,
+        total: response.metadata.total,
+        documents: response
+            .documents
+            .into_iter()
+            .enumerate()
+            .map(|(i, document)| RankedDocument {
+                rank: i + 1,
+                other_rank: other
+                    .documents
+                    .iter()
+                    .position(|x| x.mdn_url == document.mdn_url)
+                    .map(|j| j + 1),
+                mdn_url: document.mdn_url,
+                title: document.title,
+                score: document.score,
+            })
+            .collect(),
+    }
+}
+
+/// Runs the same search with two ranking profiles, `a` defaults to the configured one,
+/// so relevance changes can be compared before rolling them out.
+pub async fn compare_ranking_profiles(
+    request: HttpRequest,
+    client: web::Data<Elasticsearch>,
+) -> Result<HttpResponse, ApiError> {
+    let params: Params = request.query_string().parse()?;
+    let profiles =
+        web::Query::<CompareProfiles>::from_query(request.query_string()).map_err(|e| {
+            SearchError::Query {
+                key: "b".to_string(),
+                message: e.to_string(),
+            }
+        })?;
+    let profile = |key: &str, name: &str| {
+        search_ranking::profile(name).ok_or_else(|| SearchError::Query {
+            key: key.to_string(),
+            message: format!("Unknown ranking profile '{name}'."),
+        })
+    };
+    let a = profile("a", &profiles.a)?;
+    let b = profile("b", &profiles.b)?;
+
+    let a_response = lexical_search(&client, &params, &a).await?;
+    let b_response = lexical_search(&client, &params, &b).await?;
+    let comparison = RankingComparison {
+        a: ranking(profiles.a.clone(), a_response.clone(), &b_response),
+        b: ranking(profiles.b.clone(), b_response, &a_response),
+    };
+    Ok(HttpResponse::Ok().json(comparison))
+}
+
+/// Urls on the first page of a lexical search with the given ranking profile, for
+/// evaluating ranking changes offline. Takes the same query string as the search endpoint.
+pub async fn search_urls(
+    client: &Elasticsearch,
+    query_string: &str,
+    profile: &RankingProfile,
+) -> Result<Vec<String>, ApiError> {
+    let params: Params = query_string.parse()?;
+    let search_response: elastic::SearchResponse =
+        parse_or_get_error_reason(do_search(client, &params, profile).await).await?;
+    Ok(search_response
+        .hits
+        .hits
+        .into_iter()
+        .map(|hit| hit._id)
+        .collect())
+}
+
+/// Completes on titles only, with a `bool_prefix` query so the last (partial) term is
+/// matched as a prefix. Popularity breaks ties like in the "best" sort of the full search.
+async fn do_autocomplete(
+    client: &Elasticsearch,
+    params: &AutocompleteParams,
+) -> Result<ElasticResponse, SearchError> {
+    let query = elastic::Query::MultiMatch(elastic::QueryMultiMatch {
+        query: params.q.trim().to_string(),
+        fields: vec![elastic::Field::Title],
+        kind: Some(elastic::MultiMatchType::BoolPrefix),
+    });
+    let body = elastic::Autocomplete {
+        size: params.size,
+        query: elastic::Query::Bool(elastic::QueryBool {
+            filter: Some(vec![elastic::Query::Terms(elastic::QueryTerms::Locale(
+                params.locale.clone(),
+            ))]),
+            must: Some(vec![elastic::Query::FunctionScore(
+                elastic::QueryFunctionScore {
+                    query: &query,
+                    functions: vec![elastic::QueryFunctionScoreFunction::FieldValueFactor(
+                        elastic::QueryFunctionScoreFunctionFieldValueFactor {
+                            field: elastic::Field::Popularity,
+                            factor: 10.0,
+                            missing: 0,
+                        },
+                    )],
+                    boost_mode: elastic::BoostMode::Sum,
+                    score_mode: elastic::ScoreMode::Max,
+                },
+            )]),
+            ..elastic::QueryBool::default()
+        }),
+        _source: elastic::Source {
+            excludes: vec![elastic::Field::Body, elastic::Field::Summary],
+        },
+    };
+    debug!(
+        "elastic request: {}",
+        serde_json::to_string(&body).unwrap_or_default()
+    );
+    ELASTIC_BREAKER
+        .send(|| {
+            client
+                .search(SearchParts::Index(&["mdn_docs"]))
+                .body(&body)
+                .send()
+        })
+        .await
+}
+
 async fn do_search(
     client: &Elasticsearch,
     params: &Params,
-) -> Result<ElasticResponse, elasticsearch::Error> {
-    let suggest = if params.q.len() > 100 || params.q.split(' ').any(|x| x.len() > 30) {
+    profile: &RankingProfile,
+) -> Result<ElasticResponse, SearchError> {
+    let text = params.query.words();
+    let suggest = if text.is_empty() || text.len() > 100 || text.split(' ').any(|x| x.len() > 30) {
         /*
         If it's a really long query, or a specific word is just too long, you can get those tricky
         TransportError(500, 'search_phase_execution_exception', 'Term too complex:
@@ -206,16 +863,39 @@ async fn do_search(
         None
     } else {
         /*
-        XXX research if it it's better to use phrase suggesters and if that works
-        https://www.elastic.co/guide/en/elasticsearch/reference/current/search-suggesters.html#phrase-suggester
+        The phrase suggester corrects the whole query, and the collate query checks
+        each correction for hits in the requested locales, all in the same round-trip.
         */
         Some(elastic::Suggest {
-            text: params.q.clone(),
-            title_suggestions: elastic::Suggester::Term(elastic::TermSuggester {
-                field: elastic::Field::Title,
-            }),
-            body_suggestions: elastic::Suggester::Term(elastic::TermSuggester {
+            text: text.clone(),
+            phrase_suggestions: elastic::Suggester::Phrase(elastic::PhraseSuggester {
                 field: elastic::Field::Body,
+                size: SETTINGS.search.max_suggestions,
+                direct_generator: vec![
+                    elastic::DirectGenerator {
+                        field: elastic::Field::Title,
+                    },
+                    elastic::DirectGenerator {
+                        field: elastic::Field::Body,
+                    },
+                ],
+                collate: elastic::SuggestCollate {
+                    query: elastic::SuggestCollateQuery {
+                        source: elastic::Query::Bool(elastic::QueryBool {
+                            filter: Some(vec![
+                                elastic::Query::MultiMatch(elastic::QueryMultiMatch {
+                                    query: "{{suggestion}}".to_string(),
+                                    fields: vec![elastic::Field::Title, elastic::Field::Body],
+                                    kind: None,
+                                }),
+                                elastic::Query::Terms(elastic::QueryTerms::Locale(
+                                    params.locale.clone(),
+                                )),
+                            ]),
+                            ..elastic::QueryBool::default()
+                        }),
+                    },
+                },
             }),
         })
     };
@@ -230,40 +910,133 @@ async fn do_search(
      3. Body match-phrase
      4. Body match
 
-    The order is determined by the `boost` number in the code below.
+    The order is determined by the boosts of the ranking profile.
     Remember that sort order is a combination of "match" and popularity, but
     ideally the popularity should complement. Try to get a pretty good
     sort by pure relevance first, and let popularity just make it better.
     */
-    let mut subqueries: Vec<elastic::Query> = vec![
-        elastic::Query::Match(elastic::QueryMatch::Title(elastic::QueryMatchField {
-            query: params.q.clone(),
-            boost: 5.0,
-        })),
-        elastic::Query::Match(elastic::QueryMatch::Body(elastic::QueryMatchField {
-            query: params.q.clone(),
-            boost: 1.0,
-        })),
-    ];
-    if params.q.contains(' ') {
+    let mut subqueries: Vec<elastic::Query> = vec![];
+    if !text.is_empty() {
+        subqueries.push(elastic::Query::Match(elastic::QueryMatch::Title(
+            elastic::QueryMatchField {
+                query: text.clone(),
+                boost: profile.title_boost,
+            },
+        )));
+        subqueries.push(elastic::Query::Match(elastic::QueryMatch::Body(
+            elastic::QueryMatchField {
+                query: text.clone(),
+                boost: profile.body_boost,
+            },
+        )));
+    }
+    if text.contains(' ') {
         subqueries.push(elastic::Query::MatchPhrase(elastic::QueryMatch::Title(
             elastic::QueryMatchField {
-                query: params.q.clone(),
-                boost: 10.0,
+                query: text.clone(),
+                boost: profile.title_phrase_boost,
             },
         )));
         subqueries.push(elastic::Query::MatchPhrase(elastic::QueryMatch::Body(
             elastic::QueryMatchField {
-                query: params.q.clone(),
-                boost: 2.0,
+                query: text.clone(),
+                boost: profile.body_phrase_boost,
             },
         )));
     }
 
-    let subquery = elastic::Query::Bool(elastic::QueryBool {
-        should: Some(subqueries),
-        ..elastic::QueryBool::default()
-    });
+    /*
+    Quoted phrases and `title:` terms are required, the free text words then only
+    have to match at least once. Without operators this is the plain query from above.
+    */
+    let mut must: Vec<elastic::Query> = params
+        .query
+        .phrases()
+        .map(|phrase| {
+            elastic::Query::Bool(elastic::QueryBool {
+                should: Some(vec![
+                    elastic::Query::MatchPhrase(elastic::QueryMatch::Title(
+                        elastic::QueryMatchField {
+                            query: phrase.to_string(),
+                            boost: profile.title_phrase_boost,
+                        },
+                    )),
+                    elastic::Query::MatchPhrase(elastic::QueryMatch::Body(
+                        elastic::QueryMatchField {
+                            query: phrase.to_string(),
+                            boost: profile.body_phrase_boost,
+                        },
+                    )),
+                ]),
+                ..elastic::QueryBool::default()
+            })
+        })
+        .collect();
+    must.extend(params.query.titles().map(|term| match term {
+        Term::Word(word) => {
+            elastic::Query::Match(elastic::QueryMatch::Title(elastic::QueryMatchField {
+                query: word.clone(),
+                boost: profile.title_boost,
+            }))
+        }
+        Term::Phrase(phrase) => {
+            elastic::Query::MatchPhrase(elastic::QueryMatch::Title(elastic::QueryMatchField {
+                query: phrase.clone(),
+                boost: profile.title_phrase_boost,
+            }))
+        }
+    }));
+
+    let subquery = if must.is_empty() {
+        elastic::Query::Bool(elastic::QueryBool {
+            should: Some(subqueries),
+            ..elastic::QueryBool::default()
+        })
+    } else {
+        if !subqueries.is_empty() {
+            must.insert(
+                0,
+                elastic::Query::Bool(elastic::QueryBool {
+                    should: Some(subqueries),
+                    ..elastic::QueryBool::default()
+                }),
+            );
+        }
+        elastic::Query::Bool(elastic::QueryBool {
+            must: Some(must),
+            ..elastic::QueryBool::default()
+        })
+    };
+
+    let mut filter = vec![elastic::Query::Terms(elastic::QueryTerms::Locale(
+        params.locale.clone(),
+    ))];
+    let paths: Vec<elastic::Query> = params
+        .query
+        .paths()
+        .map(|path| elastic::Query::Prefix(elastic::QueryPrefix::Slug(area_prefix(path))))
+        .collect();
+    if !paths.is_empty() {
+        filter.push(elastic::Query::Bool(elastic::QueryBool {
+            should: Some(paths),
+            ..elastic::QueryBool::default()
+        }));
+    }
+    let must_not: Vec<elastic::Query> = params
+        .query
+        .excluded()
+        .map(|term| {
+            let (query, kind) = match term {
+                Term::Word(word) => (word.clone(), None),
+                Term::Phrase(phrase) => (phrase.clone(), Some(elastic::MultiMatchType::Phrase)),
+            };
+            elastic::Query::MultiMatch(elastic::QueryMultiMatch {
+                query,
+                fields: vec![elastic::Field::Title, elastic::Field::Body],
+                kind,
+            })
+        })
+        .collect();
 
     let highlight = elastic::Highlight {
         fields: elastic::HighlightFields {
@@ -277,142 +1050,170 @@ async fn do_search(
         encoder: elastic::HighlightEncoder::HTML,
     };
 
+    /*
+    Every sort ends with the slug as a tiebreaker, so the sort values of the last hit
+    identify a position in the results that `search_after` can continue from.
+    */
+    let (functions, score_mode) = search_ranking::score_functions(profile);
     let (sort, query) = match params.sort {
         Sort::Relevance => (
-            Some(vec![
+            vec![
                 elastic::SortField::Score(elastic::Order::Desc),
                 elastic::SortField::Popularity(elastic::Order::Desc),
-            ]),
+                elastic::SortField::Slug(elastic::Order::Asc),
+            ],
             subquery,
         ),
         Sort::Popularity => (
-            Some(vec![
+            vec![
                 elastic::SortField::Popularity(elastic::Order::Desc),
                 elastic::SortField::Score(elastic::Order::Desc),
-            ]),
+                elastic::SortField::Slug(elastic::Order::Asc),
+            ],
             subquery,
         ),
         Sort::Best => (
-            None,
+            vec![
+                elastic::SortField::Score(elastic::Order::Desc),
+                elastic::SortField::Slug(elastic::Order::Asc),
+            ],
             elastic::Query::FunctionScore(elastic::QueryFunctionScore {
                 query: &subquery,
-                functions: vec![elastic::QueryFunctionScoreFunction::FieldValueFactor(
-                    elastic::QueryFunctionScoreFunctionFieldValueFactor {
-                        field: elastic::Field::Popularity,
-                        factor: 10,
-                        missing: 0,
-                    },
-                )],
+                functions,
                 boost_mode: elastic::BoostMode::Sum,
-                score_mode: elastic::ScoreMode::Max,
+                score_mode,
             }),
         ),
     };
 
     let search_body = elastic::Search {
-        from: 10 * (params.page - 1),
+        from: match params.search_after {
+            Some(_) => 0,
+            None => 10 * (params.page - 1),
+        },
         size: 10,
         _source: elastic::Source {
             excludes: vec![elastic::Field::Body],
         },
-        sort,
+        sort: Some(sort),
+        search_after: params.search_after.clone(),
         query: elastic::Query::Bool(elastic::QueryBool {
-            filter: Some(vec![elastic::Query::Terms(elastic::QueryTerms::Locale(
-                params.locale.clone(),
-            ))]),
+            filter: Some(filter),
             must: Some(vec![query]),
+            must_not: (!must_not.is_empty()).then_some(must_not),
             ..elastic::QueryBool::default()
         }),
         highlight,
         suggest,
+        post_filter: facet_filter(params),
+        aggs: Some(facet_aggregations()),
     };
     debug!(
         "elastic request: {}",
         serde_json::to_string(&search_body).unwrap_or_default()
     );
-    client
-        .search(SearchParts::Index(&["mdn_docs"]))
-        .body(search_body)
-        .send()
+    ELASTIC_BREAKER
+        .send(|| {
+            client
+                .search(SearchParts::Index(&["mdn_docs"]))
+                .body(&search_body)
+                .send()
+        })
         .await
 }
 
-async fn get_suggestion(
-    suggest: elastic::ResponseSuggest,
-    client: &Elasticsearch,
-    locales: &[elastic::Locale],
-) -> Option<Vec<Suggestion>> {
-    let mut options: Vec<elastic::ResponseSuggestionOption> = suggest
-        .body_suggestions
-        .into_iter()
-        .chain(suggest.title_suggestions)
-        .flat_map(|suggestion| suggestion.options)
-        .collect();
-    options.sort_unstable_by(|a, b| {
-        (b.score, b.freq)
-            .partial_cmp(&(a.score, a.freq))
-            .unwrap_or(Ordering::Equal)
-    });
-    for option in options {
-        // Sure, this is different way to spell, but what will it yield if you actually search it?
-        let count = match parse_or_get_error_reason::<elastic::CountResponse>(
-            do_count(client, &option.text, locales).await,
-        )
-        .await
-        {
-            Ok(x) => x.count,
-            Err(e) => {
-                error!("{}", e);
-                continue;
-            }
-        };
-        if count > 0 {
-            /*
-            Since they're sorted by score, it's usually never useful
-            to suggestion more than exactly 1 good suggestion.
-            */
-            return Some(vec![Suggestion {
-                text: option.text,
-                total: elastic::ResponseTotal {
-                    value: count,
-                    relation: elastic::ResponseTotalRelation::Equal,
-                },
-            }]);
-        };
-    }
-    None
+fn area_prefix(area: &str) -> String {
+    format!("{}/", area.to_lowercase())
 }
 
-async fn do_count(
-    client: &Elasticsearch,
-    query: &str,
-    locales: &[elastic::Locale],
-) -> Result<ElasticResponse, elasticsearch::Error> {
-    let body = elastic::Count {
-        query: elastic::Query::Bool(elastic::QueryBool {
-            filter: Some(vec![
-                elastic::Query::MultiMatch(elastic::QueryMultiMatch {
-                    query: query.to_string(),
-                    fields: vec![elastic::Field::Title, elastic::Field::Body],
-                }),
-                elastic::Query::Terms(elastic::QueryTerms::Locale(locales.to_vec())),
-            ]),
+/// Facet filters go into the `post_filter` so the facet counts are not narrowed down
+/// by the facets selected.
+fn facet_filter(params: &Params) -> Option<elastic::Query<'static>> {
+    let mut filter = vec![];
+    if !params.area.is_empty() {
+        filter.push(elastic::Query::Bool(elastic::QueryBool {
+            should: Some(
+                params
+                    .area
+                    .iter()
+                    .map(|area| {
+                        elastic::Query::Prefix(elastic::QueryPrefix::Slug(area_prefix(area)))
+                    })
+                    .collect(),
+            ),
             ..elastic::QueryBool::default()
+        }));
+    }
+    if !params.page_type.is_empty() {
+        filter.push(elastic::Query::Terms(elastic::QueryTerms::PageType(
+            params.page_type.clone(),
+        )));
+    }
+    if !params.status.is_empty() {
+        filter.push(elastic::Query::Terms(elastic::QueryTerms::Status(
+            params.status.clone(),
+        )));
+    }
+    if filter.is_empty() {
+        None
+    } else {
+        Some(elastic::Query::Bool(elastic::QueryBool {
+            filter: Some(filter),
+            ..elastic::QueryBool::default()
+        }))
+    }
+}
+
+fn facet_aggregations() -> elastic::Aggregations<'static> {
+    elastic::Aggregations {
+        area: elastic::Aggregation::Filters(elastic::AggregationFilters {
+            filters: AREAS
+                .iter()
+                .map(|area| {
+                    (
+                        area.to_string(),
+                        elastic::Query::Prefix(elastic::QueryPrefix::Slug(area_prefix(area))),
+                    )
+                })
+                .collect(),
         }),
-    };
-    debug!(
-        "elastic request: {}",
-        serde_json::to_string(&body).unwrap_or_default()
-    );
-    client
-        .count(CountParts::Index(&["mdn_docs"]))
-        .body(body)
-        .send()
-        .await
+        page_type: elastic::Aggregation::Terms(elastic::AggregationTerms {
+            field: elastic::Field::PageType,
+            size: 20,
+        }),
+        status: elastic::Aggregation::Terms(elastic::AggregationTerms {
+            field: elastic::Field::Status,
+            size: 10,
+        }),
+    }
+}
+
+/// Ranked suggestions, the collate query already dropped the ones without hits.
+fn get_suggestions(suggest: elastic::ResponseSuggest) -> Vec<Suggestion> {
+    let mut options: Vec<elastic::ResponseSuggestionOption> = suggest
+        .phrase_suggestions
+        .into_iter()
+        .flat_map(|suggestion| suggestion.options)
+        .collect();
+    options.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    let mut seen = HashSet::new();
+    options.retain(|option| seen.insert(option.text.clone()));
+    options
+        .into_iter()
+        .take(SETTINGS.search.max_suggestions as usize)
+        .map(|option| Suggestion {
+            text: option.text,
+            // Collate only tells us there is at least one hit, not how many.
+            total: elastic::ResponseTotal {
+                value: 1,
+                relation: elastic::ResponseTotalRelation::GreaterThanOrEqual,
+            },
+        })
+        .collect()
 }
 
 async fn parse_or_get_error_reason<T>(
-    result: Result<ElasticResponse, elasticsearch::Error>,
+    result: Result<ElasticResponse, SearchError>,
 ) -> Result<T, SearchError>
 where
     T: serde::de::DeserializeOwned,
@@ -0,0 +1,128 @@
+use actix_web::{web, HttpResponse};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::error::ApiError;
+use crate::db::model::{SearchQueryReport, SearchQueryStatsInsert};
+use crate::db::search_stats::{record_search, search_report, SearchReportKind};
+use crate::db::Pool;
+use crate::settings::SETTINGS;
+
+const MAX_QUERY_LENGTH: usize = 200;
+
+pub struct SearchEvent<'a> {
+    pub query: &'a str,
+    pub locales: Vec<String>,
+    pub sort: &'a str,
+    pub page: u64,
+    pub total_hits: u64,
+    pub took_ms: u64,
+    pub suggestion_shown: bool,
+    pub from_suggestion: bool,
+}
+
+pub fn sampled() -> bool {
+    let rate = SETTINGS.search.analytics_sample_rate;
+    rate > 0.0 && (Uuid::new_v4().as_u128() % 10_000) as f64 / 10_000.0 < rate
+}
+
+/// Lower-cases and collapses whitespace, and masks tokens that look like they could
+/// identify someone (email addresses and long numbers).
+pub fn normalize_query(query: &str) -> String {
+    let normalized = query
+        .split_whitespace()
+        .map(|token| {
+            if token.contains('@') {
+                "<email>".to_string()
+            } else if token.chars().filter(char::is_ascii_digit).count() >= 6 {
+                "<number>".to_string()
+            } else {
+                token.to_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    normalized.chars().take(MAX_QUERY_LENGTH).collect()
+}
+
+/// Records a search in the background.
+pub fn record(pool: &Pool, event: SearchEvent) {
+    let mut locales = event.locales;
+    locales.sort();
+    let search = SearchQueryStatsInsert {
+        day: Utc::now().date_naive(),
+        query: normalize_query(event.query),
+        locales: locales.join(","),
+        sort: event.sort.to_string(),
+        searches: 1,
+        paginated: i64::from(event.page > 1),
+        zero_results: i64::from(event.total_hits == 0),
+        suggestions_shown: i64::from(event.suggestion_shown),
+        suggestions_clicked: i64::from(event.from_suggestion),
+        total_hits: event.total_hits as i64,
+        took_ms: event.took_ms as i64,
+    };
+    // Recorded off the request path, analytics must never slow down or fail a search.
+    let pool = pool.clone();
+    actix_rt::task::spawn_blocking(move || {
+        let result = pool
+            .get()
+            .map_err(|e| e.to_string())
+            .and_then(|mut conn| record_search(&mut conn, search).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("failed to record search: {}", e);
+        }
+    });
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SearchReportQuery {
+    #[serde(default = "default_report_days")]
+    #[validate(range(min = 1, max = 365, message = "'days' must be between 1 and 365"))]
+    days: i64,
+    #[serde(default = "default_report_limit")]
+    #[validate(range(min = 1, max = 500, message = "'limit' must be between 1 and 500"))]
+    limit: i64,
+}
+
+fn default_report_days() -> i64 {
+    30
+}
+
+fn default_report_limit() -> i64 {
+    50
+}
+
+#[derive(Serialize)]
+pub struct SearchReport {
+    top_queries: Vec<SearchQueryReport>,
+    zero_result_queries: Vec<SearchQueryReport>,
+    suggested_queries: Vec<SearchQueryReport>,
+}
+
+pub async fn search_analytics_report(
+    query: web::Query<SearchReportQuery>,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+    let since = Utc::now().date_naive() - Duration::days(query.days - 1);
+    let mut conn = pool.get()?;
+    let report = SearchReport {
+        top_queries: search_report(&mut conn, SearchReportKind::Top, since, query.limit)?,
+        zero_result_queries: search_report(
+            &mut conn,
+            SearchReportKind::ZeroResults,
+            since,
+            query.limit,
+        )?,
+        suggested_queries: search_report(
+            &mut conn,
+            SearchReportKind::Suggested,
+            since,
+            query.limit,
+        )?,
+    };
+    Ok(HttpResponse::Ok().json(report))
+}
@@ -0,0 +1,79 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{KeyInit, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ai::explain::HmacSha256;
+use crate::api::error::SearchError;
+use crate::settings::SETTINGS;
+
+/// Position after the last hit of a page, handed out to clients as an opaque token.
+///
+/// The token is signed together with the query it was issued for, so it can neither be
+/// forged nor reused for a different query.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Cursor {
+    /// The page this cursor points to.
+    pub page: u64,
+    /// Sort values of the last hit of the previous page.
+    pub search_after: Vec<Value>,
+}
+
+fn sign(payload: &str, scope: &str) -> Option<Vec<u8>> {
+    let key = SETTINGS.search.cursor_sign_key.as_ref()?;
+    let mut mac = HmacSha256::new_from_slice(key).ok()?;
+    mac.update(payload.as_bytes());
+    mac.update(scope.as_bytes());
+    Some(mac.finalize().into_bytes().to_vec())
+}
+
+fn invalid() -> SearchError {
+    SearchError::Query {
+        key: "cursor".to_string(),
+        message: "Invalid cursor.".to_string(),
+    }
+}
+
+impl Cursor {
+    /// Returns `None` if no `search.cursor_sign_key` is configured.
+    pub fn encode(&self, scope: &str) -> Option<String> {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).ok()?);
+        let signature = URL_SAFE_NO_PAD.encode(sign(&payload, scope)?);
+        Some(format!("{payload}.{signature}"))
+    }
+
+    pub fn decode(token: &str, scope: &str) -> Result<Self, SearchError> {
+        let (payload, signature) = token.split_once('.').ok_or_else(invalid)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+        let key = SETTINGS
+            .search
+            .cursor_sign_key
+            .as_ref()
+            .ok_or_else(invalid)?;
+        let mut mac = HmacSha256::new_from_slice(key).map_err(|_| invalid())?;
+        mac.update(payload.as_bytes());
+        mac.update(scope.as_bytes());
+        mac.verify_slice(&signature).map_err(|_| invalid())?;
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        serde_json::from_slice(&payload).map_err(|_| invalid())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor {
+            page: 12,
+            search_after: vec![json!(3.5), json!("web/api/fetch_api")],
+        };
+        let token = cursor.encode("fetch").unwrap();
+        assert_eq!(Cursor::decode(&token, "fetch").unwrap(), cursor);
+        assert!(Cursor::decode(&token, "xhr").is_err());
+        assert!(Cursor::decode(&token.replace('.', "x."), "fetch").is_err());
+        assert!(Cursor::decode("garbage", "fetch").is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use crate::api::elastic;
+use crate::api::error::SearchError;
+
+/// A single search term, either a word or a quoted phrase.
+#[derive(Debug, PartialEq)]
+pub enum Term {
+    Word(String),
+    Phrase(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Clause {
+    /// Free text, matched against title and body.
+    Text(Term),
+    /// `-word` or `-"some phrase"`, documents matching it are excluded.
+    Exclude(Term),
+    /// `title:word` or `title:"some phrase"`.
+    Title(Term),
+    /// `locale:de`, restricts (and replaces) the requested locales.
+    Locale(elastic::Locale),
+    /// `in:Web/API`, restricts the results to pages below that path.
+    In(String),
+}
+
+/// The parsed form of the `q` parameter.
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub clauses: Vec<Clause>,
+}
+
+impl SearchQuery {
+    pub fn parse(q: &str) -> Result<Self, SearchError> {
+        let mut clauses = vec![];
+        let mut rest = q.trim_start();
+        while !rest.is_empty() {
+            let (clause, remaining) = parse_clause(rest)?;
+            clauses.push(clause);
+            rest = remaining.trim_start();
+        }
+        let query = SearchQuery { clauses };
+        if !query
+            .clauses
+            .iter()
+            .any(|clause| matches!(clause, Clause::Text(_) | Clause::Title(_)))
+        {
+            return Err(query_error(
+                "Ensure the query contains at least one search term.",
+            ));
+        }
+        Ok(query)
+    }
+
+    /// The free text words, which are searched the way a plain query always was.
+    pub fn words(&self) -> String {
+        self.clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                Clause::Text(Term::Word(word)) => Some(word.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// All free text and title terms, without any operators.
+    pub fn plain_text(&self) -> String {
+        self.clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                Clause::Text(Term::Word(text) | Term::Phrase(text))
+                | Clause::Title(Term::Word(text) | Term::Phrase(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn phrases(&self) -> impl Iterator<Item = &str> {
+        self.clauses.iter().filter_map(|clause| match clause {
+            Clause::Text(Term::Phrase(phrase)) => Some(phrase.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn titles(&self) -> impl Iterator<Item = &Term> {
+        self.clauses.iter().filter_map(|clause| match clause {
+            Clause::Title(term) => Some(term),
+            _ => None,
+        })
+    }
+
+    pub fn excluded(&self) -> impl Iterator<Item = &Term> {
+        self.clauses.iter().filter_map(|clause| match clause {
+            Clause::Exclude(term) => Some(term),
+            _ => None,
+        })
+    }
+
+    pub fn locales(&self) -> Vec<elastic::Locale> {
+        self.clauses
+            .iter()
+            .filter_map(|clause| match clause {
+                Clause::Locale(locale) => Some(locale.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.clauses.iter().filter_map(|clause| match clause {
+            Clause::In(path) => Some(path.as_str()),
+            _ => None,
+        })
+    }
+}
+
+fn query_error(message: &str) -> SearchError {
+    SearchError::Query {
+        key: "q".to_string(),
+        message: message.to_string(),
+    }
+}
+
+/// CSS vendor prefixes like `-webkit-box` look like exclusions, but are not.
+fn is_vendor_prefixed(s: &str) -> bool {
+    let mut parts = s[1..].splitn(2, '-');
+    matches!(
+        (parts.next(), parts.next()),
+        (Some(prefix), Some(_)) if !prefix.is_empty() && prefix.chars().all(|c| c.is_ascii_alphabetic())
+    )
+}
+
+fn parse_clause(s: &str) -> Result<(Clause, &str), SearchError> {
+    if let Some(negated) = s.strip_prefix('-') {
+        if !negated.is_empty()
+            && !negated.starts_with(char::is_whitespace)
+            && !is_vendor_prefixed(s)
+        {
+            let (term, rest) = parse_term(negated)?;
+            return Ok((Clause::Exclude(term), rest));
+        }
+    }
+    for prefix in ["title:", "locale:", "in:"] {
+        if !s
+            .get(..prefix.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+        {
+            continue;
+        }
+        let value = &s[prefix.len()..];
+        if value.is_empty() || value.starts_with(char::is_whitespace) {
+            return Err(query_error(&format!("Missing value after '{prefix}'.")));
+        }
+        let (term, rest) = parse_term(value)?;
+        let clause = match (prefix, term) {
+            ("title:", term) => Clause::Title(term),
+            ("locale:", Term::Word(locale)) => Clause::Locale(
+                locale
+                    .to_lowercase()
+                    .parse()
+                    .map_err(|_| query_error(&format!("Unknown locale '{locale}'.")))?,
+            ),
+            (_, Term::Word(path)) => {
+                let path = path.trim_matches('/');
+                if path.is_empty() {
+                    return Err(query_error("Missing value after 'in:'."));
+                }
+                Clause::In(path.to_string())
+            }
+            (_, Term::Phrase(_)) => {
+                return Err(query_error(&format!(
+                    "'{prefix}' does not support quoted phrases."
+                )))
+            }
+        };
+        return Ok((clause, rest));
+    }
+    let (term, rest) = parse_term(s)?;
+    Ok((Clause::Text(term), rest))
+}
+
+fn parse_term(s: &str) -> Result<(Term, &str), SearchError> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let end = quoted
+            .find('"')
+            .ok_or_else(|| query_error("Unterminated quoted phrase."))?;
+        let phrase = quoted[..end]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if phrase.is_empty() {
+            return Err(query_error("Empty quoted phrase."));
+        }
+        Ok((Term::Phrase(phrase), &quoted[end + 1..]))
+    } else {
+        let end = s.find(char::is_whitespace).unwrap_or(s.len());
+        Ok((Term::Word(s[..end].to_string()), &s[end..]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn word(s: &str) -> Term {
+        Term::Word(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        let query =
+            SearchQuery::parse(r#"fetch "abort signal" -xhr title:Request in:/Web/API/ locale:de"#)
+                .unwrap();
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Text(word("fetch")),
+                Clause::Text(Term::Phrase("abort signal".to_string())),
+                Clause::Exclude(word("xhr")),
+                Clause::Title(word("Request")),
+                Clause::In("Web/API".to_string()),
+                Clause::Locale(elastic::Locale::German),
+            ]
+        );
+        assert_eq!(query.words(), "fetch");
+    }
+
+    #[test]
+    fn test_parse_plain() {
+        let query = SearchQuery::parse("  -webkit-box  display:flex ").unwrap();
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Text(word("-webkit-box")),
+                Clause::Text(word("display:flex"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for q in [
+            r#"fetch "abort"#,
+            "fetch title:",
+            "fetch locale:xx",
+            "-fetch",
+            "in:Web/API",
+            r#"fetch in:"Web API""#,
+        ] {
+            assert!(
+                matches!(SearchQuery::parse(q), Err(SearchError::Query { .. })),
+                "{q}"
+            );
+        }
+    }
+}
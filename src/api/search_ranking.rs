@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+
+use crate::api::elastic;
+use crate::settings::{RankingProfile, SETTINGS};
+
+/// Looks up a configured profile, `default` is built in unless configured.
+pub fn profile(name: &str) -> Option<RankingProfile> {
+    match SETTINGS.search.ranking.profiles.get(name) {
+        Some(profile) => Some(profile.clone()),
+        None if name == "default" => Some(RankingProfile::default()),
+        None => None,
+    }
+}
+
+/// The profile for a search, the first requested locale with an override wins.
+pub fn profile_for_locales<'a>(locales: impl IntoIterator<Item = &'a str>) -> RankingProfile {
+    let ranking = &SETTINGS.search.ranking;
+    let name = locales
+        .into_iter()
+        .find_map(|locale| ranking.locales.get(locale))
+        .unwrap_or(&ranking.profile);
+    profile(name).unwrap_or_else(|| {
+        warn!("unknown ranking profile: {}", name);
+        RankingProfile::default()
+    })
+}
+
+/// Score functions for the `best` sort. With a recency decay the function scores are
+/// summed up, otherwise only the popularity counts.
+pub fn score_functions(
+    profile: &RankingProfile,
+) -> (Vec<elastic::QueryFunctionScoreFunction>, elastic::ScoreMode) {
+    let popularity = elastic::QueryFunctionScoreFunction::FieldValueFactor(
+        elastic::QueryFunctionScoreFunctionFieldValueFactor {
+            field: elastic::Field::Popularity,
+            factor: profile.popularity_factor,
+            missing: 0,
+        },
+    );
+    match &profile.recency {
+        None => (vec![popularity], elastic::ScoreMode::Max),
+        Some(recency) => (
+            vec![
+                popularity,
+                elastic::QueryFunctionScoreFunction::Decay(
+                    elastic::QueryFunctionScoreFunctionDecay {
+                        exp: BTreeMap::from([(
+                            recency.field.clone(),
+                            elastic::DecayParams {
+                                origin: "now".to_string(),
+                                scale: format!("{}d", recency.scale_in_days),
+                                offset: format!("{}d", recency.offset_in_days),
+                                decay: recency.decay,
+                            },
+                        )]),
+                        weight: recency.weight,
+                    },
+                ),
+            ],
+            elastic::ScoreMode::Sum,
+        ),
+    }
+}
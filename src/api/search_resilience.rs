@@ -0,0 +1,178 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_rt::time::sleep;
+use elasticsearch::http::response::Response as ElasticResponse;
+use once_cell::sync::Lazy;
+
+use crate::api::error::SearchError;
+use crate::settings::SETTINGS;
+
+pub static ELASTIC_BREAKER: Lazy<CircuitBreaker> = Lazy::new(|| {
+    CircuitBreaker::new(
+        SETTINGS.search.circuit_breaker_threshold,
+        Duration::from_secs(SETTINGS.search.circuit_breaker_cooldown_in_sec),
+    )
+});
+
+/// Opens after `threshold` consecutive failures and rejects calls until `cooldown`
+/// has passed. The first call after that is let through: success closes the breaker
+/// again, failure re-opens it for another `cooldown`.
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        CircuitBreaker {
+            threshold,
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.opened_at {
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => false,
+            Some(_) => {
+                // Half open: let this call through, everything else waits for its outcome.
+                state.opened_at = Some(Instant::now());
+                true
+            }
+            None => true,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failures = 0;
+        state.opened_at = None;
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failures += 1;
+        if state.failures >= self.threshold {
+            if state.opened_at.is_none() {
+                warn!("Elasticsearch circuit breaker opened");
+            }
+            state.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Sends an idempotent request, retrying transport errors and 429/5xx responses
+    /// with exponential backoff.
+    pub async fn send<F, Fut>(&self, send: F) -> Result<ElasticResponse, SearchError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<ElasticResponse, elasticsearch::Error>>,
+    {
+        if !self.allow() {
+            return Err(SearchError::Unavailable);
+        }
+        let attempts = SETTINGS.search.retry_attempts.max(1);
+        let mut backoff = Duration::from_millis(SETTINGS.search.retry_backoff_in_ms);
+        let mut attempt = 1;
+        loop {
+            let result = send().await;
+            let retryable = match &result {
+                Ok(response) => {
+                    let status = response.status_code();
+                    status.is_server_error() || status.as_u16() == 429
+                }
+                Err(_) => true,
+            };
+            if !retryable {
+                self.record_success();
+                return result.map_err(Into::into);
+            }
+            if attempt >= attempts {
+                self.record_failure();
+                return result.map_err(Into::into);
+            }
+            debug!("retrying elastic request, attempt {} failed", attempt);
+            sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+/// Last good responses by normalized query, served when Elasticsearch is unavailable.
+/// Evicts the oldest entry once `capacity` is reached.
+pub struct ResponseCache<T> {
+    capacity: usize,
+    entries: Mutex<(HashMap<String, T>, VecDeque<String>)>,
+}
+
+impl<T: Clone> ResponseCache<T> {
+    pub fn new(capacity: usize) -> Self {
+        ResponseCache {
+            capacity,
+            entries: Mutex::new((HashMap::new(), VecDeque::new())),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<T> {
+        let entries = self.entries.lock().unwrap();
+        entries.0.get(key).cloned()
+    }
+
+    pub fn insert(&self, key: String, value: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let (map, order) = &mut *entries;
+        if map.insert(key.clone(), value).is_none() {
+            order.push_back(key);
+            while order.len() > self.capacity {
+                if let Some(oldest) = order.pop_front() {
+                    map.remove(&oldest);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_and_recovers() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert!(breaker.allow());
+        breaker.record_failure();
+        assert!(!breaker.allow());
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+        breaker.record_success();
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn test_cache_evicts_oldest() {
+        let cache = ResponseCache::new(2);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        cache.insert("c".to_string(), 3);
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b"), Some(2));
+        assert_eq!(cache.get("c"), Some(3));
+    }
+}
@@ -18,6 +18,7 @@ pub struct SettingUpdateRequest {
     pub mdnplus_newsletter: Option<bool>,
     pub no_ads: Option<bool>,
     pub ai_help_history: Option<bool>,
+    pub bcd_digest: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -26,6 +27,7 @@ pub struct SettingsResponse {
     pub mdnplus_newsletter: Option<bool>,
     pub no_ads: Option<bool>,
     pub ai_help_history: Option<bool>,
+    pub bcd_digest: Option<bool>,
 }
 
 impl From<Settings> for SettingsResponse {
@@ -35,6 +37,7 @@ impl From<Settings> for SettingsResponse {
             mdnplus_newsletter: Some(val.mdnplus_newsletter),
             no_ads: Some(val.no_ads),
             ai_help_history: Some(val.ai_help_history),
+            bcd_digest: Some(val.bcd_digest),
         }
     }
 }
@@ -60,6 +63,7 @@ pub async fn update_settings(
                 None
             },
             ai_help_history: settings_update.ai_help_history,
+            bcd_digest: settings_update.bcd_digest,
         };
         db::settings::create_or_update_settings(&mut conn_pool, settings_insert)
             .map_err(DbError::from)?;
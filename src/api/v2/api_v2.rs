@@ -1,11 +1,15 @@
 use super::{
+    browsers::{list_browser_releases, list_browsers},
+    features::feature_timeline,
     multiple_collections::{
         add_collection_item_to_collection, create_multiple_collection, delete_collection,
         get_collection_by_id, get_collection_item_in_collection_by_id, get_collections,
         lookup_collections_containing_article, modify_collection,
         modify_collection_item_in_collection, remove_collection_item_from_collection,
     },
+    notifications::{get_notifications, mark_one_read, mark_read},
     updates::get_updates,
+    updates_feed::{updates_feed_atom, updates_feed_json, updates_feed_rss},
 };
 use actix_web::dev::HttpServiceFactory;
 use actix_web::web;
@@ -16,6 +20,20 @@ pub fn api_v2_service() -> impl HttpServiceFactory {
         .service(web::resource("/updates/").route(web::get().to(get_updates)))
         /* We cannot cache /updates/collections/ **/
         .service(web::resource("/updates/collections/").route(web::get().to(get_updates)))
+        .service(web::resource("/updates/feed.atom").route(web::get().to(updates_feed_atom)))
+        .service(web::resource("/updates/feed.rss").route(web::get().to(updates_feed_rss)))
+        .service(web::resource("/updates/feed.json").route(web::get().to(updates_feed_json)))
+        .service(
+            web::resource(["/features/{path}/timeline", "/features/{path}/timeline/"])
+                .route(web::get().to(feature_timeline)),
+        )
+        .service(web::resource("/browsers/").route(web::get().to(list_browsers)))
+        .service(
+            web::resource("/browsers/{name}/releases/").route(web::get().to(list_browser_releases)),
+        )
+        .service(web::resource("/notifications/").route(web::get().to(get_notifications)))
+        .service(web::resource("/notifications/read/").route(web::post().to(mark_read)))
+        .service(web::resource("/notifications/{id}/read/").route(web::post().to(mark_one_read)))
         .service(
             web::resource("/collections/")
                 .route(web::get().to(get_collections))
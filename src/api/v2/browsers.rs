@@ -0,0 +1,115 @@
+use actix_web::{web, HttpResponse};
+use serde::de::Error as _;
+use serde::{Deserialize, Serialize};
+
+use crate::api::error::ApiError;
+use crate::api::v2::updates::from_name;
+use crate::db::v2::browsers::{
+    get_browser, get_browser_releases, get_browsers, get_current_releases,
+};
+use crate::db::v2::model::{Browser, BrowserRelease};
+use crate::db::Pool;
+use crate::helpers::array_like_maybe;
+
+/// Status of a browser release as recorded by BCD.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseStatus {
+    Retired,
+    Current,
+    Exclusive,
+    Beta,
+    Nightly,
+    Esr,
+    Planned,
+}
+
+impl ReleaseStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Retired => "retired",
+            Self::Current => "current",
+            Self::Exclusive => "exclusive",
+            Self::Beta => "beta",
+            Self::Nightly => "nightly",
+            Self::Esr => "esr",
+            Self::Planned => "planned",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BrowsersQueryParams {
+    /// Only browsers whose current release uses one of the engines.
+    #[serde(default, deserialize_with = "array_like_maybe")]
+    pub engine: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+pub struct ReleasesQueryParams {
+    #[serde(default, deserialize_with = "statuses_maybe")]
+    pub status: Option<Vec<ReleaseStatus>>,
+}
+
+fn statuses_maybe<'de, D>(deserializer: D) -> Result<Option<Vec<ReleaseStatus>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let Some(names) = array_like_maybe(deserializer)? else {
+        return Ok(None);
+    };
+    names
+        .into_iter()
+        .map(|name| from_name(&name).map_err(D::Error::custom))
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+#[derive(Serialize)]
+pub struct BrowserResponse {
+    #[serde(flatten)]
+    pub browser: Browser,
+    pub current: Option<BrowserRelease>,
+}
+
+pub async fn list_browsers(
+    pool: web::Data<Pool>,
+    query: web::Query<BrowsersQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    let current = get_current_releases(&mut conn)?;
+    let browsers: Vec<BrowserResponse> = get_browsers(&mut conn)?
+        .into_iter()
+        .map(|browser| BrowserResponse {
+            current: current
+                .iter()
+                .find(|release| release.browser == browser.name)
+                .cloned(),
+            browser,
+        })
+        .filter(|browser| match &query.engine {
+            Some(engines) => browser
+                .current
+                .as_ref()
+                .is_some_and(|release| engines.contains(&release.engine)),
+            None => true,
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(browsers))
+}
+
+pub async fn list_browser_releases(
+    pool: web::Data<Pool>,
+    name: web::Path<String>,
+    query: web::Query<ReleasesQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    let name = name.into_inner();
+    get_browser(&mut conn, &name)?.ok_or(ApiError::DocumentNotFound)?;
+    let statuses: Option<Vec<&str>> = query
+        .status
+        .as_ref()
+        .map(|statuses| statuses.iter().map(ReleaseStatus::as_str).collect());
+    let releases = get_browser_releases(&mut conn, &name, statuses.as_deref())?;
+    Ok(HttpResponse::Ok().json(releases))
+}
@@ -0,0 +1,35 @@
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+use crate::api::error::ApiError;
+use crate::db::v2::features::{get_feature, get_feature_timeline};
+use crate::db::v2::model::{BcdFeature, FeatureTimelineEvent};
+use crate::db::Pool;
+
+#[derive(Deserialize)]
+pub struct TimelineQueryParams {
+    #[serde(default)]
+    pub subfeatures: bool,
+}
+
+#[derive(Serialize)]
+pub struct FeatureTimeline {
+    /// Includes the Baseline status and date computed by the last sync.
+    pub feature: BcdFeature,
+    pub events: Vec<FeatureTimelineEvent>,
+}
+
+pub async fn feature_timeline(
+    pool: web::Data<Pool>,
+    path: web::Path<String>,
+    query: web::Query<TimelineQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    let path = path.into_inner();
+    let feature = get_feature(&mut conn, &path)?.ok_or(ApiError::DocumentNotFound)?;
+    let events = get_feature_timeline(&mut conn, &path, query.subfeatures)?;
+    Ok(HttpResponse::Ok().json(FeatureTimeline {
+        feature,
+        events,
+    }))
+}
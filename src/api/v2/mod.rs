@@ -1,3 +1,7 @@
 pub mod api_v2;
+pub mod browsers;
+pub mod features;
 pub mod multiple_collections;
+pub mod notifications;
 pub mod updates;
+pub mod updates_feed;
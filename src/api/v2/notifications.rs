@@ -0,0 +1,251 @@
+use std::fmt::Write;
+
+use actix_identity::Identity;
+use actix_rt::ArbiterHandle;
+use actix_web::web::Data;
+use actix_web::{web, HttpResponse};
+use chrono::{NaiveDate, NaiveDateTime};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::api::error::ApiError;
+use crate::api::mail::{Mail, Mailer};
+use crate::db::model::UserQuery;
+use crate::db::types::BcdUpdateEventType;
+use crate::db::users::get_user;
+use crate::db::v2::model::BcdNotificationQuery;
+use crate::db::v2::notifications::{
+    claim_notifications_for_digest, count_unread_notifications, get_notifications_for_user,
+    mark_notifications_read, release_notifications,
+};
+use crate::db::Pool;
+use crate::helpers::{maybe_to_utc, to_utc};
+use crate::ids::EncodedId;
+use crate::settings::SETTINGS;
+
+#[derive(Deserialize, Validate)]
+pub struct NotificationsQueryParams {
+    #[serde(default)]
+    pub unread: bool,
+    #[validate(range(min = 1, max = 100, message = "'limit' must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct NotificationDocument {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Serialize)]
+pub struct Notification {
+    pub id: String,
+    #[serde(serialize_with = "to_utc")]
+    pub created_at: NaiveDateTime,
+    #[serde(serialize_with = "maybe_to_utc")]
+    pub read_at: Option<NaiveDateTime>,
+    pub event_type: BcdUpdateEventType,
+    pub path: String,
+    pub browser: String,
+    pub browser_name: String,
+    pub version: String,
+    pub release_date: NaiveDate,
+    pub document: NotificationDocument,
+}
+
+#[derive(Serialize)]
+pub struct NotificationsResponse {
+    pub items: Vec<Notification>,
+    pub unread: i64,
+}
+
+#[derive(Deserialize)]
+pub struct MarkReadRequest {
+    /// All unread notifications are marked as read without ids.
+    pub ids: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct MarkReadResponse {
+    pub updated: usize,
+}
+
+impl From<BcdNotificationQuery> for Notification {
+    fn from(val: BcdNotificationQuery) -> Self {
+        Notification {
+            id: EncodedId::encode(val.id),
+            created_at: val.created_at,
+            read_at: val.read_at,
+            event_type: val.event_type,
+            path: val.path,
+            browser: val.browser,
+            browser_name: val.browser_name,
+            version: val.version,
+            release_date: val.release_date,
+            document: NotificationDocument {
+                title: val.document_title,
+                url: val.document_url,
+            },
+        }
+    }
+}
+
+pub async fn get_notifications(
+    user_id: Identity,
+    pool: web::Data<Pool>,
+    query: web::Query<NotificationsQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+    let mut conn_pool = pool.get()?;
+    let user: UserQuery = get_user(&mut conn_pool, user_id.id().unwrap())?;
+    let items = get_notifications_for_user(
+        &mut conn_pool,
+        user.id,
+        query.unread,
+        query.limit.unwrap_or(20),
+        query.offset.unwrap_or(0),
+    )?
+    .into_iter()
+    .map(Notification::from)
+    .collect();
+    let unread = count_unread_notifications(&mut conn_pool, user.id)?;
+    Ok(HttpResponse::Ok().json(NotificationsResponse { items, unread }))
+}
+
+pub async fn mark_read(
+    user_id: Identity,
+    pool: web::Data<Pool>,
+    payload: web::Json<MarkReadRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let ids = payload
+        .into_inner()
+        .ids
+        .map(|ids| {
+            ids.iter()
+                .map(EncodedId::decode)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let mut conn_pool = pool.get()?;
+    let user: UserQuery = get_user(&mut conn_pool, user_id.id().unwrap())?;
+    let updated = mark_notifications_read(&mut conn_pool, user.id, ids.as_deref())?;
+    Ok(HttpResponse::Ok().json(MarkReadResponse { updated }))
+}
+
+pub async fn mark_one_read(
+    user_id: Identity,
+    pool: web::Data<Pool>,
+    id: web::Path<EncodedId>,
+) -> Result<HttpResponse, ApiError> {
+    let id = id.get()?;
+    let mut conn_pool = pool.get()?;
+    let user: UserQuery = get_user(&mut conn_pool, user_id.id().unwrap())?;
+    let updated = mark_notifications_read(&mut conn_pool, user.id, Some(&[id][..]))?;
+    Ok(HttpResponse::Ok().json(MarkReadResponse { updated }))
+}
+
+/// Mails a digest of their new notifications to every user who asked for one.
+pub async fn send_digests(
+    pool: Data<Pool>,
+    mailer: Data<Mailer>,
+    arbiter: Data<ArbiterHandle>,
+) -> Result<HttpResponse, ApiError> {
+    if mailer.0.is_none() {
+        return Err(ApiError::NotImplemented);
+    }
+    if !arbiter.spawn(async move {
+        if let Err(e) = do_send_digests(&pool, &mailer).await {
+            error!("{}", e);
+        }
+    }) {
+        return Ok(HttpResponse::InternalServerError().finish());
+    }
+    Ok(HttpResponse::Accepted().finish())
+}
+
+pub async fn do_send_digests(pool: &Pool, mailer: &Mailer) -> Result<usize, ApiError> {
+    let Some(sender) = &mailer.0 else {
+        return Ok(0);
+    };
+    let Some(notifications) = &SETTINGS.notifications else {
+        return Ok(0);
+    };
+    let claimed = {
+        let mut conn = pool.get()?;
+        claim_notifications_for_digest(&mut conn)?
+    };
+    // Grouped up front, the groups borrow the iterator and can't be held across awaits.
+    let groups: Vec<(String, Vec<BcdNotificationQuery>)> = claimed
+        .into_iter()
+        .chunk_by(|(email, _)| email.clone())
+        .into_iter()
+        .map(|(email, group)| (email, group.map(|(_, n)| n).collect()))
+        .collect();
+    let mut sent = 0;
+    for (email, group) in groups {
+        let mail = Mail {
+            from: notifications.mail_from.clone(),
+            to: email,
+            subject: format!(
+                "{} browser compatibility updates for your MDN collections",
+                group.len()
+            ),
+            text: digest_text(&group),
+        };
+        if let Err(e) = sender.send(&mail).await {
+            warn!("Error mailing bcd digest: {}", e);
+            let ids: Vec<i64> = group.iter().map(|n| n.id).collect();
+            let mut conn = pool.get()?;
+            release_notifications(&mut conn, &ids)?;
+            continue;
+        }
+        sent += 1;
+    }
+    info!("Sent {} bcd digests", sent);
+    Ok(sent)
+}
+
+fn describe(event_type: BcdUpdateEventType) -> &'static str {
+    match event_type {
+        BcdUpdateEventType::AddedStable => "supported",
+        BcdUpdateEventType::AddedPreview => "supported in preview",
+        BcdUpdateEventType::RemovedStable => "no longer supported",
+        BcdUpdateEventType::AddedFlag => "supported behind a flag",
+        BcdUpdateEventType::RemovedFlag => "no longer behind a flag",
+        BcdUpdateEventType::AddedPrefix => "supported with a prefix",
+        BcdUpdateEventType::RemovedPrefix => "no longer prefixed",
+        BcdUpdateEventType::AddedPartial => "partially supported",
+        BcdUpdateEventType::RemovedPartial => "fully supported",
+        BcdUpdateEventType::VersionCorrected => "support version corrected",
+        _ => "changed",
+    }
+}
+
+/// The notifications of one user, which are ordered by page.
+fn digest_text(notifications: &[BcdNotificationQuery]) -> String {
+    let mut text = String::from("Browser support changed for pages in your MDN collections:\n");
+    for (url, group) in &notifications.iter().chunk_by(|n| &n.document_url) {
+        let group: Vec<_> = group.collect();
+        let _ = write!(text, "\n{} ({})\n", group[0].document_title, url);
+        for n in group {
+            let _ = writeln!(
+                text,
+                "  - {}: {} in {} {} ({})",
+                n.path,
+                describe(n.event_type),
+                n.browser_name,
+                n.version,
+                n.release_date
+            );
+        }
+    }
+    let _ = write!(
+        text,
+        "\nSee all notifications at {}/en-US/plus/notifications\n",
+        SETTINGS.application.document_base_url
+    );
+    text
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
-use crate::db::types::{BcdUpdateEventType, EngineType};
+use crate::db::types::{BaselineStatus, BcdUpdateEventType, EngineType};
 use crate::db::v2::bcd_updates::{get_bcd_updates_for_collection, get_bcd_updates_paginated};
 use crate::db::v2::model::{Event, Status};
+use crate::db::v2::pagination::{token, UpdatesCursor};
 use crate::helpers::{array_like_maybe, decode_ids_maybe};
 use crate::{api::error::ApiError, db::Pool};
 
@@ -10,7 +11,10 @@ use actix_identity::Identity;
 use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::NaiveDate;
 use itertools::Itertools;
+use serde::de::{Error as _, IntoDeserializer};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum AscOrDesc {
@@ -20,19 +24,68 @@ pub enum AscOrDesc {
     Desc,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Validate)]
 pub struct BcdUpdatesQueryParams {
+    #[serde(default, deserialize_with = "baseline_maybe")]
+    pub baseline: Option<Vec<BaselineStatus>>,
     #[serde(default, deserialize_with = "array_like_maybe")]
     pub browsers: Option<Vec<String>>,
     #[serde(default, deserialize_with = "array_like_maybe")]
     pub category: Option<Vec<String>>,
     #[serde(default, deserialize_with = "decode_ids_maybe")]
     pub collections: Option<Vec<i64>>,
+    #[serde(default, deserialize_with = "event_types_maybe")]
+    pub event_type: Option<Vec<BcdUpdateEventType>>,
     pub page: Option<i64>,
+    /// Number of release groups per page.
+    #[validate(range(min = 1, max = 100, message = "'per_page' must be between 1 and 100"))]
+    pub per_page: Option<i64>,
+    /// Continue after the `next` cursor of a previous page instead of using `page`.
+    #[serde(default, with = "token")]
+    pub cursor: Option<UpdatesCursor>,
     pub q: Option<String>,
     pub sort: Option<AscOrDesc>,
 }
 
+pub(crate) fn from_name<'a, T: Deserialize<'a>>(
+    name: &'a str,
+) -> Result<T, serde::de::value::Error> {
+    T::deserialize(name.into_deserializer())
+}
+
+fn event_types_maybe<'de, D>(deserializer: D) -> Result<Option<Vec<BcdUpdateEventType>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let Some(names) = array_like_maybe(deserializer)? else {
+        return Ok(None);
+    };
+    names
+        .into_iter()
+        .map(|name| match from_name(&name) {
+            Ok(BcdUpdateEventType::Unknown) | Err(_) => {
+                Err(D::Error::custom(format!("unknown event type: {name}")))
+            }
+            Ok(event_type) => Ok(event_type),
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+fn baseline_maybe<'de, D>(deserializer: D) -> Result<Option<Vec<BaselineStatus>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let Some(names) = array_like_maybe(deserializer)? else {
+        return Ok(None);
+    };
+    names
+        .into_iter()
+        .map(|name| from_name(&name).map_err(D::Error::custom))
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
 #[derive(Serialize, Hash, Eq, PartialEq)]
 pub enum UpdateType {
     #[serde(rename(serialize = "browser_grouping"))]
@@ -46,12 +99,16 @@ pub struct BcdUpdatesPaginatedResponse {
     pub data: Vec<BcdUpdate>,
     pub query: BcdUpdatesQueryParams,
     pub last: i64,
+    pub total: i64,
+    #[serde(with = "token")]
+    pub next: Option<UpdatesCursor>,
 }
 
 #[derive(Serialize)]
 pub struct BcdUpdateEvent {
     pub added: Vec<FeatureInfo>,
     pub removed: Vec<FeatureInfo>,
+    pub changed: Vec<FeatureChange>,
 }
 
 #[derive(Serialize)]
@@ -60,6 +117,17 @@ pub struct FeatureInfo {
     pub compat: CompatInfo,
 }
 
+/// Any other change of a support statement, e.g. a feature that is no longer behind a
+/// flag, with the statements before and after it.
+#[derive(Serialize)]
+pub struct FeatureChange {
+    pub event_type: BcdUpdateEventType,
+    #[serde(flatten)]
+    pub feature: FeatureInfo,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
 #[derive(Serialize)]
 pub struct StatusInfo {
     deprecated: bool,
@@ -74,6 +142,14 @@ pub struct CompatInfo {
     pub spec_url: Option<String>,
     pub status: Option<StatusInfo>,
     pub engines: Vec<EngineType>,
+    pub baseline: Option<BaselineInfo>,
+}
+
+#[derive(Serialize)]
+pub struct BaselineInfo {
+    pub status: BaselineStatus,
+    /// When the feature became available in all core browsers.
+    pub date: Option<NaiveDate>,
 }
 #[derive(Serialize)]
 pub struct BrowserInfo {
@@ -97,31 +173,16 @@ pub struct BcdUpdate {
 
 fn query_contains_restricted_filters(query: &BcdUpdatesQueryParams) -> bool {
     query.browsers.is_some()
+        || query.baseline.is_some()
+        || query.event_type.is_some()
         || query.q.is_some()
         || query.sort.is_some()
         || query.category.is_some()
 }
 
-pub async fn get_updates(
-    _req: HttpRequest,
-    pool: web::Data<Pool>,
-    user_id: Option<Identity>,
-    query: web::Query<BcdUpdatesQueryParams>,
-) -> Result<HttpResponse, ApiError> {
-    if user_id.is_none() && query_contains_restricted_filters(&query) {
-        return Err(ApiError::LoginRequiredForFeature("BCD Filters".to_string()));
-    }
-
-    let mut conn_pool = pool.get()?;
-
-    let updates = if let (Some(_), Some(user_id)) = (&query.collections, &user_id) {
-        get_bcd_updates_for_collection(&mut conn_pool, &query, user_id)?
-    } else {
-        get_bcd_updates_paginated(&mut conn_pool, &query)?
-    };
-
-    let mapped_updates = updates
-        .0
+/// Groups the updates by browser release and buckets their events by type.
+pub fn group_updates(updates: Vec<crate::db::v2::model::BcdUpdate>) -> Vec<BcdUpdate> {
+    updates
         .into_iter()
         .chunk_by(|key| {
             (
@@ -131,18 +192,23 @@ pub async fn get_updates(
                 key.name.clone(),
                 key.release_date,
                 key.release_id.clone(),
+                key.release_notes.clone(),
             )
         })
         .into_iter()
         .map(|(key, group)| {
-            let (added, removed): (Vec<_>, Vec<_>) = group
-                .into_iter()
-                .flat_map(|f| f.compat)
-                .partition(|f| f.event_type == BcdUpdateEventType::AddedStable);
-            let (added, removed) = (
-                added.into_iter().map(Into::into).collect(),
-                removed.into_iter().map(Into::into).collect(),
-            );
+            let mut events = BcdUpdateEvent {
+                added: vec![],
+                removed: vec![],
+                changed: vec![],
+            };
+            for event in group.into_iter().flat_map(|f| f.compat) {
+                match event.event_type {
+                    BcdUpdateEventType::AddedStable => events.added.push(event.into()),
+                    BcdUpdateEventType::RemovedStable => events.removed.push(event.into()),
+                    _ => events.changed.push(event.into()),
+                }
+            }
 
             BcdUpdate {
                 _type: UpdateType::BrowserGrouping,
@@ -151,18 +217,42 @@ pub async fn get_updates(
                     engine_version: key.1,
                     engine: key.2,
                     name: key.3.to_string(),
-                    release_notes: "".to_string(),
+                    release_notes: key.6.unwrap_or_default(),
                     version: key.5,
                 }),
                 release_date: key.4,
-                events: BcdUpdateEvent { added, removed },
+                events,
             }
         })
-        .collect();
+        .collect()
+}
+
+pub async fn get_updates(
+    _req: HttpRequest,
+    pool: web::Data<Pool>,
+    user_id: Option<Identity>,
+    query: web::Query<BcdUpdatesQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    if user_id.is_none() && query_contains_restricted_filters(&query) {
+        return Err(ApiError::LoginRequiredForFeature("BCD Filters".to_string()));
+    }
+
+    query.validate()?;
+
+    let mut conn_pool = pool.get()?;
+
+    let page = if let (Some(_), Some(user_id)) = (&query.collections, &user_id) {
+        get_bcd_updates_for_collection(&mut conn_pool, &query, user_id)?
+    } else {
+        get_bcd_updates_paginated(&mut conn_pool, &query)?
+    };
+
     let response = BcdUpdatesPaginatedResponse {
-        data: mapped_updates,
+        data: group_updates(page.updates),
         query: query.into_inner(),
-        last: updates.1,
+        last: page.last,
+        total: page.total,
+        next: page.next,
     };
     Ok(HttpResponse::Ok().json(response))
 }
@@ -177,11 +267,26 @@ impl From<Event> for FeatureInfo {
                 spec_url: val.spec_url,
                 status: val.status.map(Into::<StatusInfo>::into),
                 engines: val.engines,
+                baseline: val.baseline_status.map(|status| BaselineInfo {
+                    status,
+                    date: val.baseline_date,
+                }),
             },
         }
     }
 }
 
+impl From<Event> for FeatureChange {
+    fn from(mut val: Event) -> Self {
+        FeatureChange {
+            event_type: val.event_type,
+            before: val.before.take(),
+            after: val.after.take(),
+            feature: val.into(),
+        }
+    }
+}
+
 impl From<Status> for StatusInfo {
     fn from(val: Status) -> Self {
         StatusInfo {
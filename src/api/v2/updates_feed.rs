@@ -0,0 +1,373 @@
+use std::fmt::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::http::header::{
+    self, ETag, EntityTag, Header, HttpDate, IfModifiedSince, IfNoneMatch, LastModified,
+};
+use actix_web::{web, HttpRequest, HttpResponse, HttpResponseBuilder};
+use chrono::{DateTime, NaiveTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api::error::ApiError;
+use crate::api::v2::updates::{group_updates, BcdUpdate, BcdUpdatesQueryParams, FeatureInfo};
+use crate::db::v2::bcd_sync_runs::last_successful_sync_run;
+use crate::db::v2::bcd_updates::get_bcd_updates_paginated;
+use crate::db::Pool;
+use crate::helpers::array_like_maybe;
+use crate::settings::SETTINGS;
+
+/// Number of browser releases in a feed.
+const FEED_LENGTH: i64 = 20;
+const FEED_TITLE: &str = "MDN browser compatibility updates";
+
+#[derive(Clone, Copy)]
+pub enum FeedFormat {
+    Atom,
+    Rss,
+    Json,
+}
+
+#[derive(Deserialize)]
+pub struct FeedQueryParams {
+    #[serde(default, deserialize_with = "array_like_maybe")]
+    pub browsers: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "array_like_maybe")]
+    pub category: Option<Vec<String>>,
+}
+
+struct Feed {
+    title: String,
+    home_page_url: String,
+    feed_url: String,
+    updated: DateTime<Utc>,
+    entries: Vec<FeedEntry>,
+}
+
+struct FeedEntry {
+    id: String,
+    title: String,
+    url: String,
+    published: DateTime<Utc>,
+    content_html: String,
+}
+
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    feed_url: &'a str,
+    items: Vec<JsonFeedItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem<'a> {
+    id: &'a str,
+    url: &'a str,
+    title: &'a str,
+    content_html: &'a str,
+    date_published: String,
+}
+
+/// Validators of the feeds, which only change with a sync run.
+struct Validators {
+    etag: EntityTag,
+    last_modified: SystemTime,
+}
+
+impl Validators {
+    fn matches(&self, req: &HttpRequest) -> bool {
+        if req.headers().contains_key(header::IF_NONE_MATCH) {
+            return match IfNoneMatch::parse(req) {
+                Ok(IfNoneMatch::Any) => true,
+                Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&self.etag)),
+                Err(_) => false,
+            };
+        }
+        match IfModifiedSince::parse(req) {
+            Ok(IfModifiedSince(since)) => SystemTime::from(since) >= self.last_modified,
+            Err(_) => false,
+        }
+    }
+
+    fn apply(&self, builder: &mut HttpResponseBuilder) {
+        builder
+            .insert_header(ETag(self.etag.clone()))
+            .insert_header(LastModified(HttpDate::from(self.last_modified)));
+    }
+}
+
+pub async fn updates_feed_atom(
+    req: HttpRequest,
+    pool: web::Data<Pool>,
+    query: web::Query<FeedQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    updates_feed(req, pool, query.into_inner(), FeedFormat::Atom).await
+}
+
+pub async fn updates_feed_rss(
+    req: HttpRequest,
+    pool: web::Data<Pool>,
+    query: web::Query<FeedQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    updates_feed(req, pool, query.into_inner(), FeedFormat::Rss).await
+}
+
+pub async fn updates_feed_json(
+    req: HttpRequest,
+    pool: web::Data<Pool>,
+    query: web::Query<FeedQueryParams>,
+) -> Result<HttpResponse, ApiError> {
+    updates_feed(req, pool, query.into_inner(), FeedFormat::Json).await
+}
+
+async fn updates_feed(
+    req: HttpRequest,
+    pool: web::Data<Pool>,
+    query: FeedQueryParams,
+    format: FeedFormat,
+) -> Result<HttpResponse, ApiError> {
+    let mut conn_pool = pool.get()?;
+
+    let validators = last_successful_sync_run(&mut conn_pool)?
+        .and_then(|run| Some((run.id, run.finished_at?)))
+        .map(|(id, finished_at)| Validators {
+            etag: EntityTag::new_strong(format!("bcd-sync-{id}")),
+            // HTTP dates have a resolution of seconds.
+            last_modified: UNIX_EPOCH
+                + Duration::from_secs(finished_at.and_utc().timestamp().max(0) as u64),
+        });
+    if let Some(validators) = &validators {
+        if validators.matches(&req) {
+            let mut builder = HttpResponse::NotModified();
+            validators.apply(&mut builder);
+            return Ok(builder.finish());
+        }
+    }
+
+    let title = match &query.browsers {
+        Some(browsers) => format!("{FEED_TITLE} for {}", browsers.join(", ")),
+        None => FEED_TITLE.to_string(),
+    };
+    let params = BcdUpdatesQueryParams {
+        baseline: None,
+        browsers: query.browsers,
+        category: query.category,
+        collections: None,
+        event_type: None,
+        page: None,
+        per_page: Some(FEED_LENGTH),
+        cursor: None,
+        q: None,
+        sort: None,
+    };
+    let updates = group_updates(get_bcd_updates_paginated(&mut conn_pool, &params)?.updates);
+
+    let home_page_url = format!(
+        "{}/en-US/plus/updates",
+        SETTINGS.application.document_base_url
+    );
+    let entries: Vec<FeedEntry> = updates
+        .iter()
+        .filter_map(|update| feed_entry(update, &home_page_url))
+        .collect();
+    let updated = validators
+        .as_ref()
+        .map(|validators| DateTime::<Utc>::from(validators.last_modified))
+        .or_else(|| entries.first().map(|entry| entry.published))
+        .unwrap_or_else(Utc::now);
+    let feed = Feed {
+        title,
+        home_page_url,
+        feed_url: req.full_url().to_string(),
+        updated,
+        entries,
+    };
+
+    let mut builder = HttpResponse::Ok();
+    if let Some(validators) = &validators {
+        validators.apply(&mut builder);
+    }
+    Ok(match format {
+        FeedFormat::Atom => builder
+            .content_type("application/atom+xml; charset=utf-8")
+            .body(atom(&feed)),
+        FeedFormat::Rss => builder
+            .content_type("application/rss+xml; charset=utf-8")
+            .body(rss(&feed)),
+        FeedFormat::Json => builder
+            .content_type("application/feed+json")
+            .body(json_feed(&feed)),
+    })
+}
+
+fn feed_entry(update: &BcdUpdate, home_page_url: &str) -> Option<FeedEntry> {
+    let browser = update.browser.as_ref()?;
+    let mut content_html = String::new();
+    for (heading, features) in [
+        ("Added", update.events.added.iter().collect::<Vec<_>>()),
+        ("Removed", update.events.removed.iter().collect()),
+        (
+            "Changed",
+            update.events.changed.iter().map(|c| &c.feature).collect(),
+        ),
+    ] {
+        if features.is_empty() {
+            continue;
+        }
+        let _ = write!(content_html, "<h2>{heading}</h2><ul>");
+        for feature in features {
+            content_html.push_str("<li>");
+            feature_html(&mut content_html, feature);
+            content_html.push_str("</li>");
+        }
+        content_html.push_str("</ul>");
+    }
+
+    Some(FeedEntry {
+        id: format!(
+            "urn:mdn:bcd-updates:{}:{}",
+            browser.browser, browser.version
+        ),
+        title: format!("{} {}", browser.name, browser.version),
+        url: format!("{home_page_url}?browsers={}", browser.browser),
+        published: update.release_date.and_time(NaiveTime::MIN).and_utc(),
+        content_html,
+    })
+}
+
+fn feature_html(out: &mut String, feature: &FeatureInfo) {
+    let path = escape(&feature.path);
+    let _ = match &feature.compat.mdn_url {
+        Some(url) => write!(out, "<a href=\"{}\"><code>{path}</code></a>", escape(url)),
+        None => write!(out, "<code>{path}</code>"),
+    };
+}
+
+/// Escapes text for XML and HTML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn rfc3339(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn atom(feed: &Feed) -> String {
+    let mut out = String::from(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    let _ = write!(
+        out,
+        r#"<feed xmlns="http://www.w3.org/2005/Atom"><id>{feed_url}</id><title>{title}</title><updated>{updated}</updated><link rel="self" href="{feed_url}"/><link rel="alternate" type="text/html" href="{home}"/>"#,
+        feed_url = escape(&feed.feed_url),
+        title = escape(&feed.title),
+        updated = rfc3339(&feed.updated),
+        home = escape(&feed.home_page_url),
+    );
+    for entry in &feed.entries {
+        let _ = write!(
+            out,
+            r#"<entry><id>{id}</id><title>{title}</title><updated>{date}</updated><published>{date}</published><link rel="alternate" type="text/html" href="{url}"/><content type="html">{content}</content></entry>"#,
+            id = escape(&entry.id),
+            title = escape(&entry.title),
+            date = rfc3339(&entry.published),
+            url = escape(&entry.url),
+            content = escape(&entry.content_html),
+        );
+    }
+    out.push_str("</feed>");
+    out
+}
+
+fn rss(feed: &Feed) -> String {
+    let mut out = String::from(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+    let _ = write!(
+        out,
+        r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom"><channel><title>{title}</title><link>{home}</link><description>{title}</description><lastBuildDate>{updated}</lastBuildDate><atom:link rel="self" type="application/rss+xml" href="{feed_url}"/>"#,
+        title = escape(&feed.title),
+        home = escape(&feed.home_page_url),
+        updated = feed.updated.to_rfc2822(),
+        feed_url = escape(&feed.feed_url),
+    );
+    for entry in &feed.entries {
+        let _ = write!(
+            out,
+            r#"<item><guid isPermaLink="false">{id}</guid><title>{title}</title><link>{url}</link><pubDate>{date}</pubDate><description>{content}</description></item>"#,
+            id = escape(&entry.id),
+            title = escape(&entry.title),
+            url = escape(&entry.url),
+            date = entry.published.to_rfc2822(),
+            content = escape(&entry.content_html),
+        );
+    }
+    out.push_str("</channel></rss>");
+    out
+}
+
+fn json_feed(feed: &Feed) -> String {
+    serde_json::to_string(&JsonFeed {
+        version: "https://jsonfeed.org/version/1.1",
+        title: &feed.title,
+        home_page_url: &feed.home_page_url,
+        feed_url: &feed.feed_url,
+        items: feed
+            .entries
+            .iter()
+            .map(|entry| JsonFeedItem {
+                id: &entry.id,
+                url: &entry.url,
+                title: &entry.title,
+                content_html: &entry.content_html,
+                date_published: rfc3339(&entry.published),
+            })
+            .collect(),
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x?a=1&b='2'">"#),
+            "&lt;a href=&quot;x?a=1&amp;b=&apos;2&apos;&quot;&gt;"
+        );
+    }
+    #[test]
+    fn test_json_feed() {
+        let published = DateTime::parse_from_rfc3339("2022-11-15T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let feed = Feed {
+            title: "MDN browser compatibility updates".to_string(),
+            home_page_url: "https://developer.mozilla.org/en-US/plus/updates".to_string(),
+            feed_url: "https://developer.mozilla.org/api/v2/updates/feed.json".to_string(),
+            updated: published,
+            entries: vec![FeedEntry {
+                id: "firefox-107".to_string(),
+                title: "Firefox 107".to_string(),
+                url: "https://developer.mozilla.org/en-US/plus/updates#firefox-107".to_string(),
+                published,
+                content_html: "<h2>Added</h2>".to_string(),
+            }],
+        };
+        let json: serde_json::Value = serde_json::from_str(&json_feed(&feed)).unwrap();
+        assert_eq!(json["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(json["items"][0]["id"], "firefox-107");
+        assert_eq!(json["items"][0]["content_html"], "<h2>Added</h2>");
+        assert_eq!(json["items"][0]["date_published"], "2022-11-15T00:00:00Z");
+    }
+}
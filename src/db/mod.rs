@@ -11,6 +11,7 @@ pub mod play;
 #[allow(unused_imports)]
 pub mod schema;
 pub mod schema_manual;
+pub mod search_stats;
 pub mod settings;
 pub mod types;
 pub mod users;
@@ -1,7 +1,7 @@
 use crate::db::types::{AiHelpMessageStatus, FxaEventStatus, Subscription};
 use crate::db::{schema::*, types::FxaEvent};
 use crate::helpers::to_utc;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -117,6 +117,7 @@ pub struct Settings {
     pub mdnplus_newsletter: bool,
     pub no_ads: bool,
     pub ai_help_history: bool,
+    pub bcd_digest: bool,
 }
 
 #[derive(Insertable, AsChangeset, Default)]
@@ -127,6 +128,7 @@ pub struct SettingsInsert {
     pub mdnplus_newsletter: Option<bool>,
     pub no_ads: Option<bool>,
     pub ai_help_history: Option<bool>,
+    pub bcd_digest: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -199,6 +201,9 @@ pub struct PlaygroundInsert {
     pub gist: String,
     pub active: bool,
     pub flagged: bool,
+    pub title: Option<String>,
+    pub parent_id: Option<i64>,
+    pub key_version: i32,
 }
 
 #[derive(Queryable, Serialize, Debug, Default)]
@@ -210,6 +215,53 @@ pub struct PlaygroundQuery {
     pub active: bool,
     pub flagged: bool,
     pub deleted_user_id: Option<i64>,
+    pub title: Option<String>,
+    pub revision: i64,
+    pub parent_id: Option<i64>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub key_version: i32,
+}
+
+#[derive(Insertable, Serialize, Debug)]
+#[diesel(table_name = playground_flags)]
+pub struct PlaygroundFlagInsert {
+    pub playground_id: i64,
+    pub reason: Option<String>,
+}
+
+#[derive(Queryable, Serialize, Debug)]
+#[diesel(table_name = playground_flags)]
+pub struct PlaygroundFlagQuery {
+    pub id: i64,
+    pub playground_id: i64,
+    pub reason: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Serialize, Debug)]
+#[diesel(table_name = playground_revisions)]
+pub struct PlaygroundRevisionInsert {
+    pub playground_id: i64,
+    pub revision: i64,
+    pub code: Value,
+}
+
+#[derive(Queryable, Serialize, Debug)]
+#[diesel(table_name = playground_revisions)]
+pub struct PlaygroundRevisionQuery {
+    pub id: i64,
+    pub playground_id: i64,
+    pub revision: i64,
+    pub code: Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Serialize, Debug)]
+#[diesel(table_name = playground_code)]
+pub struct PlaygroundCodeInsert {
+    pub gist: String,
+    pub code: Value,
 }
 
 #[derive(Insertable, Serialize, Debug, Default)]
@@ -348,3 +400,35 @@ pub struct AiHelpMessageMetaInsert<'a> {
     /// Consulted MDN content to answer the question.
     pub sources: Option<Value>,
 }
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = search_query_stats)]
+pub struct SearchQueryStatsInsert {
+    pub day: NaiveDate,
+    pub query: String,
+    pub locales: String,
+    pub sort: String,
+    pub searches: i64,
+    pub paginated: i64,
+    pub zero_results: i64,
+    pub suggestions_shown: i64,
+    pub suggestions_clicked: i64,
+    pub total_hits: i64,
+    pub took_ms: i64,
+}
+
+#[derive(QueryableByName, Serialize, Debug)]
+pub struct SearchQueryReport {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub query: String,
+    #[diesel(sql_type = diesel::sql_types::Int8)]
+    pub searches: i64,
+    #[diesel(sql_type = diesel::sql_types::Int8)]
+    pub zero_results: i64,
+    #[diesel(sql_type = diesel::sql_types::Int8)]
+    pub suggestions_shown: i64,
+    #[diesel(sql_type = diesel::sql_types::Int8)]
+    pub suggestions_clicked: i64,
+    #[diesel(sql_type = diesel::sql_types::Int8)]
+    pub avg_took_ms: i64,
+}
@@ -1,20 +1,265 @@
-use crate::db::model::PlaygroundInsert;
+use crate::db::model::{
+    PlaygroundCodeInsert, PlaygroundFlagInsert, PlaygroundFlagQuery, PlaygroundInsert,
+    PlaygroundQuery, PlaygroundRevisionInsert,
+};
 use crate::db::schema;
 
-use diesel::{insert_into, PgConnection};
+use chrono::NaiveDateTime;
+use diesel::{delete, insert_into, PgConnection};
 use diesel::{prelude::*, update};
+use serde_json::Value;
 
 pub fn create_playground(
     conn: &mut PgConnection,
     playground: PlaygroundInsert,
-) -> QueryResult<usize> {
+) -> QueryResult<i64> {
     insert_into(schema::playground::table)
         .values(&playground)
+        .returning(schema::playground::id)
+        .get_result(conn)
+}
+
+pub fn flag_playground(
+    conn: &mut PgConnection,
+    gist_id: &str,
+    reason: Option<String>,
+) -> QueryResult<i64> {
+    conn.transaction(|conn| {
+        let id = update(schema::playground::table.filter(schema::playground::gist.eq(gist_id)))
+            .set(schema::playground::flagged.eq(true))
+            .returning(schema::playground::id)
+            .get_result(conn)?;
+        insert_into(schema::playground_flags::table)
+            .values(PlaygroundFlagInsert {
+                playground_id: id,
+                reason,
+            })
+            .execute(conn)?;
+        Ok(id)
+    })
+}
+
+pub fn get_flagged_playgrounds(conn: &mut PgConnection) -> QueryResult<Vec<PlaygroundQuery>> {
+    schema::playground::table
+        .filter(schema::playground::flagged.eq(true))
+        .order_by(schema::playground::id)
+        .get_results(conn)
+}
+
+pub fn get_playground_flags(
+    conn: &mut PgConnection,
+    ids: &[i64],
+) -> QueryResult<Vec<PlaygroundFlagQuery>> {
+    schema::playground_flags::table
+        .filter(schema::playground_flags::playground_id.eq_any(ids))
+        .order_by(schema::playground_flags::created_at)
+        .get_results(conn)
+}
+
+/// Hidden playgrounds are marked as not `active` and are no longer served.
+pub fn hide_playground(conn: &mut PgConnection, id: i64) -> QueryResult<usize> {
+    update(schema::playground::table.filter(schema::playground::id.eq(id)))
+        .set(schema::playground::active.eq(false))
         .execute(conn)
 }
 
-pub fn flag_playground(conn: &mut PgConnection, gist_id: &str) -> QueryResult<usize> {
-    update(schema::playground::table.filter(schema::playground::gist.eq(gist_id)))
-        .set(schema::playground::flagged.eq(true))
+/// Makes a playground visible again and removes it from the moderation queue.
+/// Past flags are kept.
+pub fn restore_playground(conn: &mut PgConnection, id: i64) -> QueryResult<usize> {
+    update(schema::playground::table.filter(schema::playground::id.eq(id)))
+        .set((
+            schema::playground::active.eq(true),
+            schema::playground::flagged.eq(false),
+        ))
+        .execute(conn)
+}
+
+pub fn get_playground_by_id(conn: &mut PgConnection, id: i64) -> QueryResult<PlaygroundQuery> {
+    schema::playground::table
+        .filter(schema::playground::id.eq(id))
+        .first(conn)
+}
+
+pub fn get_playground(conn: &mut PgConnection, gist_id: &str) -> QueryResult<PlaygroundQuery> {
+    schema::playground::table
+        .filter(schema::playground::gist.eq(gist_id))
+        .first(conn)
+}
+
+pub fn get_playgrounds_for_user(
+    conn: &mut PgConnection,
+    user_id: i64,
+) -> QueryResult<Vec<PlaygroundQuery>> {
+    schema::playground::table
+        .filter(schema::playground::user_id.eq(user_id))
+        .order_by(schema::playground::updated_at.desc())
+        .get_results(conn)
+}
+
+pub fn get_playground_gists(
+    conn: &mut PgConnection,
+    ids: &[i64],
+) -> QueryResult<Vec<(i64, String)>> {
+    schema::playground::table
+        .filter(schema::playground::id.eq_any(ids))
+        .select((schema::playground::id, schema::playground::gist))
+        .get_results(conn)
+}
+
+pub fn delete_playground(conn: &mut PgConnection, id: i64) -> QueryResult<usize> {
+    delete(schema::playground::table.filter(schema::playground::id.eq(id))).execute(conn)
+}
+
+pub fn create_playground_revision(
+    conn: &mut PgConnection,
+    revision: PlaygroundRevisionInsert,
+) -> QueryResult<usize> {
+    insert_into(schema::playground_revisions::table)
+        .values(&revision)
         .execute(conn)
 }
+
+/// Bumps the revision of a playground and stores the new revision's code.
+/// Returns the new revision number.
+pub fn add_playground_revision(
+    conn: &mut PgConnection,
+    id: i64,
+    title: Option<&str>,
+    code: Value,
+) -> QueryResult<i64> {
+    conn.transaction(|conn| {
+        let revision = match title {
+            Some(title) => update(schema::playground::table.filter(schema::playground::id.eq(id)))
+                .set((
+                    schema::playground::revision.eq(schema::playground::revision + 1),
+                    schema::playground::title.eq(title),
+                    schema::playground::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(schema::playground::revision)
+                .get_result(conn)?,
+            None => update(schema::playground::table.filter(schema::playground::id.eq(id)))
+                .set((
+                    schema::playground::revision.eq(schema::playground::revision + 1),
+                    schema::playground::updated_at.eq(diesel::dsl::now),
+                ))
+                .returning(schema::playground::revision)
+                .get_result(conn)?,
+        };
+        create_playground_revision(
+            conn,
+            PlaygroundRevisionInsert {
+                playground_id: id,
+                revision,
+                code,
+            },
+        )?;
+        Ok(revision)
+    })
+}
+
+/// Undoes `add_playground_revision`, restoring the state of the playground before it.
+pub fn revert_playground_revision(
+    conn: &mut PgConnection,
+    previous: &PlaygroundQuery,
+) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        delete(
+            schema::playground_revisions::table
+                .filter(schema::playground_revisions::playground_id.eq(previous.id))
+                .filter(schema::playground_revisions::revision.gt(previous.revision)),
+        )
+        .execute(conn)?;
+        update(schema::playground::table.filter(schema::playground::id.eq(previous.id)))
+            .set((
+                schema::playground::revision.eq(previous.revision),
+                schema::playground::title.eq(&previous.title),
+                schema::playground::updated_at.eq(previous.updated_at),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+pub fn get_playground_revision(
+    conn: &mut PgConnection,
+    id: i64,
+    revision: i64,
+) -> QueryResult<Option<Value>> {
+    schema::playground_revisions::table
+        .filter(
+            schema::playground_revisions::playground_id
+                .eq(id)
+                .and(schema::playground_revisions::revision.eq(revision)),
+        )
+        .select(schema::playground_revisions::code)
+        .first(conn)
+        .optional()
+}
+
+pub fn get_playground_revisions(
+    conn: &mut PgConnection,
+    id: i64,
+) -> QueryResult<Vec<(i64, NaiveDateTime)>> {
+    schema::playground_revisions::table
+        .filter(schema::playground_revisions::playground_id.eq(id))
+        .select((
+            schema::playground_revisions::revision,
+            schema::playground_revisions::created_at,
+        ))
+        .order_by(schema::playground_revisions::revision.desc())
+        .get_results(conn)
+}
+
+pub fn create_playground_code(
+    conn: &mut PgConnection,
+    code: PlaygroundCodeInsert,
+) -> QueryResult<usize> {
+    insert_into(schema::playground_code::table)
+        .values(&code)
+        .on_conflict_do_nothing()
+        .execute(conn)
+}
+
+pub fn upsert_playground_code(
+    conn: &mut PgConnection,
+    code: PlaygroundCodeInsert,
+) -> QueryResult<usize> {
+    insert_into(schema::playground_code::table)
+        .values(&code)
+        .on_conflict(schema::playground_code::gist)
+        .do_update()
+        .set(schema::playground_code::code.eq(&code.code))
+        .execute(conn)
+}
+
+pub fn delete_playground_code(conn: &mut PgConnection, gist_id: &str) -> QueryResult<usize> {
+    delete(schema::playground_code::table.filter(schema::playground_code::gist.eq(gist_id)))
+        .execute(conn)
+}
+
+pub fn get_playground_code(conn: &mut PgConnection, gist_id: &str) -> QueryResult<Option<Value>> {
+    schema::playground_code::table
+        .filter(schema::playground_code::gist.eq(gist_id))
+        .select(schema::playground_code::code)
+        .first(conn)
+        .optional()
+}
+
+pub fn get_gists_without_code(conn: &mut PgConnection) -> QueryResult<Vec<String>> {
+    schema::playground::table
+        .filter(
+            schema::playground::gist
+                .ne_all(schema::playground_code::table.select(schema::playground_code::gist)),
+        )
+        .select(schema::playground::gist)
+        .order_by(schema::playground::id)
+        .get_results(conn)
+}
+
+pub fn count_playgrounds_by_key_version(conn: &mut PgConnection) -> QueryResult<Vec<(i32, i64)>> {
+    schema::playground::table
+        .group_by(schema::playground::key_version)
+        .select((schema::playground::key_version, diesel::dsl::count_star()))
+        .order_by(schema::playground::key_version)
+        .get_results(conn)
+}
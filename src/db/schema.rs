@@ -5,10 +5,18 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "ai_help_message_status"))]
     pub struct AiHelpMessageStatus;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "baseline_status"))]
+    pub struct BaselineStatus;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "bcd_event_type"))]
     pub struct BcdEventType;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "bcd_sync_status"))]
+    pub struct BcdSyncStatus;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "engine_type"))]
     pub struct EngineType;
@@ -134,6 +142,7 @@ diesel::table! {
 diesel::table! {
     use diesel::sql_types::*;
     use crate::db::types::*;
+    use super::sql_types::BaselineStatus;
 
     bcd_features (id) {
         id -> Int8,
@@ -145,6 +154,42 @@ diesel::table! {
         source_file -> Text,
         spec_url -> Nullable<Text>,
         standard_track -> Nullable<Bool>,
+        baseline_status -> BaselineStatus,
+        baseline_date -> Nullable<Date>,
+        baseline_changed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::types::*;
+
+    bcd_notifications (id) {
+        id -> Int8,
+        user_id -> Int8,
+        bcd_update_id -> Int8,
+        document_id -> Int8,
+        created_at -> Timestamp,
+        read_at -> Nullable<Timestamp>,
+        mailed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::types::*;
+    use super::sql_types::BcdSyncStatus;
+
+    bcd_sync_runs (id) {
+        id -> Int8,
+        started_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        status -> BcdSyncStatus,
+        inserted -> Int8,
+        updated -> Int8,
+        removed -> Int8,
+        report -> Jsonb,
+        errors -> Array<Nullable<Text>>,
     }
 }
 
@@ -162,6 +207,8 @@ diesel::table! {
         event_type -> BcdEventType,
         feature -> Int8,
         engines -> Array<Nullable<EngineType>>,
+        statement_before -> Nullable<Jsonb>,
+        statement_after -> Nullable<Jsonb>,
     }
 }
 
@@ -254,6 +301,49 @@ diesel::table! {
         active -> Bool,
         flagged -> Bool,
         deleted_user_id -> Nullable<Int8>,
+        title -> Nullable<Text>,
+        revision -> Int8,
+        parent_id -> Nullable<Int8>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        key_version -> Int4,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::types::*;
+
+    playground_code (id) {
+        id -> Int8,
+        gist -> Text,
+        code -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::types::*;
+
+    playground_flags (id) {
+        id -> Int8,
+        playground_id -> Int8,
+        reason -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::types::*;
+
+    playground_revisions (id) {
+        id -> Int8,
+        playground_id -> Int8,
+        revision -> Int8,
+        code -> Jsonb,
+        created_at -> Timestamp,
     }
 }
 
@@ -269,6 +359,26 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::db::types::*;
+
+    search_query_stats (id) {
+        id -> Int8,
+        day -> Date,
+        query -> Text,
+        locales -> Text,
+        sort -> Text,
+        searches -> Int8,
+        paginated -> Int8,
+        zero_results -> Int8,
+        suggestions_shown -> Int8,
+        suggestions_clicked -> Int8,
+        total_hits -> Int8,
+        took_ms -> Int8,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use crate::db::types::*;
@@ -281,6 +391,7 @@ diesel::table! {
         mdnplus_newsletter -> Bool,
         no_ads -> Bool,
         ai_help_history -> Bool,
+        bcd_digest -> Bool,
     }
 }
 
@@ -342,6 +453,9 @@ diesel::joinable!(ai_help_history -> users (user_id));
 diesel::joinable!(ai_help_history_messages -> users (user_id));
 diesel::joinable!(ai_help_limits -> users (user_id));
 diesel::joinable!(ai_help_message_meta -> users (user_id));
+diesel::joinable!(bcd_notifications -> bcd_updates (bcd_update_id));
+diesel::joinable!(bcd_notifications -> documents (document_id));
+diesel::joinable!(bcd_notifications -> users (user_id));
 diesel::joinable!(bcd_updates -> bcd_features (feature));
 diesel::joinable!(bcd_updates -> browser_releases (browser_release));
 diesel::joinable!(browser_releases -> browsers (browser));
@@ -350,6 +464,8 @@ diesel::joinable!(collection_items -> multiple_collections (multiple_collection_
 diesel::joinable!(collection_items -> users (user_id));
 diesel::joinable!(multiple_collections -> users (user_id));
 diesel::joinable!(playground -> users (user_id));
+diesel::joinable!(playground_flags -> playground (playground_id));
+diesel::joinable!(playground_revisions -> playground (playground_id));
 diesel::joinable!(settings -> users (user_id));
 diesel::joinable!(user_subscription_transitions -> users (user_id));
 
@@ -361,6 +477,8 @@ diesel::allow_tables_to_appear_in_same_query!(
     ai_help_limits,
     ai_help_message_meta,
     bcd_features,
+    bcd_notifications,
+    bcd_sync_runs,
     bcd_updates,
     browser_releases,
     browsers,
@@ -368,7 +486,11 @@ diesel::allow_tables_to_appear_in_same_query!(
     documents,
     multiple_collections,
     playground,
+    playground_code,
+    playground_flags,
+    playground_revisions,
     raw_webhook_events_tokens,
+    search_query_stats,
     settings,
     user_subscription_transitions,
     users,
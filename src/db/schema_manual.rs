@@ -3,6 +3,7 @@ use crate::db::schema::*;
 diesel::table! {
     use diesel::sql_types::*;
     use crate::db::types::*;
+    use crate::db::schema::sql_types::BaselineStatus;
     use crate::db::schema::sql_types::BcdEventType;
 
     bcd_updates_view (browser, event_type, release_id, path) {
@@ -26,6 +27,10 @@ diesel::table! {
         standard_track -> Nullable<Bool>,
         status -> Nullable<Text>,
         engines -> Array<Nullable<EngineType>>,
+        statement_before -> Nullable<Jsonb>,
+        statement_after -> Nullable<Jsonb>,
+        baseline_status -> Nullable<BaselineStatus>,
+        baseline_date -> Nullable<Date>,
     }
 }
 
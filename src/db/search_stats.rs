@@ -0,0 +1,70 @@
+use chrono::NaiveDate;
+use diesel::sql_types::{BigInt, Date};
+use diesel::upsert::excluded;
+use diesel::{insert_into, prelude::*, sql_query, PgConnection};
+
+use crate::db::model::{SearchQueryReport, SearchQueryStatsInsert};
+use crate::db::schema::search_query_stats as stats;
+
+pub enum SearchReportKind {
+    Top,
+    ZeroResults,
+    Suggested,
+}
+
+/// Adds a (sampled) search to the daily aggregate of its query, locales and sort.
+pub fn record_search(
+    conn: &mut PgConnection,
+    search: SearchQueryStatsInsert,
+) -> QueryResult<usize> {
+    insert_into(stats::table)
+        .values(&search)
+        .on_conflict((stats::day, stats::query, stats::locales, stats::sort))
+        .do_update()
+        .set((
+            stats::searches.eq(stats::searches + excluded(stats::searches)),
+            stats::paginated.eq(stats::paginated + excluded(stats::paginated)),
+            stats::zero_results.eq(stats::zero_results + excluded(stats::zero_results)),
+            stats::suggestions_shown
+                .eq(stats::suggestions_shown + excluded(stats::suggestions_shown)),
+            stats::suggestions_clicked
+                .eq(stats::suggestions_clicked + excluded(stats::suggestions_clicked)),
+            stats::total_hits.eq(excluded(stats::total_hits)),
+            stats::took_ms.eq(stats::took_ms + excluded(stats::took_ms)),
+        ))
+        .execute(conn)
+}
+
+pub fn search_report(
+    conn: &mut PgConnection,
+    kind: SearchReportKind,
+    since: NaiveDate,
+    limit: i64,
+) -> QueryResult<Vec<SearchQueryReport>> {
+    let (having, order_by) = match kind {
+        SearchReportKind::Top => ("TRUE", "searches DESC"),
+        SearchReportKind::ZeroResults => ("SUM(zero_results) > 0", "zero_results DESC"),
+        SearchReportKind::Suggested => (
+            "SUM(suggestions_shown) > 0",
+            "suggestions_clicked DESC, suggestions_shown DESC",
+        ),
+    };
+    sql_query(format!(
+        "SELECT
+  query,
+  SUM(searches)::BIGINT AS searches,
+  SUM(zero_results)::BIGINT AS zero_results,
+  SUM(suggestions_shown)::BIGINT AS suggestions_shown,
+  SUM(suggestions_clicked)::BIGINT AS suggestions_clicked,
+  (SUM(took_ms) / GREATEST(SUM(searches), 1))::BIGINT AS avg_took_ms
+FROM search_query_stats
+WHERE day >= $1
+GROUP BY query
+HAVING {having}
+ORDER BY {order_by}, query
+LIMIT $2"
+    ))
+    .bind::<Date, _>(since)
+    .bind::<BigInt, _>(limit)
+    .load(conn)
+}
@@ -155,7 +155,6 @@ impl From<Subscription> for String {
     PartialOrd,
     Serialize,
 )]
-// We only currently (22-12-2022) support added/removed stable.
 #[ExistingTypePath = "crate::db::schema::sql_types::BcdEventType"]
 pub enum BcdUpdateEventType {
     #[serde(rename = "added_stable")]
@@ -168,10 +167,44 @@ pub enum BcdUpdateEventType {
     AddedNonNull,
     #[serde(rename = "removed_stable")]
     RemovedStable,
+    #[serde(rename = "added_flag")]
+    AddedFlag,
+    #[serde(rename = "removed_flag")]
+    RemovedFlag,
+    #[serde(rename = "added_prefix")]
+    AddedPrefix,
+    #[serde(rename = "removed_prefix")]
+    RemovedPrefix,
+    #[serde(rename = "added_partial")]
+    AddedPartial,
+    #[serde(rename = "removed_partial")]
+    RemovedPartial,
+    #[serde(rename = "version_corrected")]
+    VersionCorrected,
     #[serde(other)]
     Unknown,
 }
 
+#[derive(
+    Copy, Clone, diesel_derive_enum::DbEnum, Debug, Deserialize, Eq, Hash, PartialEq, Serialize,
+)]
+#[ExistingTypePath = "crate::db::schema::sql_types::BaselineStatus"]
+#[serde(rename_all = "snake_case")]
+pub enum BaselineStatus {
+    Limited,
+    Newly,
+    Widely,
+}
+
+#[derive(Copy, Clone, diesel_derive_enum::DbEnum, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[ExistingTypePath = "crate::db::schema::sql_types::BcdSyncStatus"]
+#[serde(rename_all = "snake_case")]
+pub enum BcdSyncStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
 #[derive(Copy, Clone, diesel_derive_enum::DbEnum, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[ExistingTypePath = "crate::db::schema::sql_types::EngineType"]
 pub enum EngineType {
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use chrono::{Months, NaiveDate};
+use serde_json::Value;
+
+use crate::db::types::{BaselineStatus, BcdUpdateEventType};
+use crate::db::v2::bcd_feed::supports;
+
+/// The browsers a feature has to ship in to be interoperable.
+pub const CORE_BROWSERS: [&str; 7] = [
+    "chrome",
+    "chrome_android",
+    "edge",
+    "firefox",
+    "firefox_android",
+    "safari",
+    "safari_ios",
+];
+
+/// How long a feature has to be interoperable to be widely available.
+const WIDELY_AVAILABLE_AFTER: Months = Months::new(30);
+
+pub struct SupportEvent<'a> {
+    pub browser: &'a str,
+    pub release_date: NaiveDate,
+    pub event_type: BcdUpdateEventType,
+    pub statement_after: Option<&'a Value>,
+}
+
+/// Whether an event leaves a feature available (`Some(true)`) or unavailable
+/// (`Some(false)`) by default in a browser. The statement after the change decides,
+/// without one only the event types that imply it count.
+fn support_change(event: &SupportEvent) -> Option<bool> {
+    if let Some(after) = event.statement_after {
+        return Some(supports(after));
+    }
+    match event.event_type {
+        BcdUpdateEventType::AddedStable => Some(true),
+        BcdUpdateEventType::RemovedStable | BcdUpdateEventType::AddedFlag => Some(false),
+        _ => None,
+    }
+}
+
+/// The date since which a feature is supported by every core browser, or `None` if one
+/// of them does not support it (anymore). A browser counts from the release since which
+/// it supports the feature without interruption until today.
+pub fn baseline_date<'a>(events: impl IntoIterator<Item = SupportEvent<'a>>) -> Option<NaiveDate> {
+    let mut events: Vec<_> = events.into_iter().collect();
+    events.sort_by_key(|e| e.release_date);
+
+    let mut supported_since: HashMap<&str, Option<NaiveDate>> = HashMap::new();
+    for event in events {
+        let Some(supported) = support_change(&event) else {
+            continue;
+        };
+        let since = supported_since.entry(event.browser).or_default();
+        match (supported, *since) {
+            (true, None) => *since = Some(event.release_date),
+            (false, _) => *since = None,
+            _ => {}
+        }
+    }
+
+    CORE_BROWSERS
+        .iter()
+        .map(|browser| supported_since.get(browser).copied().flatten())
+        .try_fold(None, |latest: Option<NaiveDate>, since| {
+            since.map(|since| latest.max(Some(since)))
+        })
+        .flatten()
+}
+
+/// Limited without a Baseline date, newly available from that date on and widely
+/// available 30 months later.
+pub fn baseline_status(baseline_date: Option<NaiveDate>, today: NaiveDate) -> BaselineStatus {
+    match baseline_date {
+        None => BaselineStatus::Limited,
+        Some(date) if date + WIDELY_AVAILABLE_AFTER <= today => BaselineStatus::Widely,
+        Some(_) => BaselineStatus::Newly,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event<'a>(
+        browser: &'a str,
+        date: &str,
+        event_type: BcdUpdateEventType,
+        statement_after: Option<&'a Value>,
+    ) -> SupportEvent<'a> {
+        SupportEvent {
+            browser,
+            release_date: date.parse().unwrap(),
+            event_type,
+            statement_after,
+        }
+    }
+
+    #[test]
+    fn test_baseline_date() {
+        use BcdUpdateEventType::*;
+        let stable = json!({"version_added": "100"});
+        let flagged = json!({"version_added": "90", "flags": [{"type": "preference"}]});
+        let unsupported = json!({"version_added": false});
+        let preview = json!({"version_added": "preview"});
+        let events = || {
+            vec![
+                event("chrome", "2020-01-01", AddedStable, None),
+                event("chrome_android", "2020-01-01", AddedStable, None),
+                event("edge", "2020-02-01", AddedStable, None),
+                event("firefox", "2021-01-01", RemovedFlag, Some(&stable)),
+                event("firefox_android", "2021-01-01", RemovedFlag, Some(&stable)),
+                event("safari_ios", "2022-03-01", AddedStable, None),
+                event("safari", "2022-03-01", AddedStable, None),
+            ]
+        };
+        assert_eq!(baseline_date(events()), "2022-03-01".parse().ok());
+
+        let mut removed = events();
+        removed.push(event("firefox", "2023-01-01", RemovedStable, None));
+        assert_eq!(baseline_date(removed), None);
+
+        let mut readded = events();
+        readded.push(event("firefox", "2023-01-01", AddedFlag, Some(&flagged)));
+        readded.push(event("firefox", "2023-06-01", RemovedFlag, Some(&stable)));
+        assert_eq!(baseline_date(readded), "2023-06-01".parse().ok());
+
+        let mut other_browser = events();
+        other_browser.push(event("opera", "2019-01-01", AddedStable, None));
+        other_browser.push(event("opera", "2023-01-01", RemovedStable, None));
+        assert_eq!(baseline_date(other_browser), "2022-03-01".parse().ok());
+
+        // A flag removed without shipping leaves the feature unsupported.
+        let mut flag_dropped = events();
+        flag_dropped.push(event("firefox", "2023-01-01", AddedFlag, Some(&flagged)));
+        flag_dropped.push(event("firefox", "2023-06-01", RemovedFlag, Some(&unsupported)));
+        assert_eq!(baseline_date(flag_dropped), None);
+
+        let preview_only = events()
+            .into_iter()
+            .filter(|e| e.browser != "firefox")
+            .chain([event("firefox", "2023-01-01", AddedPreview, Some(&preview))]);
+        assert_eq!(baseline_date(preview_only), None);
+
+        let no_safari_ios = events().into_iter().take(5).chain(events().into_iter().skip(6));
+        assert_eq!(baseline_date(no_safari_ios), None);
+    }
+
+    #[test]
+    fn test_baseline_status() {
+        let today = "2024-09-01".parse().unwrap();
+        assert_eq!(baseline_status(None, today), BaselineStatus::Limited);
+        assert_eq!(
+            baseline_status("2022-03-01".parse().ok(), today),
+            BaselineStatus::Widely
+        );
+        assert_eq!(
+            baseline_status("2022-03-02".parse().ok(), today),
+            BaselineStatus::Newly
+        );
+    }
+}
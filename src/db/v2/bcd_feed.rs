@@ -0,0 +1,600 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::db::types::{BcdUpdateEventType, EngineType};
+
+/// A record of the BCD updates feed (or the MDN metadata) that could not be used.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FeedError {
+    pub record: String,
+    pub message: String,
+}
+
+impl FeedError {
+    fn new(record: impl Into<String>, message: impl ToString) -> Self {
+        FeedError {
+            record: record.into(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for FeedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.record, self.message)
+    }
+}
+
+#[derive(Deserialize)]
+struct RawFeed {
+    browsers: Map<String, Value>,
+    features: Vec<Value>,
+    added_removed: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct RawBrowser {
+    name: String,
+    accepts_flags: bool,
+    accepts_webextensions: bool,
+    pref_url: Option<String>,
+    preview_name: Option<String>,
+    #[serde(default)]
+    releases: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+struct RawRelease {
+    engine: Option<String>,
+    engine_version: Option<String>,
+    release_date: Option<NaiveDate>,
+    release_notes: Option<String>,
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawReleaseEntry {
+    Release {
+        browser: String,
+        version: String,
+    },
+    Changes {
+        #[serde(default)]
+        added: Vec<RawEvent>,
+        #[serde(default)]
+        removed: Vec<RawEvent>,
+        #[serde(default)]
+        changed: Vec<RawChange>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawEvent {
+    Path(String),
+    Detailed {
+        path: String,
+        #[serde(default)]
+        engines: Vec<EngineType>,
+        before: Option<Value>,
+        after: Option<Value>,
+    },
+}
+
+impl RawEvent {
+    fn into_change(self, event_type: BcdUpdateEventType) -> RawChange {
+        match self {
+            RawEvent::Path(path) => RawChange {
+                path,
+                event_type: Some(event_type),
+                engines: vec![],
+                before: None,
+                after: None,
+            },
+            RawEvent::Detailed {
+                path,
+                engines,
+                before,
+                after,
+            } => RawChange {
+                path,
+                event_type: Some(event_type),
+                engines,
+                before,
+                after,
+            },
+        }
+    }
+}
+
+/// A change of a support statement. Without an explicit `type` the kind of change is
+/// derived from the statements, see [`classify`].
+#[derive(Deserialize)]
+struct RawChange {
+    path: String,
+    #[serde(rename = "type")]
+    event_type: Option<BcdUpdateEventType>,
+    #[serde(default)]
+    engines: Vec<EngineType>,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum SpecUrl {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl SpecUrl {
+    /// The column holds a single URL, features with several specs are stored without one.
+    pub fn single(&self) -> Option<&str> {
+        match self {
+            SpecUrl::Single(url) => Some(url),
+            SpecUrl::Multiple(_) => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct FeatureStatus {
+    pub deprecated: Option<bool>,
+    pub experimental: Option<bool>,
+    pub standard_track: Option<bool>,
+}
+
+pub struct Browser {
+    pub name: String,
+    pub display_name: String,
+    pub accepts_flags: bool,
+    pub accepts_webextensions: bool,
+    pub pref_url: Option<String>,
+    pub preview_name: Option<String>,
+}
+
+pub struct Release {
+    pub browser: String,
+    pub release_id: String,
+    pub engine: Option<String>,
+    pub engine_version: Option<String>,
+    pub release_date: NaiveDate,
+    pub release_notes: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct Feature {
+    pub path: String,
+    pub mdn_url: Option<String>,
+    pub source_file: String,
+    pub spec_url: Option<SpecUrl>,
+    #[serde(default)]
+    pub status: FeatureStatus,
+}
+
+pub struct Update {
+    pub browser: String,
+    pub version: String,
+    pub path: String,
+    pub event_type: BcdUpdateEventType,
+    pub engines: Vec<EngineType>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+/// The parsed BCD updates feed. Records that fail to parse, or that reference releases
+/// or features missing from the feed, are left out and reported in `errors`.
+#[derive(Default)]
+pub struct BcdFeed {
+    pub browsers: Vec<Browser>,
+    pub releases: Vec<Release>,
+    pub features: Vec<Feature>,
+    pub updates: Vec<Update>,
+    pub errors: Vec<FeedError>,
+}
+
+impl BcdFeed {
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, FeedError> {
+        let raw: RawFeed = serde_json::from_slice(bytes).map_err(|e| FeedError::new("feed", e))?;
+        Ok(Self::from_raw(raw))
+    }
+
+    pub fn from_value(value: Value) -> Result<Self, FeedError> {
+        let raw: RawFeed = serde_json::from_value(value).map_err(|e| FeedError::new("feed", e))?;
+        Ok(Self::from_raw(raw))
+    }
+
+    fn from_raw(raw: RawFeed) -> Self {
+        let mut feed = BcdFeed::default();
+        for (name, value) in raw.browsers {
+            feed.push_browser(name, value);
+        }
+        for (i, value) in raw.features.into_iter().enumerate() {
+            let record = match value["path"].as_str() {
+                Some(path) => format!("features.{path}"),
+                None => format!("features[{i}]"),
+            };
+            match serde_json::from_value::<Feature>(value) {
+                Ok(feature) => feed.features.push(feature),
+                Err(e) => feed.errors.push(FeedError::new(record, e)),
+            }
+        }
+        let releases: HashSet<(&str, &str)> = feed
+            .releases
+            .iter()
+            .map(|r| (r.browser.as_str(), r.release_id.as_str()))
+            .collect();
+        let features: HashSet<&str> = feed.features.iter().map(|f| f.path.as_str()).collect();
+        let mut updates = vec![];
+        let mut errors = vec![];
+        for (i, value) in raw.added_removed.into_iter().enumerate() {
+            let record = format!("added_removed[{i}]");
+            let entries = match serde_json::from_value::<Vec<RawReleaseEntry>>(value) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    errors.push(FeedError::new(record, e));
+                    continue;
+                }
+            };
+            let Some((browser, version)) = entries.iter().find_map(|entry| match entry {
+                RawReleaseEntry::Release { browser, version } => {
+                    Some((browser.clone(), version.clone()))
+                }
+                _ => None,
+            }) else {
+                errors.push(FeedError::new(record, "missing browser and version"));
+                continue;
+            };
+            if !releases.contains(&(browser.as_str(), version.as_str())) {
+                errors.push(FeedError::new(
+                    record,
+                    format!("unknown release {browser} {version}"),
+                ));
+                continue;
+            }
+            for entry in entries {
+                let RawReleaseEntry::Changes {
+                    added,
+                    removed,
+                    changed,
+                } = entry
+                else {
+                    continue;
+                };
+                let changes = added
+                    .into_iter()
+                    .map(|e| e.into_change(BcdUpdateEventType::AddedStable))
+                    .chain(
+                        removed
+                            .into_iter()
+                            .map(|e| e.into_change(BcdUpdateEventType::RemovedStable)),
+                    )
+                    .chain(changed);
+                for change in changes {
+                    let record = format!("{record}.{}", change.path);
+                    if !features.contains(change.path.as_str()) {
+                        errors.push(FeedError::new(record, "unknown feature"));
+                        continue;
+                    }
+                    let event_type = change
+                        .event_type
+                        .unwrap_or_else(|| classify(change.before.as_ref(), change.after.as_ref()));
+                    if event_type == BcdUpdateEventType::Unknown {
+                        errors.push(FeedError::new(record, "unknown type of change"));
+                        continue;
+                    }
+                    updates.push(Update {
+                        browser: browser.clone(),
+                        version: version.clone(),
+                        path: change.path,
+                        event_type,
+                        engines: change.engines,
+                        before: change.before,
+                        after: change.after,
+                    });
+                }
+            }
+        }
+        feed.updates = updates;
+        feed.errors.extend(errors);
+        feed
+    }
+
+    fn push_browser(&mut self, name: String, value: Value) {
+        let record = format!("browsers.{name}");
+        let browser = match serde_json::from_value::<RawBrowser>(value) {
+            Ok(browser) => browser,
+            Err(e) => {
+                self.errors.push(FeedError::new(record, e));
+                return;
+            }
+        };
+        for (release_id, value) in browser.releases {
+            let release = match serde_json::from_value::<RawRelease>(value) {
+                Ok(release) => release,
+                Err(e) => {
+                    self.errors
+                        .push(FeedError::new(format!("{record}.{release_id}"), e));
+                    continue;
+                }
+            };
+            // Planned releases have no date yet, there is nothing to show for them.
+            let Some(release_date) = release.release_date else {
+                debug!("No release_date for {} {}", name, release_id);
+                continue;
+            };
+            self.releases.push(Release {
+                browser: name.clone(),
+                release_id,
+                engine: release.engine,
+                engine_version: release.engine_version,
+                release_date,
+                release_notes: release.release_notes,
+                status: release.status,
+            });
+        }
+        self.browsers.push(Browser {
+            name,
+            display_name: browser.name,
+            accepts_flags: browser.accepts_flags,
+            accepts_webextensions: browser.accepts_webextensions,
+            pref_url: browser.pref_url,
+            preview_name: browser.preview_name,
+        });
+    }
+}
+
+/// The first statement of a support entry, BCD lists the current one first.
+fn primary(statement: Option<&Value>) -> Option<&Map<String, Value>> {
+    match statement? {
+        Value::Array(statements) => statements.first()?.as_object(),
+        statement => statement.as_object(),
+    }
+}
+
+fn has_flags(statement: Option<&Map<String, Value>>) -> bool {
+    statement
+        .and_then(|s| s.get("flags"))
+        .and_then(Value::as_array)
+        .is_some_and(|flags| !flags.is_empty())
+}
+
+fn has_prefix(statement: Option<&Map<String, Value>>) -> bool {
+    statement.is_some_and(|s| s.get("prefix").is_some_and(Value::is_string))
+}
+
+fn is_partial(statement: Option<&Map<String, Value>>) -> bool {
+    statement
+        .and_then(|s| s.get("partial_implementation"))
+        .and_then(Value::as_bool)
+        .unwrap_or_default()
+}
+
+fn version_added(statement: Option<&Map<String, Value>>) -> Option<&Value> {
+    statement
+        .and_then(|s| s.get("version_added"))
+        .filter(|v| !matches!(v, Value::Null | Value::Bool(false)))
+}
+
+/// Whether the statement is neither removed again nor behind a flag.
+fn is_current(statement: Option<&Map<String, Value>>) -> bool {
+    statement.and_then(|s| s.get("version_removed")).is_none() && !has_flags(statement)
+}
+
+fn is_preview(statement: Option<&Map<String, Value>>) -> bool {
+    version_added(statement).is_some_and(|v| v == "preview") && is_current(statement)
+}
+
+/// Whether a stable release supports the feature by default, preview support doesn't
+/// count.
+fn is_supported(statement: Option<&Map<String, Value>>) -> bool {
+    version_added(statement).is_some_and(|v| v != "preview") && is_current(statement)
+}
+
+/// Whether a support statement, as stored with an update, supports the feature by
+/// default in a stable release.
+pub fn supports(statement: &Value) -> bool {
+    is_supported(primary(Some(statement)))
+}
+
+/// Derives the kind of change between two support statements of a browser. Returns
+/// `Unknown` when nothing we track changed.
+pub fn classify(before: Option<&Value>, after: Option<&Value>) -> BcdUpdateEventType {
+    let (before, after) = (primary(before), primary(after));
+    match (is_supported(before), is_supported(after)) {
+        (false, true) if has_flags(before) => BcdUpdateEventType::RemovedFlag,
+        (false, true) => BcdUpdateEventType::AddedStable,
+        (true, false) if has_flags(after) => BcdUpdateEventType::AddedFlag,
+        (true, false) => BcdUpdateEventType::RemovedStable,
+        (false, false) => match (has_flags(before), has_flags(after)) {
+            (false, true) => BcdUpdateEventType::AddedFlag,
+            _ if is_preview(after) && !is_preview(before) => BcdUpdateEventType::AddedPreview,
+            // A flag dropped without shipping, or preview support withdrawn.
+            (true, false) => BcdUpdateEventType::RemovedStable,
+            _ if is_preview(before) && !is_preview(after) => BcdUpdateEventType::RemovedStable,
+            _ => BcdUpdateEventType::Unknown,
+        },
+        (true, true) => {
+            if has_prefix(before) != has_prefix(after) {
+                if has_prefix(after) {
+                    BcdUpdateEventType::AddedPrefix
+                } else {
+                    BcdUpdateEventType::RemovedPrefix
+                }
+            } else if is_partial(before) != is_partial(after) {
+                if is_partial(after) {
+                    BcdUpdateEventType::AddedPartial
+                } else {
+                    BcdUpdateEventType::RemovedPartial
+                }
+            } else if version_added(before) != version_added(after) {
+                BcdUpdateEventType::VersionCorrected
+            } else {
+                BcdUpdateEventType::Unknown
+            }
+        }
+    }
+}
+
+/// A page of the MDN metadata that documents BCD features.
+pub struct PageMetadata {
+    pub mdn_url: String,
+    pub short_title: String,
+    pub browser_compat: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawPageMetadata {
+    mdn_url: String,
+    short_title: String,
+    #[serde(rename = "browserCompat")]
+    browser_compat: Vec<String>,
+}
+
+/// Parses the MDN metadata, pages without `browserCompat` are skipped.
+pub fn parse_metadata(value: Value) -> Result<(Vec<PageMetadata>, Vec<FeedError>), FeedError> {
+    let pages: Vec<Value> =
+        serde_json::from_value(value).map_err(|e| FeedError::new("metadata", e))?;
+    let mut parsed = vec![];
+    let mut errors = vec![];
+    for (i, page) in pages.into_iter().enumerate() {
+        if page["browserCompat"].as_array().is_none_or(Vec::is_empty) {
+            continue;
+        }
+        let record = match page["mdn_url"].as_str() {
+            Some(url) => format!("metadata.{url}"),
+            None => format!("metadata[{i}]"),
+        };
+        match serde_json::from_value::<RawPageMetadata>(page) {
+            Ok(page) => parsed.push(PageMetadata {
+                mdn_url: page.mdn_url,
+                short_title: page.short_title,
+                browser_compat: page.browser_compat,
+            }),
+            Err(e) => errors.push(FeedError::new(record, e)),
+        }
+    }
+    Ok((parsed, errors))
+}
+
+/// Counts of the records of a feed that would be synchronized.
+#[derive(Serialize)]
+pub struct FeedSummary {
+    pub valid: bool,
+    pub browsers: usize,
+    pub releases: usize,
+    pub features: usize,
+    pub updates: usize,
+    pub errors: Vec<FeedError>,
+}
+
+impl From<&BcdFeed> for FeedSummary {
+    fn from(feed: &BcdFeed) -> Self {
+        FeedSummary {
+            valid: feed.errors.is_empty(),
+            browsers: feed.browsers.len(),
+            releases: feed.releases.len(),
+            features: feed.features.len(),
+            updates: feed.updates.len(),
+            errors: feed.errors.clone(),
+        }
+    }
+}
+
+impl From<FeedError> for FeedSummary {
+    fn from(error: FeedError) -> Self {
+        FeedSummary {
+            valid: false,
+            browsers: 0,
+            releases: 0,
+            features: 0,
+            updates: 0,
+            errors: vec![error],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let flagged =
+            json!({"version_added": "100", "flags": [{"type": "preference", "name": "foo"}]});
+        let stable = json!({"version_added": "107"});
+        let cases = [
+            (
+                None,
+                json!({"version_added": "107"}),
+                BcdUpdateEventType::AddedStable,
+            ),
+            (
+                Some(flagged.clone()),
+                stable.clone(),
+                BcdUpdateEventType::RemovedFlag,
+            ),
+            (
+                Some(stable.clone()),
+                flagged.clone(),
+                BcdUpdateEventType::AddedFlag,
+            ),
+            (
+                Some(json!({"version_added": false})),
+                flagged.clone(),
+                BcdUpdateEventType::AddedFlag,
+            ),
+            (
+                Some(flagged.clone()),
+                json!({"version_added": false}),
+                BcdUpdateEventType::RemovedStable,
+            ),
+            (
+                Some(json!({"version_added": false})),
+                json!({"version_added": "preview"}),
+                BcdUpdateEventType::AddedPreview,
+            ),
+            (
+                Some(flagged),
+                json!({"version_added": "preview"}),
+                BcdUpdateEventType::AddedPreview,
+            ),
+            (
+                Some(json!({"version_added": "preview"})),
+                stable.clone(),
+                BcdUpdateEventType::AddedStable,
+            ),
+            (
+                Some(json!([{"version_added": "10", "prefix": "-webkit-"}])),
+                json!([{"version_added": "10"}, {"version_added": "5", "prefix": "-webkit-"}]),
+                BcdUpdateEventType::RemovedPrefix,
+            ),
+            (
+                Some(json!({"version_added": "10", "partial_implementation": true})),
+                stable.clone(),
+                BcdUpdateEventType::RemovedPartial,
+            ),
+            (
+                Some(json!({"version_added": "106"})),
+                stable.clone(),
+                BcdUpdateEventType::VersionCorrected,
+            ),
+            (
+                Some(stable.clone()),
+                json!({"version_added": "107", "version_removed": "120"}),
+                BcdUpdateEventType::RemovedStable,
+            ),
+            (Some(stable.clone()), stable, BcdUpdateEventType::Unknown),
+        ];
+        for (before, after, expected) in cases {
+            assert_eq!(classify(before.as_ref(), Some(&after)), expected, "{after}");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use actix_http::StatusCode;
+use actix_web::web::{self, Bytes};
+use reqwest::Client;
+use serde_json::Value;
+use url::Url;
+
+use crate::api::error::ApiError;
+
+const BCD_UPDATES_FILE: &str = "bcd-updates.json";
+const METADATA_FILE: &str = "metadata.json";
+
+/// Fetches a source over http(s). The BCD sync sources may also be `file://` URLs,
+/// see [`load_bcd_updates`] and [`load_mdn_metadata`].
+pub async fn fetch(client: &Client, url: &Url) -> Result<Bytes, ApiError> {
+    client
+        .get(url.clone())
+        .send()
+        .await
+        .and_then(|res| res.error_for_status())
+        .map_err(|err: reqwest::Error| match err.status() {
+            Some(StatusCode::NOT_FOUND) => {
+                warn!("Error NOT_FOUND fetching {} ", url);
+                ApiError::DocumentNotFound
+            }
+            _ => {
+                warn!("Error Unknown fetching {}: {}", url, err);
+                ApiError::Unknown
+            }
+        })?
+        .bytes()
+        .await
+        .map_err(|err| {
+            error!("{:1}", err);
+            ApiError::DocumentNotFound
+        })
+}
+
+fn local_path(url: &Url) -> Result<PathBuf, ApiError> {
+    url.to_file_path().map_err(|_| ApiError::MalformedUrl)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, ApiError> {
+    fs::read(path).map_err(|e| {
+        warn!("Error reading {}: {}", path.display(), e);
+        ApiError::DocumentNotFound
+    })
+}
+
+/// Loads the BCD updates feed. A `file://` URL may point to the feed itself or to a
+/// directory containing a `bcd-updates.json`.
+pub async fn load_bcd_updates(client: &Client, url: &Url) -> Result<Bytes, ApiError> {
+    if url.scheme() != "file" {
+        return fetch(client, url).await;
+    }
+    let path = local_path(url)?;
+    let bytes = web::block(move || {
+        if path.is_dir() {
+            read(&path.join(BCD_UPDATES_FILE))
+        } else {
+            read(&path)
+        }
+    })
+    .await??;
+    Ok(Bytes::from(bytes))
+}
+
+/// Loads the MDN metadata. A `file://` URL may point to the combined `metadata.json`
+/// of a locale or to a content build directory, in which case the `metadata.json` of
+/// every page below it is collected.
+pub async fn load_mdn_metadata(client: &Client, url: &Url) -> Result<Value, ApiError> {
+    let bytes = if url.scheme() != "file" {
+        fetch(client, url).await?.to_vec()
+    } else {
+        let path = local_path(url)?;
+        if path.is_dir() {
+            return web::block(move || {
+                let mut pages = vec![];
+                collect_metadata(&path, &mut pages)?;
+                Ok(Value::Array(pages))
+            })
+            .await?;
+        }
+        web::block(move || read(&path)).await??
+    };
+    serde_json::from_slice(&bytes).map_err(|_| ApiError::DocumentNotFound)
+}
+
+fn collect_metadata(dir: &Path, pages: &mut Vec<Value>) -> Result<(), ApiError> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        warn!("Error reading {}: {}", dir.display(), e);
+        ApiError::DocumentNotFound
+    })?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_metadata(&path, pages)?;
+        } else if path.file_name().is_some_and(|name| name == METADATA_FILE) {
+            match serde_json::from_slice(&read(&path)?) {
+                Ok(Value::Array(values)) => pages.extend(values),
+                Ok(value) => pages.push(value),
+                Err(e) => warn!("Skipping invalid {}: {}", path.display(), e),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryResult, RunQueryDsl,
+};
+use serde_json::Value;
+
+use crate::db::schema::bcd_sync_runs;
+use crate::db::types::BcdSyncStatus;
+use crate::db::v2::model::BcdSyncRun;
+
+pub fn start_sync_run(conn: &mut PgConnection) -> QueryResult<i64> {
+    diesel::insert_into(bcd_sync_runs::table)
+        .default_values()
+        .returning(bcd_sync_runs::id)
+        .get_result(conn)
+}
+
+pub struct SyncRunResult {
+    pub status: BcdSyncStatus,
+    pub inserted: i64,
+    pub updated: i64,
+    pub removed: i64,
+    pub report: Value,
+    pub errors: Vec<String>,
+}
+
+pub fn finish_sync_run(
+    conn: &mut PgConnection,
+    id: i64,
+    result: SyncRunResult,
+) -> QueryResult<usize> {
+    diesel::update(bcd_sync_runs::table.filter(bcd_sync_runs::id.eq(id)))
+        .set((
+            bcd_sync_runs::finished_at.eq(diesel::dsl::now),
+            bcd_sync_runs::status.eq(result.status),
+            bcd_sync_runs::inserted.eq(result.inserted),
+            bcd_sync_runs::updated.eq(result.updated),
+            bcd_sync_runs::removed.eq(result.removed),
+            bcd_sync_runs::report.eq(result.report),
+            bcd_sync_runs::errors.eq(result.errors.into_iter().map(Some).collect::<Vec<_>>()),
+        ))
+        .execute(conn)
+}
+
+pub fn last_sync_runs(conn: &mut PgConnection, limit: i64) -> QueryResult<Vec<BcdSyncRun>> {
+    bcd_sync_runs::table
+        .order_by(bcd_sync_runs::started_at.desc())
+        .limit(limit)
+        .get_results(conn)
+}
+
+/// The most recent run that finished successfully, i.e. when the data last changed.
+pub fn last_successful_sync_run(conn: &mut PgConnection) -> QueryResult<Option<BcdSyncRun>> {
+    bcd_sync_runs::table
+        .filter(bcd_sync_runs::status.eq(BcdSyncStatus::Succeeded))
+        .order_by(bcd_sync_runs::finished_at.desc())
+        .first(conn)
+        .optional()
+}
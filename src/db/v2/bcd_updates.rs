@@ -1,14 +1,15 @@
 use super::model::BcdUpdate;
-use super::model::BcdUpdateQuery;
-use crate::api::v2::updates::BcdUpdatesQueryParams;
+use super::model::BcdUpdateRelease;
+use crate::api::v2::updates::{AscOrDesc, BcdUpdatesQueryParams};
 use crate::apply_filters;
-use crate::bcd_updates_apply_sort;
 use crate::bcd_updates_read_table_get_updates_for_collections;
+use crate::bcd_updates_read_table_group_by_release;
 use crate::bcd_updates_read_table_group_by_select;
 use crate::db::error::DbError;
 use crate::db::schema;
+use crate::db::schema_manual::bcd_updates_view;
 use crate::db::users::get_user;
-use crate::db::v2::pagination::PaginationStats;
+use crate::db::v2::pagination::{Page, Paginate, Paginated, UpdatesCursor, DEFAULT_PER_PAGE};
 use crate::diesel::BoolExpressionMethods;
 use crate::diesel::ExpressionMethods;
 use crate::diesel::JoinOnDsl;
@@ -18,6 +19,7 @@ use crate::diesel::RunQueryDsl;
 
 use actix_identity::Identity;
 use diesel::dsl::sql;
+use diesel::query_dsl::methods::LoadQuery;
 
 use diesel::r2d2::ConnectionManager;
 use diesel::sql_types::Json;
@@ -25,92 +27,132 @@ use diesel::sql_types::{Nullable, Text};
 use diesel::PgConnection;
 use diesel::PgTextExpressionMethods;
 use r2d2::PooledConnection;
+use serde_json::Value;
+use std::collections::HashMap;
 define_sql_function!(fn lower(a: Nullable<Text>) -> Nullable<Text>);
 
-const PAGE_LENGTH: i64 = 5;
+/// A page of release groups, with the total number of groups matching the query and a
+/// cursor to continue after this page.
+pub struct BcdUpdatesPage {
+    pub updates: Vec<BcdUpdate>,
+    pub total: i64,
+    pub last: i64,
+    pub next: Option<UpdatesCursor>,
+}
 
-fn offset_from_page(page: Option<i64>) -> i64 {
-    match page {
-        Some(page) if page > 0 => (page - 1) * PAGE_LENGTH,
-        _ => 0,
+fn load_page<'a, T>(
+    pool: &mut PooledConnection<ConnectionManager<PgConnection>>,
+    query: T,
+    query_params: &BcdUpdatesQueryParams,
+) -> Result<Page<BcdUpdateRelease>, DbError>
+where
+    Paginated<T>: LoadQuery<'a, PgConnection, (BcdUpdateRelease, i64)>,
+{
+    Ok(query
+        .paginate(query_params.per_page.unwrap_or(DEFAULT_PER_PAGE))
+        .descending(!matches!(query_params.sort, Some(AscOrDesc::Asc)))
+        .page(
+            query_params
+                .cursor
+                .is_none()
+                .then_some(query_params.page)
+                .flatten(),
+        )
+        .after(query_params.cursor.clone())
+        .load_page::<BcdUpdateRelease>(pool)?)
+}
+
+/// Joins the events, loaded for the releases of the page only, to the page.
+fn with_compat(
+    page: Page<BcdUpdateRelease>,
+    compat: Vec<(BcdUpdateRelease, Value)>,
+) -> BcdUpdatesPage {
+    let mut compat: HashMap<BcdUpdateRelease, Value> = compat.into_iter().collect();
+    let next = page
+        .items
+        .last()
+        .filter(|_| page.has_more)
+        .map(|last| UpdatesCursor {
+            release_date: last.release_date,
+            browser_name: last.browser_name.clone(),
+            release_id: last.release_id.clone(),
+            total: page.total,
+        });
+    BcdUpdatesPage {
+        updates: page
+            .items
+            .into_iter()
+            .map(|release| {
+                let events = compat.remove(&release).unwrap_or_default();
+                BcdUpdate::from((release, events))
+            })
+            .collect(),
+        total: page.total,
+        last: page.pages,
+        next,
     }
 }
 
 pub fn get_bcd_updates_paginated(
     pool: &mut PooledConnection<ConnectionManager<PgConnection>>,
     query_params: &BcdUpdatesQueryParams,
-) -> Result<(Vec<BcdUpdate>, i64), DbError> {
-    let count = get_count_for_query(pool, query_params);
-    let mut query = bcd_updates_read_table_group_by_select!().into_boxed();
+) -> Result<BcdUpdatesPage, DbError> {
+    let mut query = bcd_updates_read_table_group_by_release!().into_boxed();
     query = apply_filters!(query, query_params, pool);
-    query = bcd_updates_apply_sort!(query, query_params.sort);
-
-    let offset = offset_from_page(query_params.page);
+    let page = load_page(pool, query, query_params)?;
 
-    let res = query
-        .limit(PAGE_LENGTH)
-        .offset(offset)
-        .get_results::<BcdUpdateQuery>(pool)?;
-
-    Ok((
-        res.iter().map(BcdUpdate::from).collect(),
-        count.ok().unwrap(),
-    ))
+    let (browsers, release_ids) = page_releases(&page);
+    let mut query = bcd_updates_read_table_group_by_select!().into_boxed();
+    query = apply_filters!(query, query_params, pool);
+    let compat = query
+        .filter(bcd_updates_view::browser.eq_any(browsers))
+        .filter(bcd_updates_view::release_id.eq_any(release_ids))
+        .load(pool)?;
+    Ok(with_compat(page, compat))
 }
 
 pub fn get_bcd_updates_for_collection(
     pool: &mut PooledConnection<ConnectionManager<PgConnection>>,
     query_params: &BcdUpdatesQueryParams,
     user_id: &Identity,
-) -> Result<(Vec<BcdUpdate>, i64), DbError> {
+) -> Result<BcdUpdatesPage, DbError> {
     if let Some(collections) = &query_params.collections {
-        let count = get_count_for_collections_query(
-            pool,
-            query_params,
+        let user = get_user(pool, user_id.id().unwrap())?;
+        let mut query = bcd_updates_read_table_get_updates_for_collections!(
+            bcd_updates_read_table_group_by_release!(),
             collections,
-            &user_id.id().unwrap(),
-        )?;
+            user.id
+        );
+        query = apply_filters!(query, query_params, pool);
+        let page = load_page(pool, query, query_params)?;
 
+        let (browsers, release_ids) = page_releases(&page);
         let mut query = bcd_updates_read_table_get_updates_for_collections!(
+            bcd_updates_read_table_group_by_select!(),
             collections,
-            &user_id.id().unwrap(),
-            pool
+            user.id
         );
-
         query = apply_filters!(query, query_params, pool);
-        query = bcd_updates_apply_sort!(query, query_params.sort);
-
-        let offset = offset_from_page(query_params.page);
-
-        let res = query
-            .limit(PAGE_LENGTH)
-            .offset(offset)
-            .get_results::<BcdUpdateQuery>(pool)?;
-
-        Ok((res.iter().map(BcdUpdate::from).collect(), count))
+        let compat = query
+            .filter(bcd_updates_view::browser.eq_any(browsers))
+            .filter(bcd_updates_view::release_id.eq_any(release_ids))
+            .load(pool)?;
+        Ok(with_compat(page, compat))
     } else {
-        Ok((vec![], 0))
+        Ok(BcdUpdatesPage {
+            updates: vec![],
+            total: 0,
+            last: 0,
+            next: None,
+        })
     }
 }
 
-pub fn get_count_for_query(
-    pool: &mut PooledConnection<ConnectionManager<PgConnection>>,
-    query_params: &BcdUpdatesQueryParams,
-) -> Result<i64, DbError> {
-    let mut query = bcd_updates_read_table_group_by_select!().into_boxed();
-    query = apply_filters!(query, query_params, pool);
-    let pages = query.paginate().per_page(5);
-    Ok(pages.count_pages::<BcdUpdateQuery>(pool).unwrap())
-}
-
-pub fn get_count_for_collections_query(
-    pool: &mut PooledConnection<ConnectionManager<PgConnection>>,
-    query_params: &BcdUpdatesQueryParams,
-    collections: &Vec<i64>,
-    user_id: &String,
-) -> Result<i64, DbError> {
-    let mut query = bcd_updates_read_table_get_updates_for_collections!(collections, user_id, pool);
-    query = apply_filters!(query, query_params, pool);
-    let pages = query.paginate().per_page(5);
-    Ok(pages.count_pages::<BcdUpdateQuery>(pool).unwrap())
+/// Browsers and release ids of the page, the events are loaded for their combinations
+/// and the ones not on the page are dropped again by [`with_compat`].
+fn page_releases(page: &Page<BcdUpdateRelease>) -> (Vec<String>, Vec<String>) {
+    page.items
+        .iter()
+        .map(|release| (release.browser.clone(), release.release_id.clone()))
+        .unzip()
 }
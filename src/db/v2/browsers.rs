@@ -0,0 +1,65 @@
+use diesel::{
+    ExpressionMethods, OptionalExtension, PgConnection, QueryDsl, QueryResult, RunQueryDsl,
+};
+
+use crate::db::schema::{browser_releases, browsers};
+use crate::db::v2::model::{Browser, BrowserRelease};
+
+macro_rules! browser_release_columns {
+    () => {
+        (
+            browser_releases::browser,
+            browser_releases::release_id,
+            browser_releases::engine,
+            browser_releases::engine_version,
+            browser_releases::release_date,
+            browser_releases::release_notes,
+            browser_releases::status,
+        )
+    };
+}
+
+pub fn get_browsers(conn: &mut PgConnection) -> QueryResult<Vec<Browser>> {
+    browsers::table.order_by(browsers::name).load(conn)
+}
+
+pub fn get_browser(conn: &mut PgConnection, name: &str) -> QueryResult<Option<Browser>> {
+    browsers::table
+        .filter(browsers::name.eq(name))
+        .first(conn)
+        .optional()
+}
+
+/// The releases with the status `current`, at most one per browser.
+pub fn get_current_releases(conn: &mut PgConnection) -> QueryResult<Vec<BrowserRelease>> {
+    browser_releases::table
+        .filter(browser_releases::status.eq("current"))
+        .select(browser_release_columns!())
+        .order_by((
+            browser_releases::browser,
+            browser_releases::release_date.desc(),
+        ))
+        .distinct_on(browser_releases::browser)
+        .load(conn)
+}
+
+/// Releases of a browser, newest first, optionally only those with one of the statuses.
+pub fn get_browser_releases(
+    conn: &mut PgConnection,
+    browser: &str,
+    statuses: Option<&[&str]>,
+) -> QueryResult<Vec<BrowserRelease>> {
+    let mut query = browser_releases::table
+        .filter(browser_releases::browser.eq(browser))
+        .select(browser_release_columns!())
+        .into_boxed();
+    if let Some(statuses) = statuses {
+        query = query.filter(browser_releases::status.eq_any(statuses));
+    }
+    query
+        .order_by((
+            browser_releases::release_date.desc(),
+            browser_releases::release_id.desc(),
+        ))
+        .load(conn)
+}
@@ -1,23 +1,25 @@
 #[macro_export]
-macro_rules! bcd_updates_apply_sort {
-    ($query: expr, $sort: expr) => {
-        if let Some(sort) = &$sort {
-            match sort {
-                $crate::api::v2::updates::AscOrDesc::Asc => $query.order_by((
-                    $crate::db::schema_manual::bcd_updates_view::release_date.asc(),
-                    $crate::db::schema_manual::bcd_updates_view::browser_name,
-                )),
-                $crate::api::v2::updates::AscOrDesc::Desc => $query.order_by((
-                    $crate::db::schema_manual::bcd_updates_view::release_date.desc(),
-                    $crate::db::schema_manual::bcd_updates_view::browser_name,
-                )),
-            }
-        } else {
-            $query.order_by((
-                $crate::db::schema_manual::bcd_updates_view::release_date.desc(),
-                $crate::db::schema_manual::bcd_updates_view::browser_name,
-            ))
-        }
+macro_rules! bcd_updates_release_columns {
+    () => {
+        (
+            $crate::db::schema_manual::bcd_updates_view::browser,
+            $crate::db::schema_manual::bcd_updates_view::browser_name,
+            $crate::db::schema_manual::bcd_updates_view::engine,
+            $crate::db::schema_manual::bcd_updates_view::engine_version,
+            $crate::db::schema_manual::bcd_updates_view::release_id,
+            $crate::db::schema_manual::bcd_updates_view::release_date,
+            $crate::db::schema_manual::bcd_updates_view::release_notes,
+        )
+    };
+}
+
+/// One row per release, without the events.
+#[macro_export]
+macro_rules! bcd_updates_read_table_group_by_release {
+    () => {
+        $crate::db::schema_manual::bcd_updates_view::table
+            .group_by($crate::bcd_updates_release_columns!())
+            .select($crate::bcd_updates_release_columns!())
     };
 }
 
@@ -25,21 +27,9 @@ macro_rules! bcd_updates_apply_sort {
 macro_rules! bcd_updates_read_table_group_by_select {
     () => {
 $crate::db::schema_manual::bcd_updates_view::table
-        .group_by((
-            $crate::db::schema_manual::bcd_updates_view::browser,
-            $crate::db::schema_manual::bcd_updates_view::browser_name,
-            $crate::db::schema_manual::bcd_updates_view::engine,
-            $crate::db::schema_manual::bcd_updates_view::engine_version,
-            $crate::db::schema_manual::bcd_updates_view::release_id,
-            $crate::db::schema_manual::bcd_updates_view::release_date
-        ))
+        .group_by($crate::bcd_updates_release_columns!())
         .select((
-            $crate::db::schema_manual::bcd_updates_view::browser,
-            $crate::db::schema_manual::bcd_updates_view::browser_name,
-            $crate::db::schema_manual::bcd_updates_view::engine,
-            $crate::db::schema_manual::bcd_updates_view::engine_version,
-            $crate::db::schema_manual::bcd_updates_view::release_id,
-            $crate::db::schema_manual::bcd_updates_view::release_date,
+            $crate::bcd_updates_release_columns!(),
             sql::<Json>(
                 "json_agg(json_build_object('event_type', event_type,
                                             'engines', engines,
@@ -54,7 +44,11 @@ $crate::db::schema_manual::bcd_updates_view::table
                                                             ) END,
                                             'mdn_url', mdn_url,
                                             'source_file', source_file,
-                                            'spec_url', spec_url
+                                            'spec_url', spec_url,
+                                            'before', statement_before,
+                                            'after', statement_after,
+                                            'baseline_status', baseline_status,
+                                            'baseline_date', baseline_date
      )) as compat",
             ),
         ))
@@ -63,9 +57,8 @@ $crate::db::schema_manual::bcd_updates_view::table
 
 #[macro_export]
 macro_rules! bcd_updates_read_table_get_updates_for_collections {
-    ($collections: expr, $user_id: expr, $conn_pool: expr) => {{
-        let user_query: $crate::db::model::UserQuery = get_user($conn_pool, $user_id)?;
-        let query = $crate::bcd_updates_read_table_group_by_select!()
+    ($query: expr, $collections: expr, $user_id: expr) => {{
+        let query = $query
             .inner_join(
                 schema::documents::table.on(schema::documents::uri
                     .nullable()
@@ -77,7 +70,7 @@ macro_rules! bcd_updates_read_table_get_updates_for_collections {
             )
             .filter(
                 schema::collection_items::user_id
-                    .eq(user_query.id)
+                    .eq($user_id)
                     .and(schema::collection_items::multiple_collection_id.eq_any($collections)),
             )
             .into_boxed();
@@ -106,6 +99,18 @@ macro_rules! apply_filters {
                 query.filter($crate::db::schema_manual::bcd_updates_view::browser.eq_any(browsers));
         }
 
+        if let Some(baseline) = &$query_params.baseline {
+            query = query.filter(
+                $crate::db::schema_manual::bcd_updates_view::baseline_status.eq_any(baseline),
+            );
+        }
+
+        if let Some(event_types) = &$query_params.event_type {
+            query = query.filter(
+                $crate::db::schema_manual::bcd_updates_view::event_type.eq_any(event_types),
+            );
+        }
+
         query
     }};
 }
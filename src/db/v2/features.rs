@@ -0,0 +1,72 @@
+use diesel::{
+    BoolExpressionMethods, ExpressionMethods, OptionalExtension, PgConnection, QueryDsl,
+    QueryResult, RunQueryDsl, TextExpressionMethods,
+};
+
+use crate::db::schema::{bcd_features, bcd_updates, browser_releases, browsers};
+use crate::db::v2::model::{BcdFeature, FeatureTimelineEvent};
+
+pub fn get_feature(conn: &mut PgConnection, path: &str) -> QueryResult<Option<BcdFeature>> {
+    bcd_features::table
+        .filter(bcd_features::path.eq(path))
+        .select((
+            bcd_features::path,
+            bcd_features::mdn_url,
+            bcd_features::short_title,
+            bcd_features::source_file,
+            bcd_features::spec_url,
+            bcd_features::deprecated,
+            bcd_features::experimental,
+            bcd_features::standard_track,
+            bcd_features::baseline_status,
+            bcd_features::baseline_date,
+        ))
+        .first(conn)
+        .optional()
+}
+
+/// All events of a feature, and of its subfeatures if asked for, oldest first.
+pub fn get_feature_timeline(
+    conn: &mut PgConnection,
+    path: &str,
+    subfeatures: bool,
+) -> QueryResult<Vec<FeatureTimelineEvent>> {
+    let mut query = bcd_updates::table
+        .inner_join(bcd_features::table)
+        .inner_join(browser_releases::table.inner_join(browsers::table))
+        .select((
+            bcd_features::path,
+            bcd_updates::event_type,
+            browsers::name,
+            browsers::display_name,
+            browser_releases::release_id,
+            browser_releases::engine,
+            browser_releases::engine_version,
+            browser_releases::release_date,
+            browser_releases::release_notes,
+            bcd_updates::engines,
+            bcd_updates::statement_before,
+            bcd_updates::statement_after,
+        ))
+        .into_boxed();
+    query = if subfeatures {
+        let escaped = path
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        query.filter(
+            bcd_features::path
+                .eq(path)
+                .or(bcd_features::path.like(format!("{escaped}.%"))),
+        )
+    } else {
+        query.filter(bcd_features::path.eq(path))
+    };
+    query
+        .order_by((
+            browser_releases::release_date.asc(),
+            browsers::name.asc(),
+            bcd_features::path.asc(),
+        ))
+        .get_results(conn)
+}
@@ -1,7 +1,14 @@
+pub mod baseline;
+pub mod bcd_feed;
+pub mod bcd_sources;
+pub mod bcd_sync_runs;
 pub mod bcd_updates;
+pub mod browsers;
 pub mod collection_items;
 pub mod db_macros;
+pub mod features;
 pub mod model;
 pub mod multiple_collections;
+pub mod notifications;
 pub mod pagination;
 pub mod synchronize_bcd_updates_db;
@@ -1,10 +1,9 @@
 #![allow(clippy::extra_unused_lifetimes)] /* https://github.com/rust-lang/rust-clippy/issues/9014 */
 use crate::db::model::User;
 use crate::db::schema::*;
-use crate::db::types::{BcdUpdateEventType, EngineType};
+use crate::db::types::{BaselineStatus, BcdSyncStatus, BcdUpdateEventType, EngineType};
 use crate::helpers::{maybe_to_utc, to_utc};
 use chrono::{NaiveDate, NaiveDateTime};
-use diesel::sql_types::{Date, Json, Text};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::str;
@@ -107,6 +106,10 @@ pub struct Event {
     pub status: Option<Status>,
     pub event_type: BcdUpdateEventType,
     pub engines: Vec<EngineType>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub baseline_status: Option<BaselineStatus>,
+    pub baseline_date: Option<NaiveDate>,
 }
 
 #[derive(Clone, Eq, Debug, Deserialize, PartialEq, Serialize)]
@@ -116,22 +119,15 @@ pub struct Status {
     pub standard_track: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Queryable, QueryableByName)]
-pub struct BcdUpdateQuery {
-    #[diesel(sql_type = Text)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Queryable)]
+pub struct BcdUpdateRelease {
     pub browser: String,
-    #[diesel(sql_type = Text)]
     pub browser_name: String,
-    #[diesel(sql_type = Text)]
     pub engine: String,
-    #[diesel(sql_type = Text)]
     pub engine_version: String,
-    #[diesel(sql_type = Text)]
     pub release_id: String,
-    #[diesel(sql_type = Date)]
     pub release_date: NaiveDate,
-    #[diesel(sql_type = Json)]
-    pub compat: Value,
+    pub release_notes: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -142,19 +138,102 @@ pub struct BcdUpdate {
     pub engine_version: String,
     pub release_id: String,
     pub release_date: NaiveDate,
+    pub release_notes: Option<String>,
     pub compat: Vec<Event>,
 }
 
-impl From<&BcdUpdateQuery> for BcdUpdate {
-    fn from(val: &BcdUpdateQuery) -> Self {
+impl From<(BcdUpdateRelease, Value)> for BcdUpdate {
+    fn from((release, compat): (BcdUpdateRelease, Value)) -> Self {
         BcdUpdate {
-            browser: val.browser.clone(),
-            engine: val.engine.clone(),
-            name: val.browser_name.clone(),
-            engine_version: val.engine_version.clone(),
-            release_id: val.release_id.clone(),
-            release_date: val.release_date,
-            compat: serde_json::from_value::<Vec<Event>>(val.compat.clone()).unwrap(),
+            browser: release.browser,
+            engine: release.engine,
+            name: release.browser_name,
+            engine_version: release.engine_version,
+            release_id: release.release_id,
+            release_date: release.release_date,
+            release_notes: release.release_notes,
+            compat: serde_json::from_value::<Vec<Event>>(compat).unwrap(),
         }
     }
 }
+
+#[derive(Queryable, Serialize, Debug)]
+pub struct BcdFeature {
+    pub path: String,
+    pub mdn_url: Option<String>,
+    pub short_title: Option<String>,
+    pub source_file: String,
+    pub spec_url: Option<String>,
+    pub deprecated: Option<bool>,
+    pub experimental: Option<bool>,
+    pub standard_track: Option<bool>,
+    pub baseline_status: BaselineStatus,
+    pub baseline_date: Option<NaiveDate>,
+}
+
+#[derive(Queryable, Serialize, Debug)]
+pub struct FeatureTimelineEvent {
+    pub path: String,
+    pub event_type: BcdUpdateEventType,
+    pub browser: String,
+    pub browser_name: String,
+    pub version: String,
+    pub engine: String,
+    pub engine_version: String,
+    pub release_date: NaiveDate,
+    pub release_notes: Option<String>,
+    pub engines: Vec<Option<EngineType>>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Queryable, Serialize, Debug)]
+pub struct Browser {
+    pub name: String,
+    pub display_name: String,
+    pub accepts_flags: Option<bool>,
+    pub accepts_webextensions: Option<bool>,
+    pub pref_url: Option<String>,
+    pub preview_name: Option<String>,
+}
+
+#[derive(Queryable, Serialize, Debug, Clone)]
+pub struct BrowserRelease {
+    pub browser: String,
+    pub version: String,
+    pub engine: String,
+    pub engine_version: String,
+    pub release_date: NaiveDate,
+    pub release_notes: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Queryable, Serialize, Debug)]
+pub struct BcdSyncRun {
+    pub id: i64,
+    #[serde(serialize_with = "to_utc")]
+    pub started_at: NaiveDateTime,
+    #[serde(serialize_with = "maybe_to_utc")]
+    pub finished_at: Option<NaiveDateTime>,
+    pub status: BcdSyncStatus,
+    pub inserted: i64,
+    pub updated: i64,
+    pub removed: i64,
+    pub report: Value,
+    pub errors: Vec<Option<String>>,
+}
+
+#[derive(Queryable, Debug)]
+pub struct BcdNotificationQuery {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub read_at: Option<NaiveDateTime>,
+    pub event_type: BcdUpdateEventType,
+    pub path: String,
+    pub browser: String,
+    pub browser_name: String,
+    pub version: String,
+    pub release_date: NaiveDate,
+    pub document_title: String,
+    pub document_url: String,
+}
@@ -0,0 +1,173 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::now;
+use diesel::sql_types::Int8;
+use diesel::{
+    sql_query, Connection, ExpressionMethods, JoinOnDsl, PgConnection, QueryDsl, QueryResult,
+    RunQueryDsl,
+};
+
+use crate::db::schema::{
+    bcd_features, bcd_notifications, bcd_updates, browser_releases, browsers, documents, settings,
+    users,
+};
+use crate::db::v2::model::BcdNotificationQuery;
+
+macro_rules! bcd_notifications_select {
+    () => {
+        bcd_notifications::table
+            .inner_join(
+                bcd_updates::table
+                    .inner_join(bcd_features::table)
+                    .inner_join(browser_releases::table.inner_join(browsers::table)),
+            )
+            .inner_join(documents::table)
+    };
+}
+
+macro_rules! bcd_notification_columns {
+    () => {
+        (
+            bcd_notifications::id,
+            bcd_notifications::created_at,
+            bcd_notifications::read_at,
+            bcd_updates::event_type,
+            bcd_features::path,
+            browsers::name,
+            browsers::display_name,
+            browser_releases::release_id,
+            browser_releases::release_date,
+            documents::title,
+            documents::absolute_uri,
+        )
+    };
+}
+
+/// Notifies users of the events created by a sync run that affect a page in one of
+/// their collections: the feature links to the page, or the page lists the feature, or
+/// a parent of it, in its `documents.paths`. Each case is its own insert, so that both
+/// can use the index on their column.
+pub fn create_notifications(conn: &mut PgConnection, sync_run_id: i64) -> QueryResult<usize> {
+    let by_uri = sql_query(
+        r#"INSERT INTO bcd_notifications (user_id, bcd_update_id, document_id)
+        SELECT DISTINCT ci.user_id, u.id, d.id
+        FROM bcd_updates u
+        JOIN bcd_features f ON f.id = u.feature
+        JOIN documents d ON d.uri = lower(f.mdn_url)
+        JOIN collection_items ci ON ci.document_id = d.id AND ci.deleted_at IS NULL
+        WHERE u.created_at >= (SELECT started_at FROM bcd_sync_runs WHERE id = $1)
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind::<Int8, _>(sync_run_id)
+    .execute(conn)?;
+    let by_paths = sql_query(
+        r#"INSERT INTO bcd_notifications (user_id, bcd_update_id, document_id)
+        SELECT DISTINCT ci.user_id, u.id, d.id
+        FROM bcd_updates u
+        JOIN bcd_features f ON f.id = u.feature
+        CROSS JOIN LATERAL (
+            SELECT array_agg(array_to_string(parts[1:n], '.')) AS prefixes
+            FROM string_to_array(f.path, '.') parts, generate_series(1, cardinality(parts)) n
+        ) p
+        JOIN documents d ON d.paths && p.prefixes
+        JOIN collection_items ci ON ci.document_id = d.id AND ci.deleted_at IS NULL
+        WHERE u.created_at >= (SELECT started_at FROM bcd_sync_runs WHERE id = $1)
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind::<Int8, _>(sync_run_id)
+    .execute(conn)?;
+    Ok(by_uri + by_paths)
+}
+
+/// Newest first.
+pub fn get_notifications_for_user(
+    conn: &mut PgConnection,
+    user_id: i64,
+    unread_only: bool,
+    limit: i64,
+    offset: i64,
+) -> QueryResult<Vec<BcdNotificationQuery>> {
+    let mut query = bcd_notifications_select!()
+        .filter(bcd_notifications::user_id.eq(user_id))
+        .select(bcd_notification_columns!())
+        .into_boxed();
+    if unread_only {
+        query = query.filter(bcd_notifications::read_at.is_null());
+    }
+    query
+        .order_by((
+            bcd_notifications::created_at.desc(),
+            browser_releases::release_date.desc(),
+            bcd_features::path.asc(),
+            bcd_notifications::id.asc(),
+        ))
+        .limit(limit)
+        .offset(offset)
+        .get_results(conn)
+}
+
+pub fn count_unread_notifications(conn: &mut PgConnection, user_id: i64) -> QueryResult<i64> {
+    bcd_notifications::table
+        .filter(bcd_notifications::user_id.eq(user_id))
+        .filter(bcd_notifications::read_at.is_null())
+        .count()
+        .get_result(conn)
+}
+
+/// Marks the given notifications of a user as read, or all of them without ids.
+pub fn mark_notifications_read(
+    conn: &mut PgConnection,
+    user_id: i64,
+    ids: Option<&[i64]>,
+) -> QueryResult<usize> {
+    let mut query = diesel::update(bcd_notifications::table)
+        .filter(bcd_notifications::user_id.eq(user_id))
+        .filter(bcd_notifications::read_at.is_null())
+        .into_boxed();
+    if let Some(ids) = ids {
+        query = query.filter(bcd_notifications::id.eq_any(ids));
+    }
+    query.set(bcd_notifications::read_at.eq(now)).execute(conn)
+}
+
+/// Claims the unread notifications which have not been mailed yet, of the users who
+/// asked for a digest, by marking them mailed. Returns them together with the email
+/// address of their user, ordered by user. Concurrent runs never claim the same rows,
+/// the update only takes the ones still unmailed once the other run committed.
+pub fn claim_notifications_for_digest(
+    conn: &mut PgConnection,
+) -> QueryResult<Vec<(String, BcdNotificationQuery)>> {
+    conn.transaction(|conn| {
+        let ids: Vec<i64> = diesel::update(bcd_notifications::table)
+            .filter(
+                bcd_notifications::user_id.eq_any(
+                    settings::table
+                        .filter(settings::bcd_digest.eq(true))
+                        .select(settings::user_id),
+                ),
+            )
+            .filter(bcd_notifications::read_at.is_null())
+            .filter(bcd_notifications::mailed_at.is_null())
+            .set(bcd_notifications::mailed_at.eq(now))
+            .returning(bcd_notifications::id)
+            .get_results(conn)?;
+        bcd_notifications_select!()
+            .inner_join(users::table.on(users::id.eq(bcd_notifications::user_id)))
+            .filter(bcd_notifications::id.eq_any(ids))
+            .select((users::email, bcd_notification_columns!()))
+            .order_by((
+                bcd_notifications::user_id.asc(),
+                documents::title.asc(),
+                documents::id.asc(),
+                browser_releases::release_date.asc(),
+                bcd_features::path.asc(),
+            ))
+            .get_results(conn)
+    })
+}
+
+/// Hands claimed notifications back to the next digest, after mailing them failed.
+pub fn release_notifications(conn: &mut PgConnection, ids: &[i64]) -> QueryResult<usize> {
+    diesel::update(bcd_notifications::table.filter(bcd_notifications::id.eq_any(ids)))
+        .set(bcd_notifications::mailed_at.eq(None::<NaiveDateTime>))
+        .execute(conn)
+}
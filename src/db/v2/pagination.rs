@@ -1,44 +1,138 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::NaiveDate;
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::query_builder::*;
 use diesel::query_dsl::methods::LoadQuery;
-use diesel::sql_types::BigInt;
+use diesel::sql_types::{BigInt, Date, Text};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-pub trait PaginationStats: Sized {
-    fn paginate(self) -> Paginated<Self>;
+pub const DEFAULT_PER_PAGE: i64 = 5;
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// Position after the last release group of a page, handed out to clients as an
+/// opaque token. Release groups are ordered by `(release_date, browser_name)` with the
+/// release id as a tie-breaker. The total counted for the first page travels along, so
+/// later pages don't have to group every release again to count them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UpdatesCursor {
+    pub release_date: NaiveDate,
+    pub browser_name: String,
+    pub release_id: String,
+    pub total: i64,
+}
+
+impl UpdatesCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(token: &str) -> Option<Self> {
+        let payload = URL_SAFE_NO_PAD.decode(token).ok()?;
+        serde_json::from_slice(&payload).ok()
+    }
 }
 
-const DEFAULT_PER_PAGE: i64 = 5;
+/// The cursor goes over the wire as its token, both in query strings and responses.
+pub mod token {
+    use super::*;
 
-impl<T> PaginationStats for T {
-    fn paginate(self) -> Paginated<Self> {
+    pub fn serialize<S: Serializer>(
+        cursor: &Option<UpdatesCursor>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match cursor {
+            Some(cursor) => serializer.serialize_str(&cursor.encode()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<UpdatesCursor>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|token| {
+                UpdatesCursor::decode(&token).ok_or_else(|| de::Error::custom("invalid cursor"))
+            })
+            .transpose()
+    }
+}
+
+pub trait Paginate: Sized {
+    fn paginate(self, per_page: i64) -> Paginated<Self>;
+}
+
+impl<T> Paginate for T {
+    fn paginate(self, per_page: i64) -> Paginated<Self> {
+        let per_page = per_page.clamp(1, MAX_PER_PAGE);
         Paginated {
             query: self,
-            per_page: DEFAULT_PER_PAGE,
+            per_page,
+            // One more than requested, to tell whether there is a next page.
+            limit: per_page + 1,
+            offset: 0,
+            descending: true,
+            after: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, QueryId)]
+/// A page of release groups together with the total number of groups matching the
+/// query, which is counted by the same query on the first page.
+pub struct Page<U> {
+    pub items: Vec<U>,
+    pub total: i64,
+    pub pages: i64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, QueryId)]
 pub struct Paginated<T> {
     query: T,
     per_page: i64,
+    limit: i64,
+    offset: i64,
+    descending: bool,
+    after: Option<UpdatesCursor>,
 }
 
 impl<T> Paginated<T> {
-    pub fn per_page(self, per_page: i64) -> Self {
-        Paginated { per_page, ..self }
+    pub fn page(self, page: Option<i64>) -> Self {
+        let offset = match page {
+            Some(page) if page > 0 => (page - 1) * self.per_page,
+            _ => 0,
+        };
+        Paginated { offset, ..self }
     }
 
-    pub fn count_pages<'a, U>(self, conn: &mut PgConnection) -> QueryResult<i64>
+    pub fn descending(self, descending: bool) -> Self {
+        Paginated { descending, ..self }
+    }
+
+    /// Continues after the given cursor instead of skipping pages.
+    pub fn after(self, after: Option<UpdatesCursor>) -> Self {
+        Paginated { after, ..self }
+    }
+
+    pub fn load_page<'a, U>(self, conn: &mut PgConnection) -> QueryResult<Page<U>>
     where
         Self: LoadQuery<'a, PgConnection, (U, i64)>,
     {
         let per_page = self.per_page;
         let results = self.load::<(U, i64)>(conn)?;
         let total = results.first().map(|x| x.1).unwrap_or(0);
-        let total_pages = (total as f64 / per_page as f64).ceil() as i64;
-        Ok(total_pages)
+        let has_more = results.len() as i64 > per_page;
+        let items = results
+            .into_iter()
+            .take(per_page as usize)
+            .map(|(item, _)| item)
+            .collect();
+        Ok(Page {
+            items,
+            total,
+            pages: (total as f64 / per_page as f64).ceil() as i64,
+            has_more,
+        })
     }
 }
 
@@ -53,10 +147,45 @@ where
     T: QueryFragment<Pg>,
 {
     fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
-        out.push_sql("SELECT browser, browser_name, engine, engine_version, release_id, release_date,'[]'::json as compat,  COUNT(*) OVER () FROM (");
-        self.query.walk_ast(out.reborrow())?;
-        out.push_sql(") t LIMIT ");
-        out.push_bind_param::<BigInt, _>(&self.per_page)?;
+        let (cmp, direction) = if self.descending {
+            ("<", "DESC")
+        } else {
+            (">", "ASC")
+        };
+        match &self.after {
+            // The window function sees every group and not just the page, so the query
+            // should only select the release columns.
+            None => {
+                out.push_sql("SELECT t.*, COUNT(*) OVER () AS total FROM (");
+                self.query.walk_ast(out.reborrow())?;
+                out.push_sql(") t");
+            }
+            // Without a window function Postgres pushes the condition on the grouped
+            // columns down into the query, only the groups after the cursor are built.
+            Some(after) => {
+                out.push_sql("SELECT t.*, ");
+                out.push_bind_param::<BigInt, _>(&after.total)?;
+                out.push_sql(" AS total FROM (");
+                self.query.walk_ast(out.reborrow())?;
+                out.push_sql(") t WHERE (release_date ");
+                out.push_sql(cmp);
+                out.push_sql(" ");
+                out.push_bind_param::<Date, _>(&after.release_date)?;
+                out.push_sql(" OR (release_date = ");
+                out.push_bind_param::<Date, _>(&after.release_date)?;
+                out.push_sql(" AND (browser_name, release_id) > (");
+                out.push_bind_param::<Text, _>(&after.browser_name)?;
+                out.push_sql(", ");
+                out.push_bind_param::<Text, _>(&after.release_id)?;
+                out.push_sql(")))");
+            }
+        }
+        out.push_sql(" ORDER BY release_date ");
+        out.push_sql(direction);
+        out.push_sql(", browser_name, release_id LIMIT ");
+        out.push_bind_param::<BigInt, _>(&self.limit)?;
+        out.push_sql(" OFFSET ");
+        out.push_bind_param::<BigInt, _>(&self.offset)?;
         Ok(())
     }
 }
@@ -1,71 +1,234 @@
-use std::cmp::min;
-use std::collections::HashMap;
-use std::str::FromStr;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::api::error::ApiError;
+use crate::db::schema::sql_types::BcdEventType;
 use crate::db::schema::{self, *};
-use crate::db::types::{BcdUpdateEventType, EngineType};
+use crate::db::types::{BaselineStatus, BcdSyncStatus, BcdUpdateEventType};
+use crate::db::v2::baseline::{baseline_date, baseline_status, SupportEvent};
+use crate::db::v2::bcd_feed::{
+    parse_metadata, BcdFeed, Feature, FeedError, FeedSummary, PageMetadata, SpecUrl, Update,
+};
+use crate::db::v2::bcd_sources::{fetch, load_bcd_updates, load_mdn_metadata};
+use crate::db::v2::bcd_sync_runs::{
+    finish_sync_run, last_sync_runs, start_sync_run, SyncRunResult,
+};
+use crate::db::v2::notifications::create_notifications;
 use crate::db::Pool;
+use crate::diesel::Connection;
 use crate::diesel::ExpressionMethods;
+use crate::diesel::PgExpressionMethods;
 use crate::diesel::QueryDsl;
 use crate::diesel::RunQueryDsl;
 use crate::settings::SETTINGS;
-use actix_http::StatusCode;
 use actix_rt::ArbiterHandle;
-use actix_web::{web::Data, HttpResponse};
-use chrono::NaiveDate;
-use diesel::upsert::excluded;
+use actix_web::{web, web::Bytes, web::Data, HttpResponse};
+use chrono::{NaiveDate, Utc};
+use diesel::dsl::now;
+use diesel::sql_types::{Array, Bool, Date, Int8, Jsonb, Nullable, Text};
 use diesel::{sql_query, update, PgConnection};
 use reqwest::Client;
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
 
 use crate::diesel::BoolExpressionMethods;
 
-async fn get_bcd_updates(client: &Data<Client>) -> Result<Value, ApiError> {
-    let res = client
-        .get(SETTINGS.application.bcd_updates_url.as_ref())
-        .send()
-        .await
-        .map_err(|err: reqwest::Error| match err.status() {
-            Some(StatusCode::NOT_FOUND) => ApiError::DocumentNotFound,
-            _ => ApiError::Unknown,
-        })?
-        .json()
-        .await
-        .map_err(|err| {
-            error!("{:1}", err);
-            ApiError::DocumentNotFound
-        })?;
-    Ok(res)
+/// Rows of one table inserted, updated and removed by a sync run.
+#[derive(Serialize, Default, Clone, Copy, Debug)]
+pub struct SyncCounts {
+    pub inserted: i64,
+    pub updated: i64,
+    pub removed: i64,
 }
 
-async fn do_bcd_update(pool: Data<Pool>, client: Data<Client>) -> Result<(), ApiError> {
+/// A row returned by the upserts below.
+#[derive(QueryableByName)]
+struct Upserted {
+    #[diesel(sql_type = Bool)]
+    inserted: bool,
+}
+
+impl SyncCounts {
+    /// Counts the rows returned by an upsert with `RETURNING xmax = 0`, which is only
+    /// true for inserted rows. Unchanged rows are not returned at all.
+    fn upserted(inserted: &[Upserted]) -> Self {
+        let new = inserted.iter().filter(|x| x.inserted).count() as i64;
+        SyncCounts {
+            inserted: new,
+            updated: inserted.len() as i64 - new,
+            removed: 0,
+        }
+    }
+
+    fn add(&mut self, other: SyncCounts) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.removed += other.removed;
+    }
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct SyncReport {
+    pub browsers: SyncCounts,
+    pub browser_releases: SyncCounts,
+    pub features: SyncCounts,
+    pub updates: SyncCounts,
+    pub baseline: SyncCounts,
+    /// Notifications created for the users who collected an affected page.
+    pub notifications: i64,
+}
+
+impl SyncReport {
+    fn total(&self) -> SyncCounts {
+        let mut total = SyncCounts::default();
+        for counts in [
+            self.browsers,
+            self.browser_releases,
+            self.features,
+            self.updates,
+            self.baseline,
+        ] {
+            total.add(counts);
+        }
+        total
+    }
+}
+
+async fn get_bcd_updates(client: &Client) -> Result<BcdFeed, ApiError> {
+    let bytes = load_bcd_updates(client, &SETTINGS.application.bcd_updates_url).await?;
+    BcdFeed::from_slice(&bytes).map_err(|e| ApiError::Generic(e.to_string()))
+}
+
+async fn get_mdn_metadata(
+    client: &Client,
+) -> Result<(Vec<PageMetadata>, Vec<FeedError>), ApiError> {
+    let json = load_mdn_metadata(client, &SETTINGS.application.mdn_metadata_url).await?;
+    parse_metadata(json).map_err(|e| ApiError::Generic(e.to_string()))
+}
+
+/// Both feeds are fetched first, then everything is written in a single transaction,
+/// so a failing run leaves the previous data (and view) untouched. Invalid records are
+/// skipped and returned alongside the report. Events missing from the feed are only
+/// removed with `replace` and if the whole feed was valid, a skipped record must not
+/// delete its event.
+async fn sync_bcd(
+    conn: &mut PgConnection,
+    client: &Client,
+    uploaded: Option<BcdFeed>,
+    replace: bool,
+) -> Result<(SyncReport, Vec<FeedError>), ApiError> {
+    let mut feed = match uploaded {
+        Some(feed) => feed,
+        None => get_bcd_updates(client).await?,
+    };
+    let complete = feed.errors.is_empty();
+    let remove_missing = replace && complete;
+    let (metadata, metadata_errors) = get_mdn_metadata(client).await?;
+    feed.errors.extend(metadata_errors);
+    let report = conn.transaction(|conn| {
+        let mut report = SyncReport::default();
+        info!("Synchronize browsers");
+        synchronize_browers_and_releases(conn, &feed, &mut report)?;
+        info!("Synchronize features");
+        report.features = synchronize_features(conn, &feed.features)?;
+        info!("Synchronize paths + bcd mappings");
+        report
+            .features
+            .add(synchronize_path_mappings(conn, &metadata)?);
+        info!("Synchronize updates");
+        if replace && !complete {
+            warn!("Keeping the events missing from the feed, it has invalid records");
+        }
+        report.updates = synchronize_updates(conn, &feed.updates, remove_missing)?;
+        info!("Computing baseline status");
+        report.baseline = synchronize_baseline(conn)?;
+        info!("Refreshing view");
+        sql_query("REFRESH MATERIALIZED VIEW bcd_updates_view;")
+            .execute(conn)
+            .map_err(|e| ApiError::Generic(format!("Error updating bcd_updates_view: {e}")))?;
+        Ok::<_, ApiError>(report)
+    })?;
+    Ok((report, feed.errors))
+}
+
+async fn do_bcd_update(
+    pool: Data<Pool>,
+    client: Data<Client>,
+    uploaded: Option<BcdFeed>,
+    replace: bool,
+) -> Result<(), ApiError> {
     let mut conn = pool.get()?;
-    let mut json = get_bcd_updates(&client).await?;
-    info!("Synchronize browsers");
-    synchronize_browers_and_releases(&mut conn, json["browsers"].take()).await?;
-    info!("Synchronize features");
-    synchronize_features(&mut conn, json["features"].take()).await?;
-    info!("Synchronize paths + bcd mappings");
-    synchronize_path_mappings(&mut conn, client).await?;
-    info!("Synchronize updates");
-    synchronize_updates(&mut conn, json["added_removed"].take()).await?;
-    info!("Refreshing view");
-    if let Err(e) = sql_query("REFRESH MATERIALIZED VIEW bcd_updates_view;").execute(&mut conn) {
-        error!("Error updating bcd_updates_view , {:?}", e);
-    } else {
-        info!("updated bcd_updates_view");
+    let run_id = start_sync_run(&mut conn)?;
+    let mut result = sync_bcd(&mut conn, &client, uploaded, replace).await;
+    if let Ok((report, _)) = &mut result {
+        info!("Creating notifications");
+        match create_notifications(&mut conn, run_id) {
+            Ok(created) => report.notifications = created as i64,
+            Err(e) => warn!("Error creating bcd notifications: {}", e),
+        }
+    }
+    let (status, report, errors) = match &result {
+        Ok((report, errors)) => (
+            BcdSyncStatus::Succeeded,
+            report,
+            errors.iter().map(ToString::to_string).collect(),
+        ),
+        Err(e) => (
+            BcdSyncStatus::Failed,
+            &SyncReport::default(),
+            vec![e.to_string()],
+        ),
+    };
+    let total = report.total();
+    finish_sync_run(
+        &mut conn,
+        run_id,
+        SyncRunResult {
+            status,
+            inserted: total.inserted,
+            updated: total.updated,
+            removed: total.removed,
+            report: serde_json::to_value(report).unwrap_or_default(),
+            errors,
+        },
+    )?;
+    let (report, errors) = result?;
+    for error in &errors {
+        warn!("Skipped invalid bcd record {}", error);
     }
+    info!("bcd sync finished: {:?}", report);
     Ok(())
 }
 
+/// Starts a sync in the background. A feed posted as the request body is synchronized
+/// instead of the one at `bcd_updates_url`.
+#[derive(Deserialize)]
+pub struct UpdateBcdQuery {
+    /// Also remove the stored events missing from an uploaded feed. Uploads are
+    /// partial or historical more often than not, so by default they only upsert.
+    #[serde(default)]
+    replace: bool,
+}
+
 pub async fn update_bcd(
     pool: Data<Pool>,
     client: Data<Client>,
     arbiter: Data<ArbiterHandle>,
+    query: web::Query<UpdateBcdQuery>,
+    body: Bytes,
 ) -> Result<HttpResponse, ApiError> {
+    let uploaded = if body.is_empty() {
+        None
+    } else {
+        match BcdFeed::from_slice(&body) {
+            Ok(feed) => Some(feed),
+            Err(e) => return Ok(HttpResponse::BadRequest().json(FeedSummary::from(e))),
+        }
+    };
+    // The configured feed is complete, so it always replaces the stored events.
+    let replace = uploaded.is_none() || query.replace;
     if !arbiter.spawn(async move {
-        if let Err(e) = do_bcd_update(pool, client).await {
+        if let Err(e) = do_bcd_update(pool, client, uploaded, replace).await {
             error!("{}", e);
         }
     }) {
@@ -74,355 +237,406 @@ pub async fn update_bcd(
     Ok(HttpResponse::Accepted().finish())
 }
 
-async fn synchronize_browers_and_releases(
-    pool: &mut PgConnection,
-    json: Value,
-) -> Result<(), ApiError> {
-    let mut browser_values = Vec::new();
-    let mut releases = Vec::new();
-    json.as_object().unwrap().iter().for_each(|(k, v)| {
-        browser_values.push((
-            browsers::name.eq(k.as_str()),
-            browsers::display_name.eq(v["name"].as_str().unwrap()),
-            browsers::accepts_flags.eq(v["accepts_flags"].as_bool().unwrap()),
-            browsers::accepts_webextensions.eq(v["accepts_webextensions"].as_bool().unwrap()),
-            browsers::pref_url.eq(v["pref_url"].as_str()),
-            browsers::preview_name.eq(v["preview_name"].as_str()),
-        ));
-
-        for (release, value) in v["releases"].as_object().unwrap() {
-            match value["engine"].as_str() {
-                Some(_) => (),
-                None => debug!("No engine for {:?}", value),
-            }
-            match value["release_date"].as_str() {
-                Some(_) => (),
-                None => debug!("No release_date for {:?}", value),
-            }
-            let _release_date: Option<NaiveDate> = value["release_date"]
-                .as_str()
-                .map_or_else(|| None, |v| Some(NaiveDate::from_str(v).unwrap()));
-            if _release_date.is_none() {
-                continue;
-            }
-            releases.push((
-                browser_releases::browser.eq(k.as_str()),
-                browser_releases::engine.eq(value["engine"].as_str().unwrap_or("Unknown")),
-                browser_releases::engine_version
-                    .eq(value["engine_version"].as_str().unwrap_or("Unknown")),
-                browser_releases::release_id.eq(release),
-                browser_releases::release_date.eq(_release_date.unwrap()),
-                browser_releases::release_notes.eq(value["release_notes"].as_str()),
-                browser_releases::status.eq(value["status"].as_str()),
-            ));
-        }
-    });
-
-    diesel::insert_into(crate::db::schema::browsers::table)
-        .values(browser_values)
-        .on_conflict_do_nothing()
-        .execute(pool)
-        .map_err(|e| ApiError::Generic(e.to_string()))?;
-
-    diesel::insert_into(crate::db::schema::browser_releases::table)
-        .values(releases)
-        .on_conflict_do_nothing()
-        .execute(pool)
-        .map_err(|e| ApiError::Generic(e.to_string()))?;
-    Ok(())
+#[derive(Deserialize)]
+pub struct ValidateQuery {
+    url: Option<Url>,
 }
 
-async fn synchronize_features(pool: &mut PgConnection, json: Value) -> Result<(), ApiError> {
-    let mut features = Vec::new();
-    json.as_array().unwrap().iter().for_each(|val| {
-        if val["source_file"].as_str().is_none() {
-            debug!("No source file found for path. {:?}", val);
-            return;
-        }
-        features.push((
-            bcd_features::path.eq(val["path"].as_str().unwrap()),
-            bcd_features::mdn_url.eq(val["mdn_url"].as_str()),
-            bcd_features::source_file.eq(val["source_file"].as_str().unwrap()),
-            bcd_features::spec_url.eq(val["spec_url"].as_str()),
-            bcd_features::deprecated.eq(val["status"]
-                .as_object()
-                .and_then(|v| v["deprecated"].as_bool())),
-            bcd_features::experimental.eq(val["status"]
-                .as_object()
-                .and_then(|v| v["experimental"].as_bool())),
-            bcd_features::standard_track.eq(val["status"]
-                .as_object()
-                .and_then(|v| v["standard_track"].as_bool())),
-        ));
-    });
-
-    while !features.is_empty() {
-        let batch_size = min(1000, features.len());
-        let drained: Vec<_> = features.drain(0..batch_size).collect();
-        if let Err(e) = diesel::insert_into(bcd_features::table)
-            .values(drained)
-            .on_conflict_do_nothing()
-            .execute(pool)
-        {
-            error!("{:?}", e);
+/// Dry run of a sync: parses the feed posted as the request body, or fetched from `url`
+/// (the configured feed by default), and reports what would be synchronized. Only
+/// http(s) URLs may be passed, local files are limited to the settings.
+pub async fn validate_bcd_updates(
+    client: Data<Client>,
+    query: web::Query<ValidateQuery>,
+    body: Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let bytes = if body.is_empty() {
+        match &query.url {
+            Some(url) if matches!(url.scheme(), "http" | "https") => fetch(&client, url).await?,
+            Some(_) => return Err(ApiError::MalformedUrl),
+            None => load_bcd_updates(&client, &SETTINGS.application.bcd_updates_url).await?,
         }
-    }
+    } else {
+        body
+    };
+    let summary = match BcdFeed::from_slice(&bytes) {
+        Ok(feed) => FeedSummary::from(&feed),
+        Err(e) => FeedSummary::from(e),
+    };
+    Ok(HttpResponse::Ok().json(summary))
+}
 
-    Ok(())
+#[derive(Deserialize)]
+pub struct SyncRunsQuery {
+    limit: Option<i64>,
 }
 
-async fn synchronize_updates(pool: &mut PgConnection, json: Value) -> Result<(), ApiError> {
-    let mut release_versions_cached = HashMap::<String, i64>::new();
-    let mut feature_info_cached = HashMap::<String, i64>::new();
+pub async fn list_bcd_sync_runs(
+    pool: Data<Pool>,
+    query: web::Query<SyncRunsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let mut conn = pool.get()?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let runs = last_sync_runs(&mut conn, limit)?;
+    Ok(HttpResponse::Ok().json(runs))
+}
 
-    json.as_array().unwrap().iter().for_each(|val| {
-        let _browser = val
-            .as_array()
-            .unwrap()
+fn synchronize_browers_and_releases(
+    conn: &mut PgConnection,
+    feed: &BcdFeed,
+    report: &mut SyncReport,
+) -> Result<(), ApiError> {
+    let browsers = &feed.browsers;
+    let inserted: Vec<Upserted> = sql_query(
+        "INSERT INTO browsers
+            (name, display_name, accepts_flags, accepts_webextensions, pref_url, preview_name)
+        SELECT * FROM unnest($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (name) DO UPDATE SET
+            display_name = excluded.display_name,
+            accepts_flags = excluded.accepts_flags,
+            accepts_webextensions = excluded.accepts_webextensions,
+            pref_url = excluded.pref_url,
+            preview_name = excluded.preview_name
+        WHERE (browsers.display_name, browsers.accepts_flags, browsers.accepts_webextensions,
+            browsers.pref_url, browsers.preview_name) IS DISTINCT FROM
+            (excluded.display_name, excluded.accepts_flags, excluded.accepts_webextensions,
+            excluded.pref_url, excluded.preview_name)
+        RETURNING xmax = 0 AS inserted",
+    )
+    .bind::<Array<Text>, _>(browsers.iter().map(|b| &b.name).collect::<Vec<_>>())
+    .bind::<Array<Text>, _>(browsers.iter().map(|b| &b.display_name).collect::<Vec<_>>())
+    .bind::<Array<Bool>, _>(browsers.iter().map(|b| b.accepts_flags).collect::<Vec<_>>())
+    .bind::<Array<Bool>, _>(
+        browsers
             .iter()
-            .find(|x| x["browser"].as_str().is_some())
-            .unwrap();
-        let key = format!(
-            "{:1}-{:2}",
-            _browser["browser"].as_str().unwrap(),
-            _browser["version"].as_str().unwrap()
-        );
-        let added = val
-            .as_array()
-            .unwrap()
+            .map(|b| b.accepts_webextensions)
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Nullable<Text>>, _>(browsers.iter().map(|b| &b.pref_url).collect::<Vec<_>>())
+    .bind::<Array<Nullable<Text>>, _>(
+        browsers
             .iter()
-            .find(|x| x["added"].as_array().is_some() && !x["added"].as_array().unwrap().is_empty())
-            .map(|v| &v["added"]);
-        let removed = val
-            .as_array()
-            .unwrap()
+            .map(|b| &b.preview_name)
+            .collect::<Vec<_>>(),
+    )
+    .load(conn)
+    .map_err(|e| ApiError::Generic(format!("Error synchronizing browsers: {e}")))?;
+    report.browsers = SyncCounts::upserted(&inserted);
+
+    let releases = &feed.releases;
+    let inserted: Vec<Upserted> = sql_query(
+        "INSERT INTO browser_releases
+            (browser, engine, engine_version, release_id, release_date, release_notes, status)
+        SELECT * FROM unnest($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (browser, engine, release_date, release_id) DO UPDATE SET
+            engine_version = excluded.engine_version,
+            release_notes = excluded.release_notes,
+            status = excluded.status
+        WHERE (browser_releases.engine_version, browser_releases.release_notes,
+            browser_releases.status) IS DISTINCT FROM
+            (excluded.engine_version, excluded.release_notes, excluded.status)
+        RETURNING xmax = 0 AS inserted",
+    )
+    .bind::<Array<Text>, _>(releases.iter().map(|r| &r.browser).collect::<Vec<_>>())
+    .bind::<Array<Text>, _>(
+        releases
             .iter()
-            .find(|x| {
-                x["removed"].as_array().is_some() && !x["removed"].as_array().unwrap().is_empty()
-            })
-            .map(|v| &v["removed"]);
-
-        let mut _added_ = Vec::new();
-        let mut _removed_ = Vec::new();
-        if added.is_none() && removed.is_none() {
-            return;
-        }
-        let mut _browser_release_id = match release_versions_cached.get(&key) {
-            Some(val) => *val,
-            None => {
-                let _release_id: Result<i64, diesel::result::Error> = browser_releases::table
-                    .select(browser_releases::id)
-                    .filter(
-                        browser_releases::browser
-                            .eq(_browser["browser"].as_str().unwrap())
-                            .and(
-                                browser_releases::release_id
-                                    .eq(_browser["version"].as_str().unwrap()),
-                            ),
-                    )
-                    .first(pool);
-                if _release_id.is_err() {
-                    error!(
-                        "{:}, {:}",
-                        _browser["browser"].as_str().unwrap(),
-                        _browser["version"].as_str().unwrap()
-                    );
-                }
-                let unwrapped = _release_id.unwrap();
-
-                release_versions_cached.insert(key, unwrapped);
-                unwrapped
-            }
-        };
-
-        if let Some(Value::Array(val)) = added {
-            for added in val {
-                let path = added["path"]
-                    .as_str()
-                    .unwrap_or_else(|| added.as_str().unwrap());
-                let engines: Vec<EngineType> =
-                    serde_json::from_value(added["engines"].clone()).unwrap_or_default();
-                let feature_id = match feature_info_cached.get(path) {
-                    Some(val) => *val,
-                    None => {
-                        let _feature_id: Result<i64, diesel::result::Error> = bcd_features::table
-                            .select(bcd_features::id)
-                            .filter(bcd_features::path.eq(path))
-                            .first(pool);
-                        if _feature_id.is_err() {
-                            error!("Error {:}", path);
-                        }
-                        let unwrapped = _feature_id.unwrap();
-
-                        feature_info_cached.insert(path.to_owned(), unwrapped);
-                        unwrapped
-                    }
-                };
-                _added_.push((
-                    bcd_updates::browser_release.eq(_browser_release_id),
-                    bcd_updates::event_type.eq(BcdUpdateEventType::AddedStable),
-                    bcd_updates::feature.eq(feature_id),
-                    bcd_updates::engines.eq(engines),
-                ));
-            }
-        }
+            .map(|r| r.engine.as_deref().unwrap_or("Unknown"))
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Text>, _>(
+        releases
+            .iter()
+            .map(|r| r.engine_version.as_deref().unwrap_or("Unknown"))
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Text>, _>(releases.iter().map(|r| &r.release_id).collect::<Vec<_>>())
+    .bind::<Array<Date>, _>(releases.iter().map(|r| r.release_date).collect::<Vec<_>>())
+    .bind::<Array<Nullable<Text>>, _>(
+        releases
+            .iter()
+            .map(|r| &r.release_notes)
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Nullable<Text>>, _>(releases.iter().map(|r| &r.status).collect::<Vec<_>>())
+    .load(conn)
+    .map_err(|e| ApiError::Generic(format!("Error synchronizing browser releases: {e}")))?;
+    report.browser_releases = SyncCounts::upserted(&inserted);
+    Ok(())
+}
 
-        if let Some(val) = removed {
-            for removed in val.as_array().unwrap() {
-                let path = removed["path"]
-                    .as_str()
-                    .unwrap_or_else(|| removed.as_str().unwrap());
-                let engines: Vec<EngineType> =
-                    serde_json::from_value(removed["engines"].clone()).unwrap_or_default();
-                let feature_id = match feature_info_cached.get(path) {
-                    Some(val) => *val,
-                    None => {
-                        let _feature_id: Result<i64, diesel::result::Error> = bcd_features::table
-                            .select(bcd_features::id)
-                            .filter(bcd_features::path.eq(path))
-                            .first(pool);
-
-                        let unwrapped = _feature_id.unwrap();
-
-                        feature_info_cached.insert(path.to_owned(), unwrapped);
-                        unwrapped
-                    }
-                };
-                _removed_.push((
-                    bcd_updates::browser_release.eq(_browser_release_id),
-                    bcd_updates::event_type.eq(BcdUpdateEventType::RemovedStable),
-                    bcd_updates::feature.eq(feature_id),
-                    bcd_updates::engines.eq(engines),
-                ));
-            }
-        }
+/// `mdn_url` and `short_title` are only set on insert, afterwards they belong to
+/// [`synchronize_path_mappings`].
+fn synchronize_features(conn: &mut PgConnection, json: &[Feature]) -> Result<SyncCounts, ApiError> {
+    let inserted: Vec<Upserted> = sql_query(
+        "INSERT INTO bcd_features
+            (path, mdn_url, source_file, spec_url, deprecated, experimental, standard_track)
+        SELECT * FROM unnest($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (path) DO UPDATE SET
+            source_file = excluded.source_file,
+            spec_url = excluded.spec_url,
+            deprecated = excluded.deprecated,
+            experimental = excluded.experimental,
+            standard_track = excluded.standard_track
+        WHERE (bcd_features.source_file, bcd_features.spec_url, bcd_features.deprecated,
+            bcd_features.experimental, bcd_features.standard_track) IS DISTINCT FROM
+            (excluded.source_file, excluded.spec_url, excluded.deprecated,
+            excluded.experimental, excluded.standard_track)
+        RETURNING xmax = 0 AS inserted",
+    )
+    .bind::<Array<Text>, _>(json.iter().map(|f| &f.path).collect::<Vec<_>>())
+    .bind::<Array<Nullable<Text>>, _>(json.iter().map(|f| &f.mdn_url).collect::<Vec<_>>())
+    .bind::<Array<Text>, _>(json.iter().map(|f| &f.source_file).collect::<Vec<_>>())
+    .bind::<Array<Nullable<Text>>, _>(
+        json.iter()
+            .map(|f| f.spec_url.as_ref().and_then(SpecUrl::single))
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Nullable<Bool>>, _>(
+        json.iter()
+            .map(|f| f.status.deprecated)
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Nullable<Bool>>, _>(
+        json.iter()
+            .map(|f| f.status.experimental)
+            .collect::<Vec<_>>(),
+    )
+    .bind::<Array<Nullable<Bool>>, _>(
+        json.iter()
+            .map(|f| f.status.standard_track)
+            .collect::<Vec<_>>(),
+    )
+    .load(conn)
+    .map_err(|e| ApiError::Generic(format!("Error synchronizing features: {e}")))?;
+
+    Ok(SyncCounts::upserted(&inserted))
+}
 
-        let added_results = diesel::insert_into(bcd_updates::table)
-            .values(&_added_)
-            .on_conflict((bcd_updates::browser_release, bcd_updates::feature))
-            .do_update()
-            .set((
-                bcd_updates::browser_release.eq(excluded(bcd_updates::browser_release)),
-                bcd_updates::event_type.eq(excluded(bcd_updates::event_type)),
-                bcd_updates::feature.eq(excluded(bcd_updates::feature)),
-                bcd_updates::engines.eq(excluded(bcd_updates::engines)),
-            ))
-            .execute(pool)
-            .map_err(|e| {
-                error!("{:?} \n {:?}, {:?}", e, added, _browser_release_id);
-                e
-            });
-        if added_results.is_err() {
-            warn!("Error creating added bcd updates")
-        }
-        let remove_results = diesel::insert_into(bcd_updates::table)
-            .values(_removed_)
-            .on_conflict((bcd_updates::browser_release, bcd_updates::feature))
-            .do_update()
-            .set((
-                bcd_updates::browser_release.eq(excluded(bcd_updates::browser_release)),
-                bcd_updates::event_type.eq(excluded(bcd_updates::event_type)),
-                bcd_updates::feature.eq(excluded(bcd_updates::feature)),
-                bcd_updates::engines.eq(excluded(bcd_updates::engines)),
-            ))
-            .execute(pool)
-            .map_err(|e| {
-                error!("{:?} \n {:?} , {:?}", e, removed, _browser_release_id);
-                e
-            });
-        if remove_results.is_err() {
-            warn!("Error creating feature removed bcd updates")
-        }
-    });
-    Ok(())
+fn release_id(
+    conn: &mut PgConnection,
+    cache: &mut HashMap<(String, String), i64>,
+    browser: &str,
+    version: &str,
+) -> Result<i64, ApiError> {
+    let key = (browser.to_string(), version.to_string());
+    if let Some(id) = cache.get(&key) {
+        return Ok(*id);
+    }
+    let id = browser_releases::table
+        .select(browser_releases::id)
+        .filter(
+            browser_releases::browser
+                .eq(browser)
+                .and(browser_releases::release_id.eq(version)),
+        )
+        .first(conn)
+        .map_err(|e| ApiError::Generic(format!("Unknown release {browser} {version}: {e}")))?;
+    cache.insert(key, id);
+    Ok(id)
 }
 
-async fn synchronize_path_mappings(
-    pool: &mut PgConnection,
-    client: Data<Client>,
-) -> Result<(), ApiError> {
-    let values = client
-        .get(SETTINGS.application.mdn_metadata_url.clone())
-        .send()
-        .await
-        .map_err(|err: reqwest::Error| match err.status() {
-            Some(StatusCode::NOT_FOUND) => {
-                warn!(
-                    "Error NOT_FOUND fetching all metadata {} ",
-                    &SETTINGS.application.mdn_metadata_url
-                );
-                ApiError::DocumentNotFound
-            }
-            _ => {
-                warn!(
-                    "Error Unknown fetching all metadata {} ",
-                    &SETTINGS.application.mdn_metadata_url
-                );
-                ApiError::Unknown
-            }
-        })?;
+fn feature_id(
+    conn: &mut PgConnection,
+    cache: &mut HashMap<String, i64>,
+    path: &str,
+) -> Result<i64, ApiError> {
+    if let Some(id) = cache.get(path) {
+        return Ok(*id);
+    }
+    let id = bcd_features::table
+        .select(bcd_features::id)
+        .filter(bcd_features::path.eq(path))
+        .first(conn)
+        .map_err(|e| ApiError::Generic(format!("Unknown feature {path}: {e}")))?;
+    cache.insert(path.to_owned(), id);
+    Ok(id)
+}
+
+/// Upserts all events of the feed and, with `remove_missing`, removes the events no
+/// longer in it.
+fn synchronize_updates(
+    conn: &mut PgConnection,
+    json: &[Update],
+    remove_missing: bool,
+) -> Result<SyncCounts, ApiError> {
+    let mut release_versions_cached = HashMap::<(String, String), i64>::new();
+    let mut feature_info_cached = HashMap::<String, i64>::new();
+    let mut events_by_key = BTreeMap::<(i64, i64, BcdUpdateEventType), &Update>::new();
+
+    for event in json {
+        let browser_release_id = release_id(
+            conn,
+            &mut release_versions_cached,
+            &event.browser,
+            &event.version,
+        )?;
+        let feature = feature_id(conn, &mut feature_info_cached, &event.path)?;
+        events_by_key.insert((browser_release_id, feature, event.event_type), event);
+    }
 
-    let json: Value = values
-        .json()
-        .await
-        .map_err(|_| ApiError::DocumentNotFound)?;
+    let releases: Vec<i64> = events_by_key.keys().map(|key| key.0).collect();
+    let features: Vec<i64> = events_by_key.keys().map(|key| key.1).collect();
+    let event_types: Vec<BcdUpdateEventType> = events_by_key.keys().map(|key| key.2).collect();
+    // Arrays of arrays can't be unnested row by row, so the engines travel as JSON.
+    let engines: Vec<Value> = events_by_key
+        .values()
+        .map(|event| json!(event.engines))
+        .collect();
+    let befores: Vec<Option<&Value>> = events_by_key
+        .values()
+        .map(|event| event.before.as_ref())
+        .collect();
+    let afters: Vec<Option<&Value>> = events_by_key
+        .values()
+        .map(|event| event.after.as_ref())
+        .collect();
 
-    struct PathAndShortTitle {
-        path: String,
-        mdn_url: String,
-        short_title: String,
+    let inserted: Vec<Upserted> = sql_query(
+        "INSERT INTO bcd_updates
+            (browser_release, feature, event_type, engines, statement_before, statement_after)
+        SELECT f.browser_release, f.feature, f.event_type,
+            ARRAY(SELECT jsonb_array_elements_text(f.engines)::engine_type),
+            f.statement_before, f.statement_after
+        FROM unnest($1, $2, $3, $4, $5, $6)
+            AS f(browser_release, feature, event_type, engines, statement_before, statement_after)
+        ON CONFLICT (browser_release, feature, event_type) DO UPDATE SET
+            engines = excluded.engines,
+            statement_before = excluded.statement_before,
+            statement_after = excluded.statement_after
+        WHERE (bcd_updates.engines, bcd_updates.statement_before, bcd_updates.statement_after)
+            IS DISTINCT FROM
+            (excluded.engines, excluded.statement_before, excluded.statement_after)
+        RETURNING xmax = 0 AS inserted",
+    )
+    .bind::<Array<Int8>, _>(&releases)
+    .bind::<Array<Int8>, _>(&features)
+    .bind::<Array<BcdEventType>, _>(&event_types)
+    .bind::<Array<Jsonb>, _>(engines)
+    .bind::<Array<Nullable<Jsonb>>, _>(befores)
+    .bind::<Array<Nullable<Jsonb>>, _>(afters)
+    .load(conn)
+    .map_err(|e| ApiError::Generic(format!("Error synchronizing bcd updates: {e}")))?;
+    let mut counts = SyncCounts::upserted(&inserted);
+
+    if remove_missing {
+        counts.removed = sql_query(
+            "DELETE FROM bcd_updates u WHERE NOT EXISTS (
+                SELECT 1 FROM unnest($1, $2, $3) AS f(browser_release, feature, event_type)
+                WHERE f.browser_release = u.browser_release
+                    AND f.feature = u.feature
+                    AND f.event_type = u.event_type
+            )",
+        )
+        .bind::<Array<Int8>, _>(releases)
+        .bind::<Array<Int8>, _>(features)
+        .bind::<Array<BcdEventType>, _>(event_types)
+        .execute(conn)
+        .map_err(|e| ApiError::Generic(format!("Error removing bcd updates: {e}")))?
+            as i64;
     }
+    Ok(counts)
+}
 
-    //1. Get all values with a bcd path, extract path, mdn_url, short title.
-    let mut path_map: HashMap<String, (String, String)> = HashMap::new();
-
-    let extract: Vec<PathAndShortTitle> = json
-        .as_array()
-        .unwrap()
-        .iter()
-        .filter(|val| val["browserCompat"].as_array().is_some())
-        .map(|filtered| {
-            let paths = filtered["browserCompat"].as_array().unwrap();
-            if paths.len() > 1 {
-                debug!("Multiple paths detected for {:?}", paths);
-            }
+/// Recomputes the Baseline status of every feature from its events. `baseline_changed_at`
+/// is only touched when the status itself changes.
+fn synchronize_baseline(conn: &mut PgConnection) -> Result<SyncCounts, ApiError> {
+    let events: Vec<(i64, String, NaiveDate, BcdUpdateEventType, Option<Value>)> =
+        bcd_updates::table
+            .inner_join(browser_releases::table)
+            .select((
+                bcd_updates::feature,
+                browser_releases::browser,
+                browser_releases::release_date,
+                bcd_updates::event_type,
+                bcd_updates::statement_after,
+            ))
+            .get_results(conn)?;
+    let mut events_by_feature: HashMap<i64, Vec<SupportEvent>> = HashMap::new();
+    for (feature, browser, release_date, event_type, statement_after) in &events {
+        events_by_feature
+            .entry(*feature)
+            .or_default()
+            .push(SupportEvent {
+                browser,
+                release_date: *release_date,
+                event_type: *event_type,
+                statement_after: statement_after.as_ref(),
+            });
+    }
 
-            for path in paths {
-                path_map.insert(
-                    path.as_str().unwrap().into(),
-                    (
-                        String::from_str(filtered["mdn_url"].as_str().unwrap()).unwrap(),
-                        String::from_str(filtered["short_title"].as_str().unwrap()).unwrap(),
-                    ),
-                );
-            }
+    let features: Vec<(i64, BaselineStatus, Option<NaiveDate>)> = bcd_features::table
+        .select((
+            bcd_features::id,
+            bcd_features::baseline_status,
+            bcd_features::baseline_date,
+        ))
+        .get_results(conn)?;
+    let today = Utc::now().date_naive();
+    let mut changes: HashMap<(BaselineStatus, Option<NaiveDate>, bool), Vec<i64>> = HashMap::new();
+    for (id, status, date) in features {
+        let new_date = baseline_date(events_by_feature.remove(&id).unwrap_or_default());
+        let new_status = baseline_status(new_date, today);
+        if (new_status, new_date) != (status, date) {
+            changes
+                .entry((new_status, new_date, new_status != status))
+                .or_default()
+                .push(id);
+        }
+    }
 
-            PathAndShortTitle {
-                path: String::from_str(paths[0].as_str().unwrap()).unwrap(),
-                mdn_url: String::from_str(filtered["mdn_url"].as_str().unwrap()).unwrap(),
-                short_title: String::from_str(filtered["short_title"].as_str().unwrap()).unwrap(),
-            }
-        })
-        .collect();
+    let mut counts = SyncCounts::default();
+    for ((status, date, status_changed), ids) in changes {
+        let target = bcd_features::table.filter(bcd_features::id.eq_any(ids));
+        let values = (
+            bcd_features::baseline_status.eq(status),
+            bcd_features::baseline_date.eq(date),
+        );
+        counts.updated += if status_changed {
+            update(target)
+                .set((values, bcd_features::baseline_changed_at.eq(now)))
+                .execute(conn)
+        } else {
+            update(target).set(values).execute(conn)
+        }
+        .map_err(|e| ApiError::Generic(format!("Error updating baseline status: {e}")))?
+            as i64;
+    }
+    Ok(counts)
+}
 
-    extract.iter().for_each(|path_and_title| {
-        let res =
-            update(schema::bcd_features::table.filter(bcd_features::path.eq(&path_and_title.path)))
-                .set((
-                    bcd_features::mdn_url.eq(&path_and_title.mdn_url),
-                    bcd_features::short_title.eq(&path_and_title.short_title),
-                ))
-                .execute(pool);
-        if let Err(err) = res {
-            warn!("Error updating {:}, {:?}", &path_and_title.path, err);
+/// Sets `mdn_url` and `short_title` of features from the content metadata. Features
+/// without a page of their own inherit them from the closest parent path that has one.
+fn synchronize_path_mappings(
+    conn: &mut PgConnection,
+    pages: &[PageMetadata],
+) -> Result<SyncCounts, ApiError> {
+    //1. Get all values with a bcd path, extract path, mdn_url, short title.
+    let mut path_map: HashMap<&str, (&str, &str)> = HashMap::new();
+    for page in pages {
+        if page.browser_compat.len() > 1 {
+            debug!("Multiple paths detected for {:?}", page.browser_compat);
+        }
+        for path in &page.browser_compat {
+            path_map.insert(path, (&page.mdn_url, &page.short_title));
         }
-    });
+    }
+
+    let mut counts = SyncCounts::default();
+    for page in pages {
+        counts.updated += set_page(
+            conn,
+            &page.browser_compat[0],
+            &page.mdn_url,
+            &page.short_title,
+        )? as i64;
+    }
 
     //2. Find paths with missing info and patch them to the next higher subpath.
     let null_vals: Vec<String> = schema::bcd_features::table
         .select(schema::bcd_features::path)
         .filter(bcd_features::mdn_url.is_null())
-        .get_results::<String>(pool)?;
+        .get_results::<String>(conn)?;
     //Let's find all the features without a
     for val in null_vals {
         let mut parts: Vec<&str> = val.split('.').collect();
@@ -430,29 +644,41 @@ async fn synchronize_path_mappings(
         while !parts.is_empty() {
             let subpath = parts.join(".");
             debug!("checking subpath {:} for {:}", subpath, val);
-            if let Some(replacement) = path_map.get(&subpath) {
+            if let Some(replacement) = path_map.get(subpath.as_str()) {
                 debug!(
                     "Replacing missing url + title for path {:} with {:}'s ({:},{:})",
                     val, subpath, &replacement.0, &replacement.1
                 );
-                let res =
-                    update(schema::bcd_features::table.filter(schema::bcd_features::path.eq(&val)))
-                        .set((
-                            schema::bcd_features::mdn_url.eq(&replacement.0),
-                            schema::bcd_features::short_title.eq(&replacement.1),
-                        ))
-                        .execute(pool);
-                if let Err(err) = res {
-                    warn!(
-                        "Error updating {:} with metadata from {:}, {:?}",
-                        val, subpath, err
-                    );
-                }
+                counts.updated += set_page(conn, &val, replacement.0, replacement.1)? as i64;
                 break;
             }
             parts.pop();
         }
     }
 
-    Ok(())
+    Ok(counts)
+}
+
+/// Returns the number of rows that actually changed.
+fn set_page(
+    conn: &mut PgConnection,
+    path: &str,
+    mdn_url: &str,
+    short_title: &str,
+) -> Result<usize, ApiError> {
+    update(
+        schema::bcd_features::table.filter(
+            bcd_features::path.eq(path).and(
+                bcd_features::mdn_url
+                    .is_distinct_from(mdn_url)
+                    .or(bcd_features::short_title.is_distinct_from(short_title)),
+            ),
+        ),
+    )
+    .set((
+        bcd_features::mdn_url.eq(mdn_url),
+        bcd_features::short_title.eq(short_title),
+    ))
+    .execute(conn)
+    .map_err(|e| ApiError::Generic(format!("Error updating {path}: {e}")))
 }
@@ -22,7 +22,9 @@ use reqwest::Client as HttpClient;
 use rumba::{
     add_services,
     api::error::{error_handler, ERROR_ID_HEADER_NAME_STR},
+    api::mail::Mailer,
     api::play::{GithubFlagsClient, GithubGistClient},
+    api::play_storage::PlaygroundStorage,
     db,
     fxa::LoginManager,
     logging::{self, init_logging},
@@ -67,6 +69,7 @@ async fn main() -> anyhow::Result<()> {
     });
 
     let http_client = Data::new(HttpClient::new());
+    let mailer = Data::new(Mailer::new(SETTINGS.notifications.as_ref(), &http_client));
     let login_manager = Data::new(LoginManager::init().await?);
     let arbiter = Arbiter::new();
     let arbiter_handle = Data::new(arbiter.handle());
@@ -100,14 +103,23 @@ async fn main() -> anyhow::Result<()> {
             async_openai::Client::with_config(OpenAIConfig::new().with_api_key(&c.api_key))
         }));
 
-    let github_gist_client = Data::new(GithubGistClient(SETTINGS.playground.as_ref().and_then(
-        |p| {
-            OctocrabBuilder::new()
-                .personal_token(p.github_gist_token.clone())
-                .build()
-                .ok()
-        },
-    )));
+    let github_gist_client = Data::new(GithubGistClient(
+        SETTINGS
+            .playground
+            .as_ref()
+            .and_then(|p| p.github_gist_token.as_ref())
+            .and_then(|token| {
+                OctocrabBuilder::new()
+                    .personal_token(token.clone())
+                    .build()
+                    .ok()
+            }),
+    ));
+    let playground_storage = Data::new(PlaygroundStorage::new(
+        SETTINGS.playground.as_ref().map(|p| p.storage),
+        &pool,
+        github_gist_client.0.as_ref(),
+    ));
     let github_flags_client = Data::new(GithubFlagsClient(SETTINGS.playground.as_ref().and_then(
         |p| {
             OctocrabBuilder::new()
@@ -136,6 +148,7 @@ async fn main() -> anyhow::Result<()> {
             .wrap(Logger::new(LOG_FMT).exclude("/healthz"))
             .app_data(Data::clone(&openai_client))
             .app_data(Data::clone(&github_gist_client))
+            .app_data(Data::clone(&playground_storage))
             .app_data(Data::clone(&github_flags_client))
             .app_data(Data::clone(&basket_client))
             .app_data(Data::clone(&metrics))
@@ -143,6 +156,7 @@ async fn main() -> anyhow::Result<()> {
             .app_data(Data::clone(&supabase_pool))
             .app_data(Data::clone(&arbiter_handle))
             .app_data(Data::clone(&http_client))
+            .app_data(Data::clone(&mailer))
             .app_data(Data::clone(&login_manager))
             .app_data(Data::clone(&elastic_client));
         add_services(app)
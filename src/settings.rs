@@ -4,6 +4,7 @@ use harsh::Harsh;
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 use serde_with::{base64::Base64, serde_as};
+use std::collections::HashMap;
 use std::env;
 use url::Url;
 
@@ -44,11 +45,136 @@ pub struct Application {
     pub encoded_id_salt: String,
 }
 
+#[serde_as]
 #[derive(Deserialize)]
 pub struct Search {
     pub url: String,
     pub cache_max_age: u32,
     pub query_max_length: usize,
+    #[serde(default = "default_search_retry_attempts")]
+    pub retry_attempts: u32,
+    #[serde(default = "default_search_retry_backoff_in_ms")]
+    pub retry_backoff_in_ms: u64,
+    #[serde(default = "default_search_circuit_breaker_threshold")]
+    pub circuit_breaker_threshold: u32,
+    #[serde(default = "default_search_circuit_breaker_cooldown_in_sec")]
+    pub circuit_breaker_cooldown_in_sec: u64,
+    #[serde(default = "default_search_fallback_cache_size")]
+    pub fallback_cache_size: usize,
+    /// Share of searches recorded in `search_query_stats`, between 0 and 1.
+    #[serde(default)]
+    pub analytics_sample_rate: f64,
+    /// Signs the `search_after` cursors, cursor pagination is disabled without it.
+    #[serde_as(as = "Option<Base64>")]
+    #[serde(default)]
+    pub cursor_sign_key: Option<[u8; 32]>,
+    #[serde(default)]
+    pub ranking: Ranking,
+    /// Number of spelling suggestions returned at most.
+    #[serde(default = "default_search_max_suggestions")]
+    pub max_suggestions: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Ranking {
+    /// Profile used for searches without a locale override.
+    #[serde(default = "default_ranking_profile")]
+    pub profile: String,
+    /// Profile names by locale code, e.g. `de = "compounds"`.
+    #[serde(default)]
+    pub locales: HashMap<String, String>,
+    /// Named profiles, `default` is built in unless configured here.
+    #[serde(default)]
+    pub profiles: HashMap<String, RankingProfile>,
+}
+
+impl Default for Ranking {
+    fn default() -> Self {
+        Ranking {
+            profile: default_ranking_profile(),
+            locales: HashMap::new(),
+            profiles: HashMap::new(),
+        }
+    }
+}
+
+fn default_ranking_profile() -> String {
+    "default".to_string()
+}
+
+/// Boosts for the lexical search, unset values fall back to the defaults.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RankingProfile {
+    pub title_phrase_boost: f64,
+    pub title_boost: f64,
+    pub body_phrase_boost: f64,
+    pub body_boost: f64,
+    pub popularity_factor: f64,
+    pub recency: Option<RecencyDecay>,
+}
+
+impl Default for RankingProfile {
+    fn default() -> Self {
+        RankingProfile {
+            title_phrase_boost: 10.0,
+            title_boost: 5.0,
+            body_phrase_boost: 2.0,
+            body_boost: 1.0,
+            popularity_factor: 10.0,
+            recency: None,
+        }
+    }
+}
+
+/// Exponential decay of the score with the age of a document.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RecencyDecay {
+    #[serde(default = "default_recency_field")]
+    pub field: String,
+    pub scale_in_days: u32,
+    #[serde(default)]
+    pub offset_in_days: u32,
+    #[serde(default = "default_recency_decay")]
+    pub decay: f64,
+    #[serde(default = "default_recency_weight")]
+    pub weight: f64,
+}
+
+fn default_recency_field() -> String {
+    "modified".to_string()
+}
+
+fn default_recency_decay() -> f64 {
+    0.5
+}
+
+fn default_recency_weight() -> f64 {
+    1.0
+}
+
+fn default_search_retry_attempts() -> u32 {
+    3
+}
+
+fn default_search_retry_backoff_in_ms() -> u64 {
+    50
+}
+
+fn default_search_circuit_breaker_threshold() -> u32 {
+    5
+}
+
+fn default_search_circuit_breaker_cooldown_in_sec() -> u64 {
+    30
+}
+
+fn default_search_fallback_cache_size() -> usize {
+    1000
+}
+
+fn default_search_max_suggestions() -> u64 {
+    3
 }
 
 #[derive(Deserialize, Default)]
@@ -90,16 +216,62 @@ fn default_gist_owner() -> String {
     "mdn-bot".to_string()
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaygroundStorageBackend {
+    #[default]
+    Gist,
+    Postgres,
+}
+
+/// An additional share-id key. New share ids are encrypted with the key with the
+/// highest version and prefixed with that version.
+#[serde_as]
+#[derive(Debug, Deserialize)]
+pub struct PlaygroundCryptKey {
+    pub version: i32,
+    #[serde_as(as = "Base64")]
+    pub key: [u8; 32],
+}
+
 #[serde_as]
 #[derive(Debug, Deserialize)]
 pub struct Playground {
-    pub github_gist_token: String,
+    #[serde(default)]
+    pub github_gist_token: Option<String>,
     pub github_flags_token: String,
-    #[serde_as(as = "Base64")]
-    pub crypt_key: [u8; 32],
+    /// Key version 0, used for share ids without a version prefix.
+    #[serde_as(as = "Option<Base64>")]
+    #[serde(default)]
+    pub crypt_key: Option<[u8; 32]>,
+    #[serde(default)]
+    pub crypt_keys: Vec<PlaygroundCryptKey>,
     pub flag_repo: String,
     #[serde(default = "default_gist_owner")]
     pub github_gist_owner: String,
+    #[serde(default)]
+    pub storage: PlaygroundStorageBackend,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailBackend {
+    /// Only logs the mails, for local development.
+    #[default]
+    Log,
+    /// Posts the mails as JSON to `mail_url`, e.g. a transactional mail service.
+    Http,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Notifications {
+    #[serde(default)]
+    pub mail_backend: MailBackend,
+    #[serde(default)]
+    pub mail_url: Option<Url>,
+    #[serde(default)]
+    pub mail_token: Option<String>,
+    pub mail_from: String,
 }
 
 #[derive(Deserialize)]
@@ -115,6 +287,8 @@ pub struct Settings {
     pub basket: Option<Basket>,
     pub ai: Option<AI>,
     pub playground: Option<Playground>,
+    /// Without it no digests of BCD notifications are mailed.
+    pub notifications: Option<Notifications>,
     #[serde(default)]
     pub skip_migrations: bool,
     pub maintenance: Option<String>,
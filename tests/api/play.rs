@@ -1,15 +1,21 @@
-use crate::helpers::app::{drop_stubr, test_app_with_login};
+use std::time::Duration;
+
+use crate::helpers::app::{drop_stubr, test_app_with_login, test_app_with_playground_storage};
 use crate::helpers::db::reset;
 use crate::helpers::http_client::TestHttpClient;
 use crate::helpers::read_json;
 use actix_http::StatusCode;
+use actix_rt::time::{sleep, timeout};
 use actix_web::test;
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use assert_json_diff::assert_json_eq;
 use diesel::prelude::*;
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
-use rumba::db::model::PlaygroundQuery;
+use rumba::api::play::encrypt;
+use rumba::db::model::{PlaygroundInsert, PlaygroundQuery};
+use rumba::db::play::create_playground;
 use rumba::db::schema;
+use rumba::settings::PlaygroundStorageBackend;
 use serde_json::json;
 
 #[actix_rt::test]
@@ -47,7 +53,16 @@ async fn test_playground() -> Result<(), Error> {
     let json = read_json(load).await;
     assert_json_eq!(
         json,
-        json!({"html":"<h1>foo</h1>","css":"h1 { font-size: 4rem; }","js":"const foo = 1;","src":null})
+        json!({
+            "version": 2,
+            "mode": "javascript",
+            "head": null,
+            "html": "<h1>foo</h1>",
+            "css": "h1 { font-size: 4rem; }",
+            "files": [{"name": "script.js", "content": "const foo = 1;"}],
+            "libraries": [],
+            "src": null
+        })
     );
 
     let mut conn = pool.get()?;
@@ -100,3 +115,544 @@ async fn test_invalid_id() -> Result<(), Error> {
     drop_stubr(stubr).await;
     Ok(())
 }
+
+#[actix_rt::test]
+#[stubr::mock(port = 4321)]
+async fn test_playground_postgres_storage() -> Result<(), Error> {
+    let pool = reset()?;
+    let app = test_app_with_playground_storage(&pool, PlaygroundStorageBackend::Postgres).await?;
+    let service = test::init_service(app).await;
+    let mut client = TestHttpClient::new(service).await;
+    let save = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "html":"<h1>bar</h1>",
+                "css":"h1 { font-size: 2rem; }",
+                "js":"const bar = 1;","src":null
+            }))),
+        )
+        .await;
+    assert_eq!(save.status(), 201);
+    let json = read_json(save).await;
+    let id = json["id"].as_str().unwrap();
+    let load = client
+        .get(
+            &format!("/api/v1/play/{}", utf8_percent_encode(id, NON_ALPHANUMERIC)),
+            None,
+        )
+        .await;
+    assert_eq!(load.status(), 200);
+    let json = read_json(load).await;
+    assert_json_eq!(
+        json,
+        json!({
+            "version": 2,
+            "mode": "javascript",
+            "head": null,
+            "html": "<h1>bar</h1>",
+            "css": "h1 { font-size: 2rem; }",
+            "files": [{"name": "script.js", "content": "const bar = 1;"}],
+            "libraries": [],
+            "src": null
+        })
+    );
+
+    let mut conn = pool.get()?;
+    let playground: PlaygroundQuery = schema::playground::table.first(&mut conn)?;
+    let stored = schema::playground_code::table
+        .filter(schema::playground_code::gist.eq(&playground.gist))
+        .count()
+        .get_result::<i64>(&mut conn)?;
+    assert_eq!(stored, 1);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+#[stubr::mock(port = 4321)]
+async fn test_migrate_gists() -> Result<(), Error> {
+    let pool = reset()?;
+    let app = test_app_with_playground_storage(&pool, PlaygroundStorageBackend::Postgres).await?;
+    let service = test::init_service(app).await;
+    let mut client = TestHttpClient::new(service).await;
+    // Gists are migrated in order, the one with several files is skipped.
+    let mut conn = pool.get()?;
+    for gist in ["7ab1f6c462d9b4418f2", "2decf6c462d9b4418f2"] {
+        create_playground(
+            &mut conn,
+            PlaygroundInsert {
+                gist: gist.to_string(),
+                active: true,
+                ..Default::default()
+            },
+        )?;
+    }
+    let res = client
+        .post(
+            "/admin-api/playground/migrate/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            None,
+        )
+        .await;
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+
+    timeout(Duration::from_secs(10), async {
+        loop {
+            let migrated = schema::playground_code::table
+                .filter(schema::playground_code::gist.eq("2decf6c462d9b4418f2"))
+                .count()
+                .get_result::<i64>(&mut pool.get()?)?;
+            if migrated > 0 {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        Ok::<(), Error>(())
+    })
+    .await
+    .map_err(|_| anyhow!("Gists not migrated after 10 seconds"))??;
+
+    let code = schema::playground_code::table
+        .filter(schema::playground_code::gist.eq("2decf6c462d9b4418f2"))
+        .select(schema::playground_code::code)
+        .first::<serde_json::Value>(&mut pool.get()?)?;
+    assert_json_eq!(
+        code,
+        json!({
+            "version": 2,
+            "mode": "javascript",
+            "head": null,
+            "html": "<h1>foo</h1>",
+            "css": "h1 { font-size: 4rem; }",
+            "files": [{"name": "script.js", "content": "const foo = 1;"}],
+            "libraries": [],
+            "src": null
+        })
+    );
+    let invalid = schema::playground_code::table
+        .filter(schema::playground_code::gist.eq("7ab1f6c462d9b4418f2"))
+        .count()
+        .get_result::<i64>(&mut pool.get()?)?;
+    assert_eq!(invalid, 0);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+#[stubr::mock(port = 4321)]
+async fn test_playground_revisions_and_forks() -> Result<(), Error> {
+    let pool = reset()?;
+    let app = test_app_with_playground_storage(&pool, PlaygroundStorageBackend::Postgres).await?;
+    let service = test::init_service(app).await;
+    let mut client = TestHttpClient::new(service).await;
+    let save = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "title": "first",
+                "html": "<h1>one</h1>",
+            }))),
+        )
+        .await;
+    assert_eq!(save.status(), 201);
+    let json = read_json(save).await;
+    let id = json["id"].as_str().unwrap().to_string();
+    let encoded_id = utf8_percent_encode(&id, NON_ALPHANUMERIC).to_string();
+
+    let update = client
+        .post(
+            &format!("/api/v1/play/{encoded_id}"),
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "title": "second",
+                "html": "<h1>two</h1>",
+            }))),
+        )
+        .await;
+    assert_eq!(update.status(), 200);
+    let json = read_json(update).await;
+    assert_eq!(json["id"], id);
+    assert_eq!(json["revision"], 2);
+
+    let load = client
+        .get(&format!("/api/v1/play/{encoded_id}"), None)
+        .await;
+    let json = read_json(load).await;
+    assert_eq!(json["html"], "<h1>two</h1>");
+    let load = client
+        .get(&format!("/api/v1/play/{encoded_id}?revision=1"), None)
+        .await;
+    let json = read_json(load).await;
+    assert_eq!(json["html"], "<h1>one</h1>");
+    let load = client
+        .get(&format!("/api/v1/play/{encoded_id}?revision=3"), None)
+        .await;
+    assert_eq!(load.status(), StatusCode::NOT_FOUND);
+
+    let revisions = client
+        .get(&format!("/api/v1/play/{encoded_id}/revisions"), None)
+        .await;
+    assert_eq!(revisions.status(), 200);
+    let json = read_json(revisions).await;
+    assert_eq!(json["title"], "second");
+    assert_eq!(json["revision"], 2);
+    assert_eq!(json["revisions"].as_array().unwrap().len(), 2);
+
+    let fork = client
+        .post(&format!("/api/v1/play/{encoded_id}/fork"), None, None)
+        .await;
+    assert_eq!(fork.status(), 201);
+    let json = read_json(fork).await;
+    let fork_id = json["id"].as_str().unwrap().to_string();
+
+    let list = client.get("/api/v1/play/", None).await;
+    assert_eq!(list.status(), 200);
+    let json = read_json(list).await;
+    let playgrounds = json.as_array().unwrap();
+    assert_eq!(playgrounds.len(), 2);
+    let forked = playgrounds
+        .iter()
+        .find(|p| p["forked_from"].is_string())
+        .unwrap();
+    assert_eq!(forked["title"], "second");
+    assert_eq!(forked["revision"], 1);
+
+    let delete = client
+        .delete(
+            &format!(
+                "/api/v1/play/{}",
+                utf8_percent_encode(&fork_id, NON_ALPHANUMERIC)
+            ),
+            None,
+        )
+        .await;
+    assert_eq!(delete.status(), 200);
+    let list = client.get("/api/v1/play/", None).await;
+    let json = read_json(list).await;
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    let stored = schema::playground_code::table
+        .count()
+        .get_result::<i64>(&mut pool.get()?)?;
+    assert_eq!(stored, 1);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+#[stubr::mock(port = 4321)]
+async fn test_playground_update_not_owner() -> Result<(), Error> {
+    let pool = reset()?;
+    let app = test_app_with_playground_storage(&pool, PlaygroundStorageBackend::Postgres).await?;
+    let service = test::init_service(app).await;
+    let mut client = TestHttpClient::new(service).await;
+    let mut conn = pool.get()?;
+    create_playground(
+        &mut conn,
+        PlaygroundInsert {
+            gist: "2decf6c462d9b4418f2".to_string(),
+            active: true,
+            ..Default::default()
+        },
+    )?;
+    let id = rumba::api::play::encrypt("2decf6c462d9b4418f2")?;
+    let encoded_id = utf8_percent_encode(&id, NON_ALPHANUMERIC).to_string();
+    let update = client
+        .post(
+            &format!("/api/v1/play/{encoded_id}"),
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "html": "<h1>mine now</h1>",
+            }))),
+        )
+        .await;
+    assert_eq!(update.status(), StatusCode::FORBIDDEN);
+    let delete = client
+        .delete(&format!("/api/v1/play/{encoded_id}"), None)
+        .await;
+    assert_eq!(delete.status(), StatusCode::FORBIDDEN);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+#[stubr::mock(port = 4321)]
+async fn test_playground_moderation() -> Result<(), Error> {
+    let pool = reset()?;
+    let app = test_app_with_playground_storage(&pool, PlaygroundStorageBackend::Postgres).await?;
+    let service = test::init_service(app).await;
+    let mut client = TestHttpClient::new(service).await;
+    let admin = Some(vec![("Authorization", "Bearer TEST_TOKEN")]);
+    let save = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "html": "<h1>spam</h1>",
+            }))),
+        )
+        .await;
+    assert_eq!(save.status(), 201);
+    let json = read_json(save).await;
+    let id = json["id"].as_str().unwrap().to_string();
+    let encoded_id = utf8_percent_encode(&id, NON_ALPHANUMERIC).to_string();
+
+    let flag = client
+        .post(
+            "/api/v1/play/flag",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "id": id,
+                "reason": "spam",
+            }))),
+        )
+        .await;
+    assert_eq!(flag.status(), StatusCode::CREATED);
+
+    let flagged = client
+        .get("/admin-api/playground/flagged/", admin.clone())
+        .await;
+    assert_eq!(flagged.status(), StatusCode::OK);
+    let json = read_json(flagged).await;
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["hidden"], false);
+    assert_eq!(json[0]["flags"][0]["reason"], "spam");
+    let playground_id = json[0]["id"].as_i64().unwrap();
+
+    let hide = client
+        .post(
+            &format!("/admin-api/playground/{playground_id}/hide/"),
+            admin.clone(),
+            None,
+        )
+        .await;
+    assert_eq!(hide.status(), StatusCode::OK);
+    let load = client
+        .get(&format!("/api/v1/play/{encoded_id}"), None)
+        .await;
+    assert_eq!(load.status(), StatusCode::GONE);
+
+    let preview = client
+        .get(
+            &format!("/admin-api/playground/{playground_id}/"),
+            admin.clone(),
+        )
+        .await;
+    assert_eq!(preview.status(), StatusCode::OK);
+    let json = read_json(preview).await;
+    assert_eq!(json["hidden"], true);
+    assert_eq!(json["code"]["html"], "<h1>spam</h1>");
+
+    let restore = client
+        .post(
+            &format!("/admin-api/playground/{playground_id}/restore/"),
+            admin.clone(),
+            None,
+        )
+        .await;
+    assert_eq!(restore.status(), StatusCode::OK);
+    let load = client
+        .get(&format!("/api/v1/play/{encoded_id}"), None)
+        .await;
+    assert_eq!(load.status(), StatusCode::OK);
+
+    let delete = client
+        .delete(
+            &format!("/admin-api/playground/{playground_id}/"),
+            admin.clone(),
+        )
+        .await;
+    assert_eq!(delete.status(), StatusCode::OK);
+    let mut conn = pool.get()?;
+    let playgrounds = schema::playground::table
+        .count()
+        .get_result::<i64>(&mut conn)?;
+    assert_eq!(playgrounds, 0);
+    let stored = schema::playground_code::table
+        .count()
+        .get_result::<i64>(&mut conn)?;
+    assert_eq!(stored, 0);
+
+    // Playgrounds without a row can still be flagged, only as an issue.
+    let legacy_id = encrypt("2decf6c462d9b4418f2")?;
+    let flag = client
+        .post(
+            "/api/v1/play/flag",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "id": legacy_id,
+                "reason": "spam",
+            }))),
+        )
+        .await;
+    assert_eq!(flag.status(), StatusCode::CREATED);
+    let flagged = client.get("/admin-api/playground/flagged/", admin).await;
+    assert_eq!(read_json(flagged).await, json!([]));
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+#[stubr::mock(port = 4321)]
+async fn test_playground_code_validation() -> Result<(), Error> {
+    let pool = reset()?;
+    let app = test_app_with_playground_storage(&pool, PlaygroundStorageBackend::Postgres).await?;
+    let service = test::init_service(app).await;
+    let mut client = TestHttpClient::new(service).await;
+    let code = json!({
+        "version": 2,
+        "mode": "typescript",
+        "head": "<meta name=\"viewport\" content=\"width=device-width\">",
+        "html": "<div id=\"root\"></div>",
+        "css": null,
+        "files": [
+            {"name": "index.ts", "content": "import { greet } from './greet.ts'; greet();"},
+            {"name": "greet.ts", "content": "export const greet = (): void => console.log('hi');"}
+        ],
+        "libraries": ["https://unpkg.com/lodash@4/lodash.min.js"],
+        "src": null
+    });
+    let save = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(code.clone())),
+        )
+        .await;
+    assert_eq!(save.status(), 201);
+    let json = read_json(save).await;
+    let id = json["id"].as_str().unwrap();
+    let load = client
+        .get(
+            &format!("/api/v1/play/{}", utf8_percent_encode(id, NON_ALPHANUMERIC)),
+            None,
+        )
+        .await;
+    assert_eq!(load.status(), 200);
+    assert_json_eq!(read_json(load).await, code);
+
+    let too_large = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "version": 2,
+                "files": [{"name": "index.js", "content": "a".repeat(256 * 1024 + 1)}],
+            }))),
+        )
+        .await;
+    assert_eq!(too_large.status(), StatusCode::BAD_REQUEST);
+    let json = read_json(too_large).await;
+    assert!(json["message"]
+        .as_str()
+        .unwrap()
+        .contains("must not be longer than 262144 chars"));
+
+    let insecure_library = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "version": 2,
+                "libraries": ["http://example.com/lib.js"],
+            }))),
+        )
+        .await;
+    assert_eq!(insecure_library.status(), StatusCode::BAD_REQUEST);
+
+    let unknown_mode = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "version": 2,
+                "mode": "cobol",
+            }))),
+        )
+        .await;
+    assert_eq!(unknown_mode.status(), StatusCode::BAD_REQUEST);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+#[stubr::mock(port = 4321)]
+async fn test_playground_key_rotation() -> Result<(), Error> {
+    let pool = reset()?;
+    let app = test_app_with_playground_storage(&pool, PlaygroundStorageBackend::Postgres).await?;
+    let service = test::init_service(app).await;
+    let mut client = TestHttpClient::new(service).await;
+    let mut conn = pool.get()?;
+    create_playground(
+        &mut conn,
+        PlaygroundInsert {
+            gist: "2decf6c462d9b4418f2".to_string(),
+            active: true,
+            ..Default::default()
+        },
+    )?;
+    let save = client
+        .post(
+            "/api/v1/play/",
+            None,
+            Some(crate::helpers::http_client::PostPayload::Json(json!({
+                "version": 2,
+                "html": "<h1>rotated</h1>",
+            }))),
+        )
+        .await;
+    assert_eq!(save.status(), 201);
+    let json = read_json(save).await;
+    let id = json["id"].as_str().unwrap();
+    assert!(id.starts_with("1."));
+    let load = client
+        .get(
+            &format!("/api/v1/play/{}", utf8_percent_encode(id, NON_ALPHANUMERIC)),
+            None,
+        )
+        .await;
+    assert_eq!(load.status(), 200);
+
+    let legacy_id = rumba::api::play::encrypt_with_key("2decf6c462d9b4418f2", 0)?;
+    assert!(!legacy_id.contains('.'));
+    let load = client
+        .get(
+            &format!(
+                "/api/v1/play/{}",
+                utf8_percent_encode(&legacy_id, NON_ALPHANUMERIC)
+            ),
+            None,
+        )
+        .await;
+    assert_eq!(load.status(), 200);
+
+    let unknown_key = client
+        .get(
+            &format!(
+                "/api/v1/play/{}",
+                utf8_percent_encode(&format!("7.{legacy_id}"), NON_ALPHANUMERIC)
+            ),
+            None,
+        )
+        .await;
+    assert_eq!(unknown_key.status(), StatusCode::BAD_REQUEST);
+
+    let keys = client
+        .get(
+            "/admin-api/playground/keys/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+        )
+        .await;
+    assert_eq!(keys.status(), StatusCode::OK);
+    assert_json_eq!(
+        read_json(keys).await,
+        json!([
+            {"version": 0, "playgrounds": 1, "configured": true, "current": false},
+            {"version": 1, "playgrounds": 1, "configured": true, "current": true}
+        ])
+    );
+    drop_stubr(stubr).await;
+    Ok(())
+}
@@ -1,17 +1,30 @@
+use std::time::Duration;
+
 use crate::helpers::app::{drop_stubr, test_app_only_search};
+use crate::helpers::db::reset;
 use crate::helpers::read_json;
 use actix_http::body::BoxBody;
+use actix_rt::time::{sleep, timeout};
 use actix_web::{http::header, test};
 use anyhow::Error;
 use stubr::{Config, Stubr};
 
 async fn do_request(path: &str) -> Result<actix_web::dev::ServiceResponse<BoxBody>, Error> {
+    do_request_with_stubs(path, "tests/test_specific_stubs/search", false).await
+}
+
+/// Stubs with an `expect` count are verified when `verify` is set.
+async fn do_request_with_stubs(
+    path: &str,
+    stubs: &str,
+    verify: bool,
+) -> Result<actix_web::dev::ServiceResponse<BoxBody>, Error> {
     let stubr = Stubr::start_blocking_with(
-        vec!["tests/test_specific_stubs/search"],
+        vec![stubs],
         Config {
             port: Some(4321),
             verbose: true,
-            verify: false,
+            verify,
             global_delay: None,
             latency: None,
         },
@@ -235,9 +248,11 @@ async fn test_suggestion() -> Result<(), Error> {
 
     let json = read_json(search).await;
     assert_eq!(json["metadata"]["took_ms"], 64_i64);
+    assert_eq!(json["suggestions"].as_array().unwrap().len(), 2);
     assert_eq!(json["suggestions"][0]["text"], "footer");
-    assert_eq!(json["suggestions"][0]["total"]["value"], 142_i64);
-    assert_eq!(json["suggestions"][0]["total"]["relation"], "eq");
+    assert_eq!(json["suggestions"][0]["total"]["value"], 1_i64);
+    assert_eq!(json["suggestions"][0]["total"]["relation"], "gte");
+    assert_eq!(json["suggestions"][1]["text"], "foo bar");
     Ok(())
 }
 
@@ -259,6 +274,134 @@ async fn test_no_results() -> Result<(), Error> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_facets() -> Result<(), Error> {
+    let search =
+        do_request("/api/v1/search?q=transform&locale=en-US&area=Web/CSS&status=deprecated")
+            .await?;
+
+    assert!(search.status().is_success());
+
+    let json = read_json(search).await;
+    assert_eq!(json["metadata"]["total"]["value"], 1_i64);
+    assert_eq!(json["documents"].as_array().unwrap().len(), 1);
+    assert_eq!(json["documents"][0]["slug"], "web/css/-webkit-transform-3d");
+    assert_eq!(
+        json["facets"]["area"],
+        serde_json::json!([
+            {"value": "Web/API", "count": 12},
+            {"value": "Web/CSS", "count": 41},
+            {"value": "Web/SVG", "count": 27}
+        ])
+    );
+    assert_eq!(json["facets"]["page_type"][0]["value"], "css-property");
+    assert_eq!(json["facets"]["page_type"][0]["count"], 30_i64);
+    assert_eq!(json["facets"]["status"][0]["value"], "deprecated");
+    assert_eq!(json["facets"]["status"][0]["count"], 3_i64);
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_status_invalid() -> Result<(), Error> {
+    let search = do_request("/api/v1/search?q=transform&status=obsolete").await?;
+
+    assert_eq!(search.status(), 400);
+
+    let json = read_json(search).await;
+    assert_eq!(json["errors"]["status"][0]["code"], "invalid");
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_autocomplete() -> Result<(), Error> {
+    let search = do_request("/api/v1/search/suggest?q=array.pr&locale=en-US&locale=de").await?;
+
+    assert!(search.status().is_success());
+    assert_eq!(
+        search.headers().get(header::CACHE_CONTROL).unwrap(),
+        "max-age=86400"
+    );
+
+    let json = read_json(search).await;
+    assert_eq!(json["suggestions"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        json["suggestions"][0]["mdn_url"],
+        "/en-US/docs/Web/JavaScript/Reference/Global_Objects/Array/prototype"
+    );
+    assert_eq!(json["suggestions"][0]["title"], "Array.prototype");
+    assert_eq!(json["suggestions"][1]["locale"], "de");
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_autocomplete_invalid() -> Result<(), Error> {
+    let search = do_request("/api/v1/search/suggest?q=%20").await?;
+    assert_eq!(search.status(), 400);
+    let json = read_json(search).await;
+    assert_eq!(json["errors"]["q"][0]["code"], "invalid");
+
+    let search = do_request("/api/v1/search/suggest?q=array&size=50").await?;
+    assert_eq!(search.status(), 400);
+    let json = read_json(search).await;
+    assert_eq!(json["errors"]["size"][0]["code"], "invalid");
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_mode_semantic_not_configured() -> Result<(), Error> {
+    let search = do_request("/api/v1/search?q=mozilla&mode=semantic").await?;
+    assert_eq!(search.status(), 501);
+
+    let search = do_request("/api/v1/search?q=mozilla&mode=hybrid").await?;
+    assert_eq!(search.status(), 501);
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_mode_invalid() -> Result<(), Error> {
+    let search = do_request("/api/v1/search?q=mozilla&mode=telepathic").await?;
+
+    assert_eq!(search.status(), 400);
+
+    let json = read_json(search).await;
+    assert_eq!(json["errors"]["mode"][0]["code"], "invalid");
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_search_analytics_report() -> Result<(), Error> {
+    reset()?;
+    let search = do_request("/api/v1/search?q=mozilla&locale=en-US").await?;
+    assert!(search.status().is_success());
+    let search = do_request("/api/v1/search?q=veryspecificquery&locale=en-US").await?;
+    assert!(search.status().is_success());
+
+    let app = test_app_only_search().await;
+    let service = test::init_service(app).await;
+    // Searches are recorded in the background.
+    let json = timeout(Duration::from_secs(10), async {
+        loop {
+            let request = test::TestRequest::get()
+                .uri("/admin-api/search/report/")
+                .insert_header((header::AUTHORIZATION, "Bearer TEST_TOKEN"))
+                .to_request();
+            let report = test::call_service(&service, request).await;
+            assert!(report.status().is_success());
+            let json = read_json(report).await;
+            if json["top_queries"].as_array().unwrap().len() == 2 {
+                return json;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await?;
+    assert_eq!(json["zero_result_queries"].as_array().unwrap().len(), 1);
+    assert_eq!(json["zero_result_queries"][0]["query"], "veryspecificquery");
+    assert_eq!(json["zero_result_queries"][0]["zero_results"], 1_i64);
+    assert_eq!(json["suggested_queries"].as_array().unwrap().len(), 0);
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_elastic_error() -> Result<(), Error> {
     let search = do_request("/api/v1/search?q=closedindex&locale=en-US").await?;
@@ -269,6 +412,54 @@ async fn test_elastic_error() -> Result<(), Error> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_elastic_retries() -> Result<(), Error> {
+    // The stub expects all three attempts.
+    let search = do_request_with_stubs(
+        "/api/v1/search?q=unavailableindex&locale=en-US",
+        "tests/test_specific_stubs/search",
+        true,
+    )
+    .await?;
+
+    assert!(search.status().is_server_error());
+    assert!(!search.headers().contains_key(header::CACHE_CONTROL));
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_stale_fallback() -> Result<(), Error> {
+    let search = do_request("/api/v1/search?q=staleindex&locale=en-US").await?;
+    assert!(search.status().is_success());
+    let json = read_json(search).await;
+    assert!(json["metadata"].get("degraded").is_none());
+
+    let search = do_request_with_stubs(
+        "/api/v1/search?q=staleindex&locale=en-US",
+        "tests/test_specific_stubs/search_unavailable",
+        false,
+    )
+    .await?;
+    assert!(search.status().is_success());
+    assert_eq!(
+        search.headers().get(header::CACHE_CONTROL).unwrap(),
+        "no-store"
+    );
+    let json = read_json(search).await;
+    assert_eq!(json["metadata"]["degraded"], "stale");
+    assert_eq!(json["metadata"]["took_ms"], 12_i64);
+
+    // Nothing to fall back to for other queries.
+    let search = do_request_with_stubs(
+        "/api/v1/search?q=otherindex&locale=en-US",
+        "tests/test_specific_stubs/search_unavailable",
+        false,
+    )
+    .await?;
+    assert!(search.status().is_server_error());
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_query_too_big() -> Result<(), Error> {
     let search = do_request(&format!(
@@ -285,3 +476,136 @@ async fn test_query_too_big() -> Result<(), Error> {
     assert_eq!(json["errors"]["q"][0]["code"], "invalid");
     Ok(())
 }
+
+#[actix_rt::test]
+async fn test_query_operators() -> Result<(), Error> {
+    let search = do_request(
+        "/api/v1/search?q=abort%20%22fetch%20signal%22%20-xhr%20in:Web/API%20locale:de&locale=en-US",
+    )
+    .await?;
+
+    assert!(search.status().is_success());
+
+    let json = read_json(search).await;
+    assert_eq!(json["metadata"]["total"]["value"], 1_i64);
+    assert_eq!(json["documents"][0]["slug"], "web/api/abortsignal");
+    assert_eq!(json["documents"][0]["locale"], "de");
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_query_syntax_error() -> Result<(), Error> {
+    for q in [
+        "abort%20%22fetch%20signal",
+        "abort%20title:",
+        "abort%20locale:xx",
+        "-xhr%20in:Web/API",
+    ] {
+        let search = do_request(&format!("/api/v1/search?q={q}")).await?;
+
+        assert_eq!(search.status(), 400);
+
+        let json = read_json(search).await;
+        assert_eq!(json["errors"]["q"][0]["code"], "invalid");
+    }
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_cursor() -> Result<(), Error> {
+    let search = do_request("/api/v1/search?q=promise&locale=en-US").await?;
+
+    assert!(search.status().is_success());
+
+    let json = read_json(search).await;
+    assert_eq!(json["documents"].as_array().unwrap().len(), 10);
+    assert_eq!(json["metadata"]["page"], 1);
+    let next = json["metadata"]["next"].as_str().unwrap().to_string();
+
+    let search = do_request(&format!(
+        "/api/v1/search?q=promise&locale=en-US&cursor={next}"
+    ))
+    .await?;
+
+    assert!(search.status().is_success());
+
+    let json = read_json(search).await;
+    assert_eq!(json["documents"].as_array().unwrap().len(), 3);
+    assert_eq!(
+        json["documents"][0]["slug"],
+        "web/javascript/reference/global_objects/promise/method_10"
+    );
+    assert_eq!(json["metadata"]["page"], 2);
+    assert!(json["metadata"]["next"].is_null());
+
+    // A cursor only works for the query it was issued for.
+    let search = do_request(&format!("/api/v1/search?q=promise&locale=de&cursor={next}")).await?;
+    assert_eq!(search.status(), 400);
+    let json = read_json(search).await;
+    assert_eq!(json["errors"]["cursor"][0]["code"], "invalid");
+
+    let search = do_request("/api/v1/search?q=promise&cursor=foo.bar").await?;
+    assert_eq!(search.status(), 400);
+
+    // Semantic and hybrid results can't be continued with a cursor.
+    for mode in ["semantic", "hybrid"] {
+        let search = do_request(&format!(
+            "/api/v1/search?q=promise&locale=en-US&mode={mode}&cursor={next}"
+        ))
+        .await?;
+        assert_eq!(search.status(), 400);
+        let json = read_json(search).await;
+        assert_eq!(
+            json["errors"]["cursor"][0]["message"],
+            "Cursors are only supported in lexical mode."
+        );
+    }
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_compare_ranking_profiles() -> Result<(), Error> {
+    let stubr = Stubr::start_blocking_with(
+        vec!["tests/test_specific_stubs/search"],
+        Config {
+            port: Some(4321),
+            verbose: true,
+            verify: false,
+            global_delay: None,
+            latency: None,
+        },
+    );
+    let app = test_app_only_search().await;
+    let service = test::init_service(app).await;
+    let request = test::TestRequest::get()
+        .uri("/admin-api/search/compare/?q=websocket&locale=en-US&b=fresh")
+        .insert_header((header::AUTHORIZATION, "Bearer TEST_TOKEN"))
+        .to_request();
+    let comparison = test::call_service(&service, request).await;
+    assert!(comparison.status().is_success());
+
+    let json = read_json(comparison).await;
+    assert_eq!(json["a"]["profile"], "default");
+    assert_eq!(json["b"]["profile"], "fresh");
+    assert_eq!(
+        json["a"]["documents"][0]["mdn_url"],
+        "/en-US/docs/Web/API/WebSocket"
+    );
+    assert_eq!(json["a"]["documents"][0]["other_rank"], 2);
+    assert_eq!(
+        json["b"]["documents"][0]["mdn_url"],
+        "/en-US/docs/Web/API/WebSocketStream"
+    );
+    assert_eq!(json["b"]["documents"][0]["other_rank"], 3);
+
+    let request = test::TestRequest::get()
+        .uri("/admin-api/search/compare/?q=websocket&b=stale")
+        .insert_header((header::AUTHORIZATION, "Bearer TEST_TOKEN"))
+        .to_request();
+    let comparison = test::call_service(&service, request).await;
+    assert_eq!(comparison.status(), 400);
+    let json = read_json(comparison).await;
+    assert_eq!(json["errors"]["b"][0]["code"], "invalid");
+    drop_stubr(stubr).await;
+    Ok(())
+}
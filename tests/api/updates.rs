@@ -17,7 +17,7 @@ use rumba::db::{self, Pool};
 use serde_json::{json, Value};
 use stubr::{Config, Stubr};
 
-macro_rules! test_setup {
+macro_rules! test_app {
     () => {{
         let stubr = Stubr::start_blocking_with(
             vec!["tests/stubs", "tests/test_specific_stubs/bcd_updates"],
@@ -29,10 +29,17 @@ macro_rules! test_setup {
                 verify: false,
             },
         );
-        let mut pool = reset()?;
+        let pool = reset()?;
         let app = test_app_with_login(&pool).await?;
         let service = test::init_service(app).await;
-        let mut logged_in_client = TestHttpClient::new(service).await;
+        let logged_in_client = TestHttpClient::new(service).await;
+        (logged_in_client, stubr, pool)
+    }};
+}
+
+macro_rules! test_setup {
+    () => {{
+        let (mut logged_in_client, stubr, mut pool) = test_app!();
         wait_for_updates_sync(&mut pool, &mut logged_in_client).await?;
         (logged_in_client, stubr)
     }};
@@ -92,7 +99,7 @@ async fn test_bcd_updates_basic_pagination() -> Result<(), Error> {
                 "engine_version": "107",
                 "events": null,
                 "release_date": "2022-11-15",
-                "release_notes": ""
+                "release_notes": "https://developer.mozilla.org/docs/Mozilla/Firefox/Releases/107"
               }
 
         ),
@@ -112,7 +119,11 @@ async fn test_bcd_updates_basic_pagination() -> Result<(), Error> {
                 "experimental": false,
                 "standard_track": true
               },
-              "engines": []
+              "engines": [],
+              "baseline": {
+                "status": "limited",
+                "date": null
+              }
             }
           }
     )));
@@ -129,7 +140,11 @@ async fn test_bcd_updates_basic_pagination() -> Result<(), Error> {
                 "experimental": false,
                 "standard_track": false
               },
-              "engines": []
+              "engines": [],
+              "baseline": {
+                "status": "limited",
+                "date": null
+              }
             }
           }
     )));
@@ -137,6 +152,115 @@ async fn test_bcd_updates_basic_pagination() -> Result<(), Error> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_bcd_updates_per_page_and_cursor() -> Result<(), Error> {
+    let (mut logged_in_client, stubr) = test_setup!();
+    let res = logged_in_client
+        .get("/api/v2/updates/?per_page=20", None)
+        .await;
+    let json = read_json(res).await;
+    assert_eq!(json["data"].as_array().unwrap().len(), 9);
+    assert_eq!(json["total"], 9);
+    assert_eq!(json["last"], 1);
+    assert_eq!(json["next"], Value::Null);
+    let all = json["data"].as_array().unwrap().clone();
+
+    let mut walked = Vec::new();
+    let mut url = "/api/v2/updates/?per_page=4".to_string();
+    loop {
+        let res = logged_in_client.get(&url, None).await;
+        let json = read_json(res).await;
+        assert_eq!(json["total"], 9);
+        assert_eq!(json["last"], 3);
+        walked.extend(json["data"].as_array().unwrap().clone());
+        match json["next"].as_str() {
+            Some(cursor) => url = format!("/api/v2/updates/?per_page=4&cursor={cursor}"),
+            None => break,
+        }
+    }
+    assert_eq!(walked, all);
+
+    let res = logged_in_client
+        .get("/api/v2/updates/?per_page=101", None)
+        .await;
+    assert_eq!(res.status(), 400);
+    let res = logged_in_client
+        .get("/api/v2/updates/?cursor=garbage", None)
+        .await;
+    assert_eq!(res.status(), 400);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_bcd_updates_feeds() -> Result<(), Error> {
+    let (mut logged_in_client, stubr) = test_setup!();
+    wait_for_sync_run(&mut logged_in_client).await?;
+
+    let res = logged_in_client
+        .get("/api/v2/updates/feed.atom?browsers=firefox", None)
+        .await;
+    assert_eq!(res.status(), 200);
+    let headers = res.headers();
+    assert_eq!(
+        headers.get("Content-Type").unwrap(),
+        "application/atom+xml; charset=utf-8"
+    );
+    let etag = headers.get("ETag").unwrap().to_str()?.to_string();
+    let last_modified = headers.get("Last-Modified").unwrap().to_str()?.to_string();
+    let body = String::from_utf8(test::read_body(res).await.to_vec())?;
+    assert!(body.contains(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#));
+    assert_eq!(body.matches("<entry>").count(), 3);
+    assert!(body.contains("<title>Firefox 107</title>"));
+    assert!(!body.contains("Chrome"));
+
+    let res = logged_in_client.get("/api/v2/updates/feed.rss", None).await;
+    assert_eq!(res.status(), 200);
+    let body = String::from_utf8(test::read_body(res).await.to_vec())?;
+    assert!(body.contains(r#"<rss version="2.0""#));
+    assert_eq!(body.matches("<item>").count(), 9);
+
+    let res = logged_in_client
+        .get("/api/v2/updates/feed.json?browsers=chrome,edge", None)
+        .await;
+    assert_eq!(
+        res.headers().get("Content-Type").unwrap(),
+        "application/feed+json"
+    );
+    let feed = read_json(res).await;
+    assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+    let items = feed["items"].as_array().unwrap();
+    assert_eq!(items.len(), 6);
+    assert!(items[0]["content_html"]
+        .as_str()
+        .unwrap()
+        .starts_with("<h2>"));
+
+    let res = logged_in_client
+        .get(
+            "/api/v2/updates/feed.atom?browsers=firefox",
+            Some(vec![("If-None-Match", &etag)]),
+        )
+        .await;
+    assert_eq!(res.status(), 304);
+    let res = logged_in_client
+        .get(
+            "/api/v2/updates/feed.rss",
+            Some(vec![("If-Modified-Since", &last_modified)]),
+        )
+        .await;
+    assert_eq!(res.status(), 304);
+    let res = logged_in_client
+        .get(
+            "/api/v2/updates/feed.rss",
+            Some(vec![("If-None-Match", "\"bcd-sync-0\"")]),
+        )
+        .await;
+    assert_eq!(res.status(), 200);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
 #[actix_rt::test]
 async fn test_bcd_updates_filter_by_collections() -> Result<(), Error> {
     let (mut logged_in_client, stubr) = test_setup!();
@@ -163,6 +287,849 @@ async fn test_bcd_updates_filter_by_collections() -> Result<(), Error> {
     Ok(())
 }
 
+#[actix_rt::test]
+async fn test_bcd_sync_runs() -> Result<(), Error> {
+    let (mut logged_in_client, stubr) = test_setup!();
+    let run = wait_for_sync_run(&mut logged_in_client).await?;
+    assert_eq!(run["status"], "succeeded");
+    assert!(run["inserted"].as_i64().unwrap() > 0);
+    assert_eq!(run["removed"], 0);
+    assert!(run["finished_at"].is_string());
+    assert_eq!(run["errors"], json!([]));
+    assert!(run["report"]["updates"]["inserted"].as_i64().unwrap() > 0);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_validate_bcd_updates() -> Result<(), Error> {
+    let (mut client, stubr, _pool) = test_app!();
+
+    let res = client
+        .post(
+            "/admin-api/v2/updates/validate/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            None,
+        )
+        .await;
+    let summary = read_json(res).await;
+    assert_eq!(summary["valid"], true);
+    assert_eq!(summary["browsers"], 3);
+    assert_eq!(summary["releases"], 9);
+    assert_eq!(summary["features"], 259);
+    assert_eq!(summary["updates"], 413);
+
+    let feed = json!({
+        "browsers": {
+            "firefox": {
+                "name": "Firefox",
+                "accepts_flags": true,
+                "accepts_webextensions": true,
+                "releases": {
+                    "107": {"engine": "Gecko", "engine_version": "107", "release_date": "2022-11-15", "status": "retired"},
+                    "108": {"engine": "Gecko", "engine_version": "108", "release_date": "2022-13-45", "status": "retired"},
+                    "200": {"engine": "Gecko", "status": "planned"}
+                }
+            },
+            "broken": {"name": "Broken"}
+        },
+        "features": [
+            {"path": "api.Foo", "source_file": "api/Foo.json", "spec_url": ["https://a", "https://b"]},
+            {"path": "api.Bar", "mdn_url": null}
+        ],
+        "added_removed": [
+            [
+                {"browser": "firefox", "version": "107", "release_date": "2022-11-15"},
+                {"added": ["api.Foo", {"path": "api.Bar", "engines": ["gecko"]}], "removed": []}
+            ],
+            [
+                {"browser": "firefox", "version": "108", "release_date": "2022-12-13"},
+                {"added": ["api.Foo"], "removed": []}
+            ]
+        ]
+    });
+    let res = client
+        .post(
+            "/admin-api/v2/updates/validate/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            Some(PostPayload::Json(feed)),
+        )
+        .await;
+    let summary = read_json(res).await;
+    assert_eq!(summary["valid"], false);
+    assert_eq!(summary["browsers"], 1);
+    assert_eq!(summary["releases"], 1);
+    assert_eq!(summary["features"], 1);
+    assert_eq!(summary["updates"], 1);
+    let mut records: Vec<&str> = summary["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["record"].as_str().unwrap())
+        .collect();
+    records.sort();
+    assert_eq!(
+        records,
+        vec![
+            "added_removed[0].api.Bar",
+            "added_removed[1]",
+            "browsers.broken",
+            "browsers.firefox.108",
+            "features.api.Bar",
+        ]
+    );
+
+    let res = client
+        .post(
+            "/admin-api/v2/updates/validate/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            Some(PostPayload::Json(json!({"browsers": []}))),
+        )
+        .await;
+    let summary = read_json(res).await;
+    assert_eq!(summary["valid"], false);
+    assert_eq!(summary["errors"][0]["record"], "feed");
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_bcd_updates_uploaded_feed() -> Result<(), Error> {
+    let (mut client, stubr, _pool) = test_app!();
+    let feed = json!({
+        "browsers": {
+            "firefox": {
+                "name": "Firefox",
+                "accepts_flags": true,
+                "accepts_webextensions": true,
+                "releases": {
+                    "107": {"engine": "Gecko", "engine_version": "107", "release_date": "2022-11-15", "status": "retired"}
+                }
+            }
+        },
+        "features": [
+            {"path": "api.Foo", "source_file": "api/Foo.json", "spec_url": "https://a"}
+        ],
+        "added_removed": [
+            [
+                {"browser": "firefox", "version": "107", "release_date": "2022-11-15"},
+                {"added": ["api.Foo"], "removed": []}
+            ]
+        ]
+    });
+    let run = sync_feed(&mut client, feed.clone()).await?;
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(run["report"]["updates"]["inserted"], 1);
+
+    let res = client.get("/api/v2/updates/", None).await;
+    let json = read_json(res).await;
+    let data = json["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["browser"], "firefox");
+    assert_eq!(data[0]["version"], "107");
+
+    // Uploads only upsert, unless they replace the stored events.
+    let mut partial = feed.clone();
+    partial["added_removed"] = json!([]);
+    let run = sync_feed(&mut client, partial.clone()).await?;
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(run["report"]["updates"]["removed"], 0);
+
+    // A feed with invalid records keeps the events it is missing, even when replacing.
+    let mut invalid = feed.clone();
+    invalid["added_removed"][0][1]["added"] = json!(["api.Missing"]);
+    let run = replace_feed(&mut client, invalid).await?;
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(run["errors"].as_array().unwrap().len(), 1);
+    assert_eq!(run["report"]["updates"]["removed"], 0);
+    let res = client.get("/api/v2/updates/", None).await;
+    let json = read_json(res).await;
+    assert_eq!(json["data"].as_array().unwrap().len(), 1);
+
+    let run = replace_feed(&mut client, partial).await?;
+    assert_eq!(run["report"]["updates"]["removed"], 1);
+    let res = client.get("/api/v2/updates/", None).await;
+    let json = read_json(res).await;
+    assert_eq!(json["data"].as_array().unwrap().len(), 0);
+
+    let res = client
+        .post(
+            "/admin-api/v2/updates/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            Some(PostPayload::Json(json!({"features": []}))),
+        )
+        .await;
+    assert_eq!(res.status(), 400);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_bcd_updates_support_changes() -> Result<(), Error> {
+    let (mut client, stubr, _pool) = test_app!();
+    let feed = json!({
+        "browsers": {
+            "firefox": {
+                "name": "Firefox",
+                "accepts_flags": true,
+                "accepts_webextensions": true,
+                "releases": {
+                    "107": {"engine": "Gecko", "engine_version": "107", "release_date": "2022-11-15", "status": "retired"},
+                    "108": {"engine": "Gecko", "engine_version": "108", "release_date": "2022-12-13", "status": "retired"}
+                }
+            }
+        },
+        "features": [
+            {"path": "api.Foo", "source_file": "api/Foo.json"},
+            {"path": "api.Bar", "source_file": "api/Bar.json"}
+        ],
+        "added_removed": [
+            [
+                {"browser": "firefox", "version": "107", "release_date": "2022-11-15"},
+                {"added": ["api.Foo"], "removed": []}
+            ],
+            [
+                {"browser": "firefox", "version": "108", "release_date": "2022-12-13"},
+                {
+                    "changed": [
+                        {
+                            "path": "api.Bar",
+                            "before": {"version_added": "100", "flags": [{"type": "preference", "name": "dom.bar.enabled"}]},
+                            "after": {"version_added": "108"}
+                        },
+                        {
+                            "path": "api.Foo",
+                            "type": "version_corrected",
+                            "before": {"version_added": "107"},
+                            "after": {"version_added": "106"}
+                        }
+                    ]
+                }
+            ]
+        ]
+    });
+    let run = sync_feed(&mut client, feed).await?;
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(run["report"]["updates"]["inserted"], 3);
+
+    let res = client
+        .get("/api/v2/updates/?event_type=removed_flag", None)
+        .await;
+    let json = read_json(res).await;
+    let data = json["data"].as_array().unwrap();
+    assert_eq!(data.len(), 1);
+    assert_eq!(data[0]["version"], "108");
+    assert_eq!(data[0]["events"]["added"], json!([]));
+    let changed = data[0]["events"]["changed"].as_array().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0]["event_type"], "removed_flag");
+    assert_eq!(changed[0]["path"], "api.Bar");
+    assert_eq!(changed[0]["before"]["flags"][0]["name"], "dom.bar.enabled");
+    assert_eq!(changed[0]["after"], json!({"version_added": "108"}));
+
+    let res = client
+        .get(
+            "/api/v2/updates/?event_type=added_stable,version_corrected",
+            None,
+        )
+        .await;
+    let json = read_json(res).await;
+    let data = json["data"].as_array().unwrap();
+    assert_eq!(data.len(), 2);
+    assert_eq!(
+        data[0]["events"]["changed"][0]["event_type"],
+        "version_corrected"
+    );
+    assert_eq!(data[1]["events"]["added"][0]["path"], "api.Foo");
+
+    let res = client
+        .get("/api/v2/updates/?event_type=renamed", None)
+        .await;
+    assert_eq!(res.status(), 400);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_feature_timeline() -> Result<(), Error> {
+    let (mut client, stubr, _pool) = test_app!();
+    let release = |engine: &str, date: &str| json!({"engine": engine, "engine_version": "1", "release_date": date, "status": "retired"});
+    let feed = json!({
+        "browsers": {
+            "chrome": {"name": "Chrome", "accepts_flags": true, "accepts_webextensions": true,
+                       "releases": {"89": release("Blink", "2021-03-02")}},
+            "chrome_android": {"name": "Chrome Android", "accepts_flags": false, "accepts_webextensions": false,
+                               "releases": {"89": release("Blink", "2021-03-02")}},
+            "edge": {"name": "Edge", "accepts_flags": true, "accepts_webextensions": true,
+                     "releases": {"89": release("Blink", "2021-03-04")}},
+            "firefox": {"name": "Firefox", "accepts_flags": true, "accepts_webextensions": true,
+                        "releases": {"96": release("Gecko", "2022-01-11"), "97": release("Gecko", "2022-02-08")}},
+            "firefox_android": {"name": "Firefox Android", "accepts_flags": false, "accepts_webextensions": true,
+                                "releases": {"96": release("Gecko", "2022-01-11")}},
+            "safari": {"name": "Safari", "accepts_flags": true, "accepts_webextensions": true,
+                       "releases": {"15.4": release("WebKit", "2022-03-14")}},
+            "safari_ios": {"name": "Safari on iOS", "accepts_flags": true, "accepts_webextensions": true,
+                           "releases": {"15.4": release("WebKit", "2022-03-14")}}
+        },
+        "features": [
+            {"path": "api.Navigator.share", "source_file": "api/Navigator.json"},
+            {"path": "api.Navigator.share.data", "source_file": "api/Navigator.json"},
+            {"path": "api.Navigator.shareable", "source_file": "api/Navigator.json"}
+        ],
+        "added_removed": [
+            [{"browser": "chrome", "version": "89"}, {"added": ["api.Navigator.share", "api.Navigator.shareable"]}],
+            [{"browser": "chrome_android", "version": "89"}, {"added": ["api.Navigator.share"]}],
+            [{"browser": "edge", "version": "89"}, {"added": ["api.Navigator.share"]}],
+            [{"browser": "firefox_android", "version": "96"}, {"added": ["api.Navigator.share"]}],
+            [{"browser": "safari_ios", "version": "15.4"}, {"added": ["api.Navigator.share"]}],
+            [{"browser": "firefox", "version": "96"}, {"changed": [{
+                "path": "api.Navigator.share",
+                "before": {"version_added": "71", "flags": [{"type": "preference", "name": "dom.webshare.enabled"}]},
+                "after": {"version_added": "96"}
+            }]}],
+            [{"browser": "firefox", "version": "97"}, {"added": ["api.Navigator.share.data"]}],
+            [{"browser": "safari", "version": "15.4"}, {"added": ["api.Navigator.share"]}]
+        ]
+    });
+    let run = sync_feed(&mut client, feed).await?;
+    assert_eq!(run["status"], "succeeded");
+
+    let res = client
+        .get("/api/v2/features/api.Navigator.share/timeline", None)
+        .await;
+    assert_eq!(res.status(), 200);
+    let timeline = read_json(res).await;
+    assert_eq!(timeline["feature"]["path"], "api.Navigator.share");
+    assert_eq!(timeline["feature"]["baseline_date"], "2022-03-14");
+    let events: Vec<(&str, &str)> = timeline["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["browser"].as_str().unwrap(),
+                e["event_type"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            ("chrome", "added_stable"),
+            ("chrome_android", "added_stable"),
+            ("edge", "added_stable"),
+            ("firefox", "removed_flag"),
+            ("firefox_android", "added_stable"),
+            ("safari", "added_stable"),
+            ("safari_ios", "added_stable")
+        ]
+    );
+    assert_eq!(timeline["events"][3]["version"], "96");
+    assert_eq!(
+        timeline["events"][3]["before"]["flags"][0]["name"],
+        "dom.webshare.enabled"
+    );
+
+    let res = client
+        .get(
+            "/api/v2/features/api.Navigator.share/timeline?subfeatures=true",
+            None,
+        )
+        .await;
+    let timeline = read_json(res).await;
+    assert_eq!(timeline["feature"]["baseline_date"], "2022-03-14");
+    let paths: Vec<&str> = timeline["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "api.Navigator.share",
+            "api.Navigator.share",
+            "api.Navigator.share",
+            "api.Navigator.share",
+            "api.Navigator.share",
+            "api.Navigator.share.data",
+            "api.Navigator.share",
+            "api.Navigator.share"
+        ]
+    );
+
+    let res = client
+        .get("/api/v2/features/api.Navigator.shareable/timeline", None)
+        .await;
+    let timeline = read_json(res).await;
+    assert_eq!(timeline["feature"]["baseline_date"], Value::Null);
+
+    let res = client.get("/api/v2/features/api.Nope/timeline", None).await;
+    assert_eq!(res.status(), 404);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_bcd_updates_baseline() -> Result<(), Error> {
+    let (mut client, stubr, _pool) = test_app!();
+    let today = Utc::now().naive_utc().date();
+    let days_ago = |days: i64| (today - chrono::Duration::days(days)).to_string();
+    let release = |engine: &str, date: &str| json!({"engine": engine, "engine_version": "1", "release_date": date, "status": "retired"});
+    let browser = |name: &str, releases: Value| json!({"name": name, "accepts_flags": true, "accepts_webextensions": true, "releases": releases});
+    let feed = json!({
+        "browsers": {
+            "chrome": browser("Chrome", json!({
+                "90": release("Blink", "2021-04-14"),
+                "120": release("Blink", &days_ago(200))
+            })),
+            "chrome_android": browser("Chrome Android", json!({
+                "90": release("Blink", "2021-04-14"),
+                "120": release("Blink", &days_ago(200))
+            })),
+            "edge": browser("Edge", json!({
+                "90": release("Blink", "2021-04-15"),
+                "120": release("Blink", &days_ago(190))
+            })),
+            "firefox": browser("Firefox", json!({
+                "90": release("Gecko", "2021-07-13"),
+                "121": release("Gecko", &days_ago(150))
+            })),
+            "firefox_android": browser("Firefox Android", json!({
+                "90": release("Gecko", "2021-07-13"),
+                "121": release("Gecko", &days_ago(150))
+            })),
+            "safari": browser("Safari", json!({
+                "15": release("WebKit", "2021-09-20"),
+                "17": release("WebKit", &days_ago(60))
+            })),
+            "safari_ios": browser("Safari on iOS", json!({
+                "15": release("WebKit", "2021-09-20"),
+                "17": release("WebKit", &days_ago(60))
+            }))
+        },
+        "features": [
+            {"path": "api.A", "source_file": "api/A.json"},
+            {"path": "api.B", "source_file": "api/B.json"},
+            {"path": "api.C", "source_file": "api/C.json"}
+        ],
+        "added_removed": [
+            [{"browser": "chrome", "version": "90"}, {"added": ["api.A", "api.C"]}],
+            [{"browser": "chrome_android", "version": "90"}, {"added": ["api.A", "api.C"]}],
+            [{"browser": "edge", "version": "90"}, {"added": ["api.A", "api.C"]}],
+            [{"browser": "firefox", "version": "90"}, {"added": ["api.A"]}],
+            [{"browser": "firefox_android", "version": "90"}, {"added": ["api.A"]}],
+            [{"browser": "safari", "version": "15"}, {"added": ["api.A", "api.C"]}],
+            [{"browser": "safari_ios", "version": "15"}, {"added": ["api.A"]}],
+            [{"browser": "chrome", "version": "120"}, {"added": ["api.B"]}],
+            [{"browser": "chrome_android", "version": "120"}, {"added": ["api.B"]}],
+            [{"browser": "edge", "version": "120"}, {"added": ["api.B"]}],
+            [{"browser": "firefox", "version": "121"}, {"added": ["api.B"]}],
+            [{"browser": "firefox_android", "version": "121"}, {"added": ["api.B"]}],
+            [{"browser": "safari", "version": "17"}, {"added": ["api.B"]}],
+            [{"browser": "safari_ios", "version": "17"}, {"added": ["api.B"]}]
+        ]
+    });
+    let run = sync_feed(&mut client, feed).await?;
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(run["report"]["baseline"]["updated"], 2);
+
+    let res = client
+        .get("/api/v2/updates/?baseline=newly&per_page=20", None)
+        .await;
+    let json = read_json(res).await;
+    let data = json["data"].as_array().unwrap();
+    assert_eq!(data.len(), 7);
+    for release in data {
+        let added = release["events"]["added"].as_array().unwrap();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0]["path"], "api.B");
+        assert_eq!(
+            added[0]["compat"]["baseline"],
+            json!({"status": "newly", "date": days_ago(60)})
+        );
+    }
+
+    let res = client
+        .get("/api/v2/updates/?baseline=widely,limited&per_page=20", None)
+        .await;
+    let json = read_json(res).await;
+    let mut statuses: Vec<(String, String)> = json["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|release| release["events"]["added"].as_array().unwrap().clone())
+        .map(|e| {
+            (
+                e["path"].as_str().unwrap().to_string(),
+                e["compat"]["baseline"]["status"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+            )
+        })
+        .collect();
+    statuses.sort();
+    statuses.dedup();
+    assert_eq!(
+        statuses,
+        vec![
+            ("api.A".to_string(), "widely".to_string()),
+            ("api.C".to_string(), "limited".to_string())
+        ]
+    );
+
+    let res = client.get("/api/v2/features/api.A/timeline", None).await;
+    let timeline = read_json(res).await;
+    assert_eq!(timeline["feature"]["baseline_status"], "widely");
+    assert_eq!(timeline["feature"]["baseline_date"], "2021-09-20");
+
+    let res = client
+        .get("/api/v2/updates/?baseline=everywhere", None)
+        .await;
+    assert_eq!(res.status(), 400);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_browsers_and_releases() -> Result<(), Error> {
+    let (mut logged_in_client, stubr) = test_setup!();
+    let res = logged_in_client.get("/api/v2/browsers/", None).await;
+    assert_eq!(res.status(), 200);
+    let json = read_json(res).await;
+    let names: Vec<_> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["chrome", "edge", "firefox"]);
+    assert_eq!(json[2]["display_name"], "Firefox");
+    assert_eq!(json[2]["preview_name"], "Nightly");
+    assert_eq!(json[2]["current"]["version"], "109");
+    assert_eq!(json[2]["current"]["engine"], "Gecko");
+
+    let res = logged_in_client
+        .get("/api/v2/browsers/?engine=Gecko", None)
+        .await;
+    let json = read_json(res).await;
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["name"], "firefox");
+
+    let res = logged_in_client
+        .get("/api/v2/browsers/firefox/releases/", None)
+        .await;
+    let json = read_json(res).await;
+    let versions: Vec<_> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["version"].as_str().unwrap())
+        .collect();
+    assert_eq!(versions, vec!["109", "108", "107"]);
+    assert_eq!(
+        json[2]["release_notes"],
+        "https://developer.mozilla.org/docs/Mozilla/Firefox/Releases/107"
+    );
+
+    let res = logged_in_client
+        .get("/api/v2/browsers/firefox/releases/?status=retired", None)
+        .await;
+    let json = read_json(res).await;
+    assert_eq!(json.as_array().unwrap().len(), 2);
+    assert!(json
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|r| r["status"] == "retired"));
+
+    let res = logged_in_client
+        .get(
+            "/api/v2/browsers/firefox/releases/?status=beta,nightly",
+            None,
+        )
+        .await;
+    assert_eq!(read_json(res).await, json!([]));
+    let res = logged_in_client
+        .get("/api/v2/browsers/firefox/releases/?status=stale", None)
+        .await;
+    assert_eq!(res.status(), 400);
+    let res = logged_in_client
+        .get("/api/v2/browsers/netscape/releases/", None)
+        .await;
+    assert_eq!(res.status(), 404);
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_bcd_notifications() -> Result<(), Error> {
+    use diesel::{ExpressionMethods, QueryDsl};
+    use rumba::db::schema::{bcd_notifications, documents};
+
+    let (mut client, stubr, pool) = test_app!();
+    let res = client.get("/api/v2/collections/", None).await;
+    let collections = read_json(res).await;
+    let default_id = collections[0]["id"].as_str().unwrap().to_string();
+    client
+        .post(
+            &format!("/api/v2/collections/{default_id}/items/"),
+            None,
+            Some(PostPayload::Json(json!({
+                "url": "/en-US/docs/Web/API/CaptureController",
+                "title": "CaptureController",
+                "notes": ""
+            }))),
+        )
+        .await;
+    diesel::update(documents::table)
+        .set(documents::paths.eq(vec![Some("api.CaptureController".to_string())]))
+        .execute(&mut pool.get()?)?;
+    let res = client
+        .post(
+            "/api/v1/plus/settings/",
+            None,
+            Some(PostPayload::Json(json!({"bcd_digest": true}))),
+        )
+        .await;
+    assert_eq!(res.status(), 201);
+
+    let feed = json!({
+        "browsers": {
+            "firefox": {
+                "name": "Firefox",
+                "accepts_flags": true,
+                "accepts_webextensions": true,
+                "releases": {
+                    "107": {"engine": "Gecko", "engine_version": "107", "release_date": "2022-11-15", "status": "retired"},
+                    "108": {"engine": "Gecko", "engine_version": "108", "release_date": "2022-12-13", "status": "retired"}
+                }
+            }
+        },
+        "features": [
+            {"path": "api.CaptureController", "source_file": "api/CaptureController.json", "mdn_url": "/en-US/docs/Web/API/CaptureController"},
+            {"path": "api.CaptureController.setFocusBehavior", "source_file": "api/CaptureController.json"},
+            {"path": "api.CaptureControllerX", "source_file": "api/CaptureControllerX.json"}
+        ],
+        "added_removed": [
+            [
+                {"browser": "firefox", "version": "107", "release_date": "2022-11-15"},
+                {"added": ["api.CaptureController", "api.CaptureControllerX"], "removed": []}
+            ],
+            [
+                {"browser": "firefox", "version": "108", "release_date": "2022-12-13"},
+                {"added": ["api.CaptureController.setFocusBehavior"], "removed": []}
+            ]
+        ]
+    });
+    let run = sync_feed(&mut client, feed.clone()).await?;
+    assert_eq!(run["status"], "succeeded");
+    assert_eq!(run["report"]["notifications"], 2);
+
+    let res = client.get("/api/v2/notifications/", None).await;
+    let json = read_json(res).await;
+    assert_eq!(json["unread"], 2);
+    let items = json["items"].as_array().unwrap();
+    let mut paths: Vec<_> = items.iter().map(|n| n["path"].as_str().unwrap()).collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec![
+            "api.CaptureController",
+            "api.CaptureController.setFocusBehavior"
+        ]
+    );
+    assert_eq!(items[0]["document"]["title"], "CaptureController");
+    assert_eq!(items[0]["event_type"], "added_stable");
+    assert_eq!(items[0]["read_at"], Value::Null);
+
+    // Synchronizing the same events again does not notify again.
+    let res = client
+        .post(
+            "/admin-api/v2/updates/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            Some(PostPayload::Json(feed)),
+        )
+        .await;
+    assert_eq!(res.status(), 202);
+    let run = timeout(Duration::from_millis(10_000), async {
+        loop {
+            let res = client
+                .get(
+                    "/admin-api/v2/updates/runs/",
+                    Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+                )
+                .await;
+            let runs = read_json(res).await;
+            if runs.as_array().unwrap().len() == 2 && runs[0]["status"] != "running" {
+                return runs[0].clone();
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await?;
+    assert_eq!(run["report"]["notifications"], 0);
+
+    let res = client
+        .post(
+            "/admin-api/v2/notifications/digest/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            None,
+        )
+        .await;
+    assert_eq!(res.status(), 202);
+    timeout(Duration::from_millis(10_000), async {
+        loop {
+            let mailed: i64 = bcd_notifications::table
+                .filter(bcd_notifications::mailed_at.is_not_null())
+                .count()
+                .get_result(&mut pool.get()?)?;
+            if mailed == 2 {
+                return Ok::<(), Error>(());
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await??;
+
+    let id = items[0]["id"].as_str().unwrap();
+    let res = client
+        .post(&format!("/api/v2/notifications/{id}/read/"), None, None)
+        .await;
+    assert_eq!(read_json(res).await["updated"], 1);
+    let res = client.get("/api/v2/notifications/?unread=true", None).await;
+    let json = read_json(res).await;
+    assert_eq!(json["unread"], 1);
+    assert_eq!(json["items"].as_array().unwrap().len(), 1);
+    assert_ne!(json["items"][0]["id"], id);
+
+    let res = client
+        .post(
+            "/api/v2/notifications/read/",
+            None,
+            Some(PostPayload::Json(json!({}))),
+        )
+        .await;
+    assert_eq!(read_json(res).await["updated"], 1);
+    let res = client.get("/api/v2/notifications/", None).await;
+    let json = read_json(res).await;
+    assert_eq!(json["unread"], 0);
+    assert!(json["items"][0]["read_at"].is_string());
+    drop_stubr(stubr).await;
+    Ok(())
+}
+
+async fn sync_feed(
+    client: &mut TestHttpClient<
+        impl Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse<
+                actix_http::body::EitherBody<actix_http::body::BoxBody>,
+            >,
+            Error = actix_web::Error,
+        >,
+    >,
+    feed: Value,
+) -> Result<Value, Error> {
+    post_feed(client, "/admin-api/v2/updates/", feed).await
+}
+
+async fn replace_feed(
+    client: &mut TestHttpClient<
+        impl Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse<
+                actix_http::body::EitherBody<actix_http::body::BoxBody>,
+            >,
+            Error = actix_web::Error,
+        >,
+    >,
+    feed: Value,
+) -> Result<Value, Error> {
+    post_feed(client, "/admin-api/v2/updates/?replace=true", feed).await
+}
+
+async fn post_feed(
+    client: &mut TestHttpClient<
+        impl Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse<
+                actix_http::body::EitherBody<actix_http::body::BoxBody>,
+            >,
+            Error = actix_web::Error,
+        >,
+    >,
+    path: &str,
+    feed: Value,
+) -> Result<Value, Error> {
+    let res = client
+        .get(
+            "/admin-api/v2/updates/runs/",
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+        )
+        .await;
+    let previous = read_json(res).await[0]["id"].clone();
+    let res = client
+        .post(
+            path,
+            Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+            Some(PostPayload::Json(feed)),
+        )
+        .await;
+    assert_eq!(res.status(), 202);
+    wait_for_sync_run_after(client, &previous).await
+}
+
+async fn wait_for_sync_run(
+    client: &mut TestHttpClient<
+        impl Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse<
+                actix_http::body::EitherBody<actix_http::body::BoxBody>,
+            >,
+            Error = actix_web::Error,
+        >,
+    >,
+) -> Result<Value, Error> {
+    wait_for_sync_run_after(client, &Value::Null).await
+}
+
+/// Waits for a finished run other than `previous`.
+async fn wait_for_sync_run_after(
+    client: &mut TestHttpClient<
+        impl Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse<
+                actix_http::body::EitherBody<actix_http::body::BoxBody>,
+            >,
+            Error = actix_web::Error,
+        >,
+    >,
+    previous: &Value,
+) -> Result<Value, Error> {
+    timeout(Duration::from_millis(10_000), async {
+        loop {
+            let res = client
+                .get(
+                    "/admin-api/v2/updates/runs/",
+                    Some(vec![("Authorization", "Bearer TEST_TOKEN")]),
+                )
+                .await;
+            let runs = read_json(res).await;
+            if runs[0]["status"].is_string()
+                && runs[0]["status"] != "running"
+                && runs[0]["id"] != *previous
+            {
+                return runs[0].clone();
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .map_err(|_| anyhow!("Sync run not finished after 10 seconds"))
+}
+
 async fn wait_for_updates_sync(
     pool: &mut Pool,
     logged_in_client: &mut TestHttpClient<
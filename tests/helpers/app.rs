@@ -21,15 +21,17 @@ use octocrab::OctocrabBuilder;
 use reqwest::Client;
 use rumba::add_services;
 use rumba::api::error::error_handler;
+use rumba::api::mail::Mailer;
 use rumba::api::play::{GithubFlagsClient, GithubGistClient};
+use rumba::api::play_storage::PlaygroundStorage;
 use rumba::db::{Pool, SupaPool};
 use rumba::fxa::LoginManager;
-use rumba::settings::SETTINGS;
+use rumba::settings::{PlaygroundStorageBackend, SETTINGS};
 use slog::{slog_o, Drain};
 use std::time::Duration;
 use stubr::{Config, Stubr};
 
-use super::db::reset;
+use super::db::{get_pool, reset};
 use super::http_client::TestHttpClient;
 use super::RumbaTestResponse;
 
@@ -60,10 +62,28 @@ pub async fn test_app_with_login(
             InitError = (),
         >,
     >,
+> {
+    test_app_with_playground_storage(pool, PlaygroundStorageBackend::Gist).await
+}
+
+pub async fn test_app_with_playground_storage(
+    pool: &Pool,
+    playground_storage_backend: PlaygroundStorageBackend,
+) -> anyhow::Result<
+    App<
+        impl ServiceFactory<
+            ServiceRequest,
+            Response = RumbaTestResponse,
+            Error = Error,
+            Config = (),
+            InitError = (),
+        >,
+    >,
 > {
     let pool = Data::new(pool.clone());
     let login_manager = Data::new(LoginManager::init().await?);
     let client = Data::new(Client::new());
+    let mailer = Data::new(Mailer::new(SETTINGS.notifications.as_ref(), &client));
     init_logging();
     let arbiter = Arbiter::new();
     let arbiter_handle = Data::new(arbiter.handle());
@@ -74,6 +94,11 @@ pub async fn test_app_with_login(
             .unwrap()
             .build()?,
     )));
+    let playground_storage = Data::new(PlaygroundStorage::new(
+        Some(playground_storage_backend),
+        &pool,
+        github_gist_client.0.as_ref(),
+    ));
     let github_flags_client = Data::new(GithubFlagsClient(Some(
         OctocrabBuilder::new()
             .base_uri("http://localhost:4321")
@@ -103,9 +128,11 @@ pub async fn test_app_with_login(
         .app_data(Data::clone(&openai_client))
         .app_data(Data::clone(&supabase_pool))
         .app_data(Data::clone(&github_gist_client))
+        .app_data(Data::clone(&playground_storage))
         .app_data(Data::clone(&github_flags_client))
         .app_data(Data::clone(&pool))
         .app_data(Data::clone(&client))
+        .app_data(Data::clone(&mailer))
         .app_data(Data::clone(&basket_client))
         .app_data(Data::clone(&login_manager));
     Ok(add_services(app))
@@ -123,7 +150,16 @@ pub async fn test_app_only_search() -> App<
     let elastic_transport = Transport::single_node("http://localhost:4321").unwrap();
     let elastic_client = Elasticsearch::new(elastic_transport);
 
-    let app = App::new().app_data(Data::new(elastic_client));
+    let openai_client = Data::new(None::<async_openai::Client<OpenAIConfig>>);
+    let supabase_pool = Data::new(None::<SupaPool>);
+
+    let pool = Data::new(get_pool());
+
+    let app = App::new()
+        .app_data(Data::new(elastic_client))
+        .app_data(openai_client)
+        .app_data(supabase_pool)
+        .app_data(pool);
     add_services(app)
 }
 